@@ -0,0 +1,9 @@
+-- Add migration script here
+alter table templates
+    add column if not exists namespace varchar not null default '';
+
+alter table templates
+    drop constraint if exists templates_name_key;
+
+alter table templates
+    add constraint templates_namespace_name_key unique (namespace, name);
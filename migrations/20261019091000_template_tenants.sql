@@ -0,0 +1,9 @@
+-- Add migration script here
+alter table templates
+    add column if not exists tenant varchar not null default 'global';
+
+alter table templates
+    drop constraint if exists templates_namespace_name_key;
+
+alter table templates
+    add constraint templates_tenant_namespace_name_key unique (tenant, namespace, name);
@@ -0,0 +1,25 @@
+-- Add migration script here
+create table if not exists template_overrides
+(
+    id          bigint generated by default as identity,
+    scope       varchar     not null default '',
+    body        varchar,
+    template_id bigint      not null references templates (id) on delete cascade,
+    created_at  timestamptz not null default current_timestamp,
+    updated_at  timestamptz not null default current_timestamp,
+    unique (template_id, scope)
+);
+
+alter table template_overrides
+    add constraint template_overrides_id_pk primary key (id);
+
+create table if not exists override_inputs
+(
+    id            bigint generated by default as identity,
+    name          varchar not null default '',
+    default_value varchar not null default '',
+    override_id   bigint  not null references template_overrides (id) on delete cascade
+);
+
+alter table override_inputs
+    add constraint override_inputs_id_pk primary key (id);
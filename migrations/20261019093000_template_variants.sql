@@ -0,0 +1,14 @@
+-- Add migration script here
+create table if not exists template_variants
+(
+    id          bigint generated by default as identity,
+    locale      varchar     not null default '',
+    body        varchar     not null default '',
+    template_id bigint      not null references templates (id) on delete cascade,
+    created_at  timestamptz not null default current_timestamp,
+    updated_at  timestamptz not null default current_timestamp,
+    unique (template_id, locale)
+);
+
+alter table template_variants
+    add constraint template_variants_id_pk primary key (id);
@@ -0,0 +1,15 @@
+-- Add migration script here
+create table if not exists messages
+(
+    id         bigint generated by default as identity,
+    tenant     varchar     not null default 'global',
+    key        varchar     not null default '',
+    locale     varchar     not null default '',
+    value      varchar     not null default '',
+    created_at timestamptz not null default current_timestamp,
+    updated_at timestamptz not null default current_timestamp,
+    unique (tenant, key, locale)
+);
+
+alter table messages
+    add constraint messages_id_pk primary key (id);
@@ -0,0 +1,3 @@
+-- Add migration script here
+alter table templates
+    add column if not exists cloned_from bigint references templates (id) on delete set null;
@@ -0,0 +1,10 @@
+-- Add migration script here
+alter table templates
+    add column if not exists deleted_at timestamptz;
+
+alter table templates
+    drop constraint if exists templates_tenant_namespace_name_key;
+
+create unique index if not exists templates_tenant_namespace_name_key
+    on templates (tenant, namespace, name)
+    where deleted_at is null;
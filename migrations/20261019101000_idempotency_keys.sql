@@ -0,0 +1,14 @@
+-- Add migration script here
+create table if not exists idempotency_keys
+(
+    id         bigint generated by default as identity,
+    tenant     varchar     not null default 'global',
+    key        varchar     not null default '',
+    request    bytea       not null,
+    response   bytea       not null,
+    created_at timestamptz not null default current_timestamp,
+    unique (tenant, key)
+);
+
+alter table idempotency_keys
+    add constraint idempotency_keys_id_pk primary key (id);
@@ -0,0 +1,3 @@
+-- Add migration script here
+alter table templates
+    add column if not exists engine varchar not null default 'handlebars';
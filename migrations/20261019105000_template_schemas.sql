@@ -0,0 +1,12 @@
+-- Add migration script here
+create table if not exists template_schemas
+(
+    id          bigint generated by default as identity,
+    json_schema varchar     not null,
+    template_id bigint      not null unique references templates (id) on delete cascade,
+    created_at  timestamptz not null default current_timestamp,
+    updated_at  timestamptz not null default current_timestamp
+);
+
+alter table template_schemas
+    add constraint template_schemas_id_pk primary key (id);
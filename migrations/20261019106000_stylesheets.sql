@@ -0,0 +1,12 @@
+-- Add migration script here
+create table if not exists stylesheets
+(
+    id         bigint generated by default as identity,
+    tenant     varchar     not null unique,
+    css        varchar     not null,
+    created_at timestamptz not null default current_timestamp,
+    updated_at timestamptz not null default current_timestamp
+);
+
+alter table stylesheets
+    add constraint stylesheets_id_pk primary key (id);
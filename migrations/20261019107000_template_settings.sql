@@ -0,0 +1,12 @@
+-- Add migration script here
+create table if not exists template_settings
+(
+    id          bigint generated by default as identity,
+    settings    varchar     not null,
+    template_id bigint      not null unique references templates (id) on delete cascade,
+    created_at  timestamptz not null default current_timestamp,
+    updated_at  timestamptz not null default current_timestamp
+);
+
+alter table template_settings
+    add constraint template_settings_id_pk primary key (id);
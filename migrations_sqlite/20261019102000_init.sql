@@ -0,0 +1,79 @@
+-- Add migration script here
+create table if not exists templates
+(
+    id          integer primary key autoincrement,
+    tenant      text not null default 'global',
+    namespace   text not null default '',
+    name        text not null default '',
+    description text not null default '',
+    body        text not null default '',
+    cloned_from integer references templates (id) on delete set null,
+    created_at  text not null,
+    updated_at  text not null,
+    deleted_at  text
+);
+
+create unique index if not exists templates_tenant_namespace_name_key
+    on templates (tenant, namespace, name)
+    where deleted_at is null;
+
+create table if not exists inputs
+(
+    id            integer primary key autoincrement,
+    name          text    not null default '',
+    default_value text    not null default '',
+    template_id   integer not null references templates (id) on delete cascade
+);
+
+create table if not exists template_overrides
+(
+    id          integer primary key autoincrement,
+    scope       text    not null default '',
+    body        text,
+    template_id integer not null references templates (id) on delete cascade,
+    created_at  text    not null,
+    updated_at  text    not null,
+    unique (template_id, scope)
+);
+
+create table if not exists override_inputs
+(
+    id            integer primary key autoincrement,
+    name          text    not null default '',
+    default_value text    not null default '',
+    override_id   integer not null references template_overrides (id) on delete cascade
+);
+
+create table if not exists template_variants
+(
+    id          integer primary key autoincrement,
+    locale      text    not null default '',
+    body        text    not null default '',
+    template_id integer not null references templates (id) on delete cascade,
+    created_at  text    not null,
+    updated_at  text    not null,
+    unique (template_id, locale)
+);
+
+create table if not exists messages
+(
+    id         integer primary key autoincrement,
+    tenant     text not null default 'global',
+    key        text not null default '',
+    locale     text not null default '',
+    value      text not null default '',
+    created_at text not null,
+    updated_at text not null,
+    unique (tenant, key, locale)
+);
+
+create table if not exists idempotency_keys
+(
+    id         integer primary key autoincrement,
+    tenant     text not null default 'global',
+    key        text not null default '',
+    request    blob not null,
+    response   blob not null,
+    created_at text not null,
+    unique (tenant, key)
+);
@@ -0,0 +1,3 @@
+-- Add migration script here
+alter table templates
+    add column engine text not null default 'handlebars';
@@ -0,0 +1,3 @@
+-- Add migration script here
+alter table templates
+    add column content_type text not null default 'html';
@@ -0,0 +1,9 @@
+-- Add migration script here
+create table if not exists template_schemas
+(
+    id          integer primary key autoincrement,
+    json_schema text    not null,
+    template_id integer not null unique references templates (id) on delete cascade,
+    created_at  text    not null,
+    updated_at  text    not null
+);
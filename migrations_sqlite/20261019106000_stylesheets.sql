@@ -0,0 +1,9 @@
+-- Add migration script here
+create table if not exists stylesheets
+(
+    id         integer primary key autoincrement,
+    tenant     text    not null unique,
+    css        text    not null,
+    created_at text    not null,
+    updated_at text    not null
+);
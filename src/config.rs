@@ -1,9 +1,19 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use templating::{
+    manifest::ManifestSyncMode, repository::template::GLOBAL_TENANT,
+    service::bundle::ConflictStrategy, storage::StorageBackend,
+};
 
 #[derive(Parser)]
 pub struct AppConfig {
+    #[arg(long, env, value_enum, default_value_t = StorageBackend::Postgres)]
+    pub storage_backend: StorageBackend,
+    /// Required by the database backends.
     #[arg(long, env)]
-    pub database_url: String,
+    pub database_url: Option<String>,
     #[arg(long, env)]
     pub rust_log: String,
     #[arg(long, env)]
@@ -12,6 +22,70 @@ pub struct AppConfig {
     pub service_port: u32,
     #[arg(long, env)]
     pub run_migrations: bool,
+    /// Syncs the manifest directory on startup.
     #[arg(long, env)]
     pub seed: bool,
+    /// Directory the filesystem backend stores templates in, required by it.
+    #[arg(long, env)]
+    pub template_dir: Option<PathBuf>,
+    /// Reloads the template directory whenever a file in it changes.
+    #[arg(long, env)]
+    pub template_dir_hot_reload: bool,
+    #[arg(long, env, default_value = "manifest")]
+    pub manifest_dir: PathBuf,
+    #[arg(long, env, value_enum, default_value_t = ManifestSyncMode::CreateOnly)]
+    pub manifest_sync_mode: ManifestSyncMode,
+    #[arg(long, env, default_value = "en")]
+    pub default_locale: String,
+    #[arg(long, env, value_delimiter = ',', default_value = "en")]
+    pub supported_locales: Vec<String>,
+    #[arg(long, env, default_value_t = 30)]
+    pub trash_retention_days: u64,
+    #[arg(long, env, default_value_t = 3600, value_parser = clap::value_parser!(u64).range(1..))]
+    pub trash_purge_interval_seconds: u64,
+    #[arg(long, env, default_value_t = 24)]
+    pub idempotency_key_retention_hours: u64,
+    #[arg(long, env, default_value_t = 5000)]
+    pub render_timeout_ms: u64,
+    #[arg(long, env, default_value_t = 1048576)]
+    pub render_max_output_bytes: usize,
+    /// How deeply template partials and macros may call each other.
+    #[arg(long, env, default_value_t = 16)]
+    pub render_max_partial_depth: usize,
+    /// Redirect that tracked links are sent through. Templates can only track clicks
+    /// when it is set.
+    #[arg(long, env, requires = "click_tracking_key")]
+    pub click_tracking_url: Option<String>,
+    /// Key the tokens of tracked links are signed with, shared with the redirect.
+    #[arg(long, env)]
+    pub click_tracking_key: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<AppCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum AppCommand {
+    /// Syncs the manifest directory into the database and exits.
+    SyncManifest,
+    /// Writes a bundle of the tenant's templates, all of them unless names are given.
+    Export {
+        #[arg(long)]
+        output: PathBuf,
+        #[arg(long, default_value = GLOBAL_TENANT)]
+        tenant: String,
+        #[arg(long = "name")]
+        names: Vec<String>,
+    },
+    /// Imports a bundle into the tenant and prints the changes made.
+    Import {
+        #[arg(long)]
+        input: PathBuf,
+        #[arg(long, default_value = GLOBAL_TENANT)]
+        tenant: String,
+        #[arg(long, value_enum, default_value_t = ConflictStrategy::Skip)]
+        strategy: ConflictStrategy,
+        /// Prints the changes the import would make without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
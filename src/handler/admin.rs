@@ -0,0 +1,706 @@
+use madtofan_microservice_common::{
+    errors::{ServiceError, ServiceResult},
+    templating as shared,
+};
+use tonic::{Request, Response, Status};
+
+use templating::service::{
+    bundle::{self, ImportOptions, ImportReport},
+    catalog::CatalogMessage,
+    content::parse_content_type,
+    css::Stylesheet as TenantStylesheet,
+    engine::{self, parse_engine},
+    locale,
+    revision::TemplateRevision,
+    settings::parse_settings,
+    templating::{
+        DynTemplatingServiceTrait, EnsureOutcome as ServiceEnsureOutcome, ListedTemplates,
+        TemplateDefinition,
+    },
+};
+
+use super::{
+    metadata::{
+        get_metadata_value, get_tenant, set_revision, set_revisions, EXPECTED_REVISION_METADATA_KEY,
+    },
+    templating_admin::{
+        templating_admin_server::TemplatingAdmin, CloneTemplateRequest, ConflictStrategy,
+        EnsureOutcome, EnsureTemplateRequest, EnsureTemplateResponse, ExportTemplatesRequest,
+        ExportTemplatesResponse, FieldChange, GetStylesheetRequest, GetTemplateRequest,
+        GetTemplateSettingsRequest, ImportAction, ImportChange, ImportTemplatesRequest,
+        ImportTemplatesResponse, ListMessagesRequest, ListMessagesResponse,
+        ListTemplateEnginesResponse, ListTemplateLocalesResponse, ListTemplatesRequest,
+        ListTemplatesResponse, ListTrashedTemplatesRequest, Message, MoveTemplateRequest,
+        RemoveJsonSchemaRequest, RemoveMessageRequest, RemoveOverrideRequest,
+        RemoveStylesheetRequest, RemoveVariantRequest, RenameTemplateRequest,
+        RestoreTemplateRequest, SetJsonSchemaRequest, SetOverrideRequest, SetStylesheetRequest,
+        SetTemplateSettingsRequest, SetVariantRequest, Stylesheet, TemplateEngineListing,
+        TemplateInput, TemplateLocales, TemplateResponse, TemplateSettings, UpdateTemplateRequest,
+    },
+};
+
+pub struct AdminRequestHandler {
+    templating_service: DynTemplatingServiceTrait,
+}
+
+impl AdminRequestHandler {
+    pub fn new(templating_service: DynTemplatingServiceTrait) -> Self {
+        Self { templating_service }
+    }
+}
+
+impl From<TemplateInput> for shared::TemplateInput {
+    fn from(input: TemplateInput) -> Self {
+        Self {
+            name: input.name,
+            default_value: input.default_value,
+        }
+    }
+}
+
+impl From<shared::TemplateInput> for TemplateInput {
+    fn from(input: shared::TemplateInput) -> Self {
+        Self {
+            name: input.name,
+            default_value: input.default_value,
+        }
+    }
+}
+
+impl From<shared::TemplateResponse> for TemplateResponse {
+    fn from(template: shared::TemplateResponse) -> Self {
+        Self {
+            name: template.name,
+            description: template.description,
+            template_inputs: template
+                .template_inputs
+                .into_iter()
+                .map(TemplateInput::from)
+                .collect(),
+        }
+    }
+}
+
+/// Answers with the template, sending its revision as the `etag` metadata.
+fn revision_response(template: TemplateRevision) -> ServiceResult<Response<TemplateResponse>> {
+    let mut response = Response::new(template.template.into());
+    set_revision(&mut response, &template.revision)?;
+
+    Ok(response)
+}
+
+fn listed_response(
+    listed_templates: ListedTemplates,
+) -> ServiceResult<Response<ListTemplatesResponse>> {
+    let revisions = listed_templates
+        .templates
+        .iter()
+        .map(|template| template.revision.clone())
+        .collect::<Vec<String>>();
+    let mut response = Response::new(ListTemplatesResponse {
+        templates: listed_templates
+            .templates
+            .into_iter()
+            .map(|template| template.template.into())
+            .collect(),
+        count: listed_templates.count,
+    });
+    set_revisions(&mut response, &revisions)?;
+
+    Ok(response)
+}
+
+impl From<ServiceEnsureOutcome> for EnsureOutcome {
+    fn from(outcome: ServiceEnsureOutcome) -> Self {
+        match outcome {
+            ServiceEnsureOutcome::Created => Self::Created,
+            ServiceEnsureOutcome::Updated => Self::Updated,
+            ServiceEnsureOutcome::Unchanged => Self::Unchanged,
+        }
+    }
+}
+
+impl From<ConflictStrategy> for bundle::ConflictStrategy {
+    fn from(strategy: ConflictStrategy) -> Self {
+        match strategy {
+            ConflictStrategy::Skip => Self::Skip,
+            ConflictStrategy::Overwrite => Self::Overwrite,
+            ConflictStrategy::Rename => Self::Rename,
+        }
+    }
+}
+
+impl From<bundle::FieldChange> for FieldChange {
+    fn from(change: bundle::FieldChange) -> Self {
+        Self {
+            field: change.field,
+            existing: change.existing.to_string(),
+            imported: change.imported.to_string(),
+        }
+    }
+}
+
+impl From<bundle::ImportChange> for ImportChange {
+    fn from(change: bundle::ImportChange) -> Self {
+        let (action, renamed_to, changes) = match change.action {
+            bundle::ImportAction::Create => (ImportAction::Create, String::new(), vec![]),
+            bundle::ImportAction::Unchanged => (ImportAction::Unchanged, String::new(), vec![]),
+            bundle::ImportAction::Skip => (ImportAction::Skip, String::new(), vec![]),
+            bundle::ImportAction::Overwrite { changes } => (
+                ImportAction::Overwrite,
+                String::new(),
+                changes.into_iter().map(FieldChange::from).collect(),
+            ),
+            bundle::ImportAction::Rename { name } => (ImportAction::Rename, name, vec![]),
+        };
+
+        Self {
+            name: change.name,
+            action: action.into(),
+            renamed_to,
+            changes,
+        }
+    }
+}
+
+impl From<ImportReport> for ImportTemplatesResponse {
+    fn from(report: ImportReport) -> Self {
+        Self {
+            dry_run: report.dry_run,
+            changes: report.changes.into_iter().map(ImportChange::from).collect(),
+        }
+    }
+}
+
+impl From<locale::TemplateLocales> for TemplateLocales {
+    fn from(template: locale::TemplateLocales) -> Self {
+        Self {
+            name: template.name,
+            locales: template.locales,
+            missing_locales: template.missing_locales,
+        }
+    }
+}
+
+impl From<engine::TemplateEngineListing> for TemplateEngineListing {
+    fn from(template: engine::TemplateEngineListing) -> Self {
+        Self {
+            name: template.name,
+            engine: template.engine.to_string(),
+            content_type: template.content_type.to_string(),
+        }
+    }
+}
+
+impl From<CatalogMessage> for Message {
+    fn from(message: CatalogMessage) -> Self {
+        Self {
+            key: message.key,
+            locale: message.locale,
+            value: message.value,
+        }
+    }
+}
+
+impl From<TenantStylesheet> for Stylesheet {
+    fn from(stylesheet: TenantStylesheet) -> Self {
+        Self {
+            tenant: stylesheet.tenant,
+            css: stylesheet.css,
+        }
+    }
+}
+
+impl From<&ListTemplatesRequest> for shared::ListTemplateRequest {
+    fn from(request: &ListTemplatesRequest) -> Self {
+        Self {
+            offset: request.offset,
+            limit: request.limit,
+        }
+    }
+}
+
+/// The definition of a template written by the admin requests, with the default
+/// engine and content type when they are left out.
+fn into_definition(
+    name: String,
+    description: String,
+    body: String,
+    inputs: Vec<TemplateInput>,
+    engine: Option<String>,
+    content_type: Option<String>,
+) -> ServiceResult<TemplateDefinition> {
+    Ok(TemplateDefinition {
+        name,
+        description,
+        body,
+        engine: engine
+            .map(|engine| parse_engine(&engine))
+            .transpose()?
+            .unwrap_or_default(),
+        content_type: content_type
+            .map(|content_type| parse_content_type(&content_type))
+            .transpose()?
+            .unwrap_or_default(),
+        inputs: into_shared_inputs(inputs),
+    })
+}
+
+fn into_shared_inputs(inputs: Vec<TemplateInput>) -> Vec<shared::TemplateInput> {
+    inputs
+        .into_iter()
+        .map(shared::TemplateInput::from)
+        .collect()
+}
+
+#[tonic::async_trait]
+impl TemplatingAdmin for AdminRequestHandler {
+    async fn set_override(
+        &self,
+        request: Request<SetOverrideRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let template = self
+            .templating_service
+            .set_override(
+                tenant,
+                req.name,
+                req.scope,
+                req.body,
+                into_shared_inputs(req.template_inputs),
+            )
+            .await?;
+
+        Ok(Response::new(template.into()))
+    }
+
+    async fn remove_override(
+        &self,
+        request: Request<RemoveOverrideRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let template = self
+            .templating_service
+            .remove_override(tenant, req.name, req.scope)
+            .await?;
+
+        Ok(Response::new(template.into()))
+    }
+
+    async fn set_variant(
+        &self,
+        request: Request<SetVariantRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let template = self
+            .templating_service
+            .set_variant(tenant, req.name, req.locale, req.body)
+            .await?;
+
+        Ok(Response::new(template.into()))
+    }
+
+    async fn remove_variant(
+        &self,
+        request: Request<RemoveVariantRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let template = self
+            .templating_service
+            .remove_variant(tenant, req.name, req.locale)
+            .await?;
+
+        Ok(Response::new(template.into()))
+    }
+
+    async fn list_template_locales(
+        &self,
+        request: Request<ListTemplatesRequest>,
+    ) -> Result<Response<ListTemplateLocalesResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let templates = self
+            .templating_service
+            .list_template_locales(tenant, (&req).into(), req.namespace)
+            .await?;
+
+        Ok(Response::new(ListTemplateLocalesResponse {
+            templates: templates.into_iter().map(TemplateLocales::from).collect(),
+        }))
+    }
+
+    async fn list_messages(
+        &self,
+        request: Request<ListMessagesRequest>,
+    ) -> Result<Response<ListMessagesResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let catalog_messages = self
+            .templating_service
+            .list_messages(tenant, req.offset, req.limit)
+            .await?;
+
+        Ok(Response::new(ListMessagesResponse {
+            messages: catalog_messages
+                .messages
+                .into_iter()
+                .map(Message::from)
+                .collect(),
+            count: catalog_messages.count,
+        }))
+    }
+
+    async fn set_message(&self, request: Request<Message>) -> Result<Response<Message>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let message = self
+            .templating_service
+            .set_message(tenant, req.key, req.locale, req.value)
+            .await?;
+
+        Ok(Response::new(message.into()))
+    }
+
+    async fn remove_message(
+        &self,
+        request: Request<RemoveMessageRequest>,
+    ) -> Result<Response<Message>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let message = self
+            .templating_service
+            .remove_message(tenant, req.key, req.locale)
+            .await?;
+
+        Ok(Response::new(message.into()))
+    }
+
+    async fn move_template(
+        &self,
+        request: Request<MoveTemplateRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let template = self
+            .templating_service
+            .move_template(tenant, req.name, req.namespace)
+            .await?;
+
+        Ok(revision_response(template)?)
+    }
+
+    async fn rename_template(
+        &self,
+        request: Request<RenameTemplateRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let template = self
+            .templating_service
+            .rename_template(tenant, req.name, req.new_name)
+            .await?;
+
+        Ok(revision_response(template)?)
+    }
+
+    async fn clone_template(
+        &self,
+        request: Request<CloneTemplateRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let template = self
+            .templating_service
+            .clone_template(tenant, req.name, req.new_name)
+            .await?;
+
+        Ok(revision_response(template)?)
+    }
+
+    async fn list_trashed_templates(
+        &self,
+        request: Request<ListTrashedTemplatesRequest>,
+    ) -> Result<Response<ListTemplatesResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let trashed_templates = self
+            .templating_service
+            .list_trashed_templates(
+                tenant,
+                shared::ListTemplateRequest {
+                    offset: req.offset,
+                    limit: req.limit,
+                },
+            )
+            .await?;
+
+        Ok(listed_response(trashed_templates)?)
+    }
+
+    async fn restore_template(
+        &self,
+        request: Request<RestoreTemplateRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let template = self
+            .templating_service
+            .restore_template(tenant, req.name)
+            .await?;
+
+        Ok(revision_response(template)?)
+    }
+
+    async fn get_template(
+        &self,
+        request: Request<GetTemplateRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let template = self
+            .templating_service
+            .get_template(tenant, req.name)
+            .await?;
+
+        Ok(revision_response(template)?)
+    }
+
+    async fn update_template(
+        &self,
+        request: Request<UpdateTemplateRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let expected_revision = get_metadata_value(&request, EXPECTED_REVISION_METADATA_KEY)?;
+        let req = request.into_inner();
+        let definition = into_definition(
+            req.name,
+            req.description,
+            req.body,
+            req.template_inputs,
+            req.engine,
+            req.content_type,
+        )?;
+
+        let template = self
+            .templating_service
+            .update_template(tenant, definition, expected_revision)
+            .await?;
+
+        Ok(revision_response(template)?)
+    }
+
+    async fn ensure_template(
+        &self,
+        request: Request<EnsureTemplateRequest>,
+    ) -> Result<Response<EnsureTemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+        let definition = into_definition(
+            req.name,
+            req.description,
+            req.body,
+            req.template_inputs,
+            req.engine,
+            req.content_type,
+        )?;
+
+        let ensured_template = self
+            .templating_service
+            .ensure_template(tenant, definition)
+            .await?;
+
+        let mut response = Response::new(EnsureTemplateResponse {
+            template: Some(ensured_template.template.template.into()),
+            outcome: EnsureOutcome::from(ensured_template.outcome).into(),
+        });
+        set_revision(&mut response, &ensured_template.template.revision)?;
+
+        Ok(response)
+    }
+
+    async fn export_templates(
+        &self,
+        request: Request<ExportTemplatesRequest>,
+    ) -> Result<Response<ExportTemplatesResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let bundle = self
+            .templating_service
+            .export_templates(tenant, req.names)
+            .await?;
+        let bundle = serde_json::to_string(&bundle).map_err(|_| {
+            ServiceError::InternalServerErrorWithContext(
+                "template bundle could not be serialised".to_string(),
+            )
+        })?;
+
+        Ok(Response::new(ExportTemplatesResponse { bundle }))
+    }
+
+    async fn import_templates(
+        &self,
+        request: Request<ImportTemplatesRequest>,
+    ) -> Result<Response<ImportTemplatesResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+        let strategy = req.strategy().into();
+        let bundle = serde_json::from_str(&req.bundle).map_err(|err| {
+            ServiceError::BadRequest(format!("template bundle is not valid: {}", err))
+        })?;
+
+        let report = self
+            .templating_service
+            .import_templates(
+                tenant,
+                bundle,
+                ImportOptions {
+                    strategy,
+                    dry_run: req.dry_run,
+                },
+            )
+            .await?;
+
+        Ok(Response::new(report.into()))
+    }
+
+    async fn list_template_engines(
+        &self,
+        request: Request<ListTemplatesRequest>,
+    ) -> Result<Response<ListTemplateEnginesResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let templates = self
+            .templating_service
+            .list_template_engines(tenant, (&req).into(), req.namespace)
+            .await?;
+
+        Ok(Response::new(ListTemplateEnginesResponse {
+            templates: templates
+                .into_iter()
+                .map(TemplateEngineListing::from)
+                .collect(),
+        }))
+    }
+
+    async fn get_stylesheet(
+        &self,
+        request: Request<GetStylesheetRequest>,
+    ) -> Result<Response<Stylesheet>, Status> {
+        let tenant = get_tenant(&request)?;
+
+        let stylesheet = self.templating_service.get_stylesheet(tenant).await?;
+
+        Ok(Response::new(stylesheet.into()))
+    }
+
+    async fn set_stylesheet(
+        &self,
+        request: Request<SetStylesheetRequest>,
+    ) -> Result<Response<Stylesheet>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let stylesheet = self
+            .templating_service
+            .set_stylesheet(tenant, req.css)
+            .await?;
+
+        Ok(Response::new(stylesheet.into()))
+    }
+
+    async fn remove_stylesheet(
+        &self,
+        request: Request<RemoveStylesheetRequest>,
+    ) -> Result<Response<Stylesheet>, Status> {
+        let tenant = get_tenant(&request)?;
+
+        let stylesheet = self.templating_service.remove_stylesheet(tenant).await?;
+
+        Ok(Response::new(stylesheet.into()))
+    }
+
+    async fn set_json_schema(
+        &self,
+        request: Request<SetJsonSchemaRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let template = self
+            .templating_service
+            .set_json_schema(tenant, req.name, req.json_schema)
+            .await?;
+
+        Ok(Response::new(template.into()))
+    }
+
+    async fn remove_json_schema(
+        &self,
+        request: Request<RemoveJsonSchemaRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let template = self
+            .templating_service
+            .remove_json_schema(tenant, req.name)
+            .await?;
+
+        Ok(Response::new(template.into()))
+    }
+
+    async fn get_template_settings(
+        &self,
+        request: Request<GetTemplateSettingsRequest>,
+    ) -> Result<Response<TemplateSettings>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let settings = self
+            .templating_service
+            .get_template_settings(tenant, req.name)
+            .await?;
+        let settings = serde_json::to_string(&settings).map_err(|_| {
+            ServiceError::InternalServerErrorWithContext(
+                "template settings could not be serialised".to_string(),
+            )
+        })?;
+
+        Ok(Response::new(TemplateSettings { settings }))
+    }
+
+    async fn set_template_settings(
+        &self,
+        request: Request<SetTemplateSettingsRequest>,
+    ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let req = request.into_inner();
+
+        let template = self
+            .templating_service
+            .set_template_settings(tenant, req.name, parse_settings(&req.settings)?)
+            .await?;
+
+        Ok(Response::new(template.into()))
+    }
+}
@@ -0,0 +1,118 @@
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+use tonic::{
+    metadata::{Ascii, MetadataKey, MetadataValue},
+    Request, Response,
+};
+
+use templating::{repository::template::GLOBAL_TENANT, service::sanitize::SanitizedInput};
+
+pub const TENANT_METADATA_KEY: &str = "x-tenant-id";
+pub const NAMESPACE_METADATA_KEY: &str = "x-template-namespace";
+pub const SCOPE_METADATA_KEY: &str = "x-template-scope";
+pub const LOCALE_METADATA_KEY: &str = "x-template-locale";
+pub const ENGINE_METADATA_KEY: &str = "x-template-engine";
+pub const CONTENT_TYPE_METADATA_KEY: &str = "x-template-content-type";
+pub const OUTPUT_METADATA_KEY: &str = "x-template-output";
+pub const LAYOUT_METADATA_KEY: &str = "x-template-layout";
+pub const INLINE_CSS_METADATA_KEY: &str = "x-template-inline-css";
+pub const SANITIZED_INPUTS_METADATA_KEY: &str = "x-template-sanitized-inputs-bin";
+pub const REVISION_METADATA_KEY: &str = "etag";
+pub const REVISIONS_METADATA_KEY: &str = "x-template-revisions";
+pub const EXPECTED_REVISION_METADATA_KEY: &str = "if-match";
+pub const IDEMPOTENCY_KEY_METADATA_KEY: &str = "idempotency-key";
+
+pub fn get_metadata_value<T>(request: &Request<T>, key: &str) -> ServiceResult<Option<String>> {
+    request
+        .metadata()
+        .get(key)
+        .map(|value| value.to_str())
+        .transpose()
+        .map_err(|_| ServiceError::BadRequest(format!("{} metadata is not valid ASCII", key)))
+        .map(|value| value.map(String::from))
+}
+
+/// Requests without a tenant are served from the global tenant.
+pub fn get_tenant<T>(request: &Request<T>) -> ServiceResult<String> {
+    match get_metadata_value(request, TENANT_METADATA_KEY)? {
+        Some(tenant) if tenant.trim().is_empty() => Err(ServiceError::BadRequest(
+            "tenant metadata cannot be empty".to_string(),
+        )),
+        Some(tenant) => Ok(tenant),
+        None => Ok(GLOBAL_TENANT.to_string()),
+    }
+}
+
+/// Sends the revision of the template the response was written from, to be given
+/// back as the expected revision of the next write.
+pub fn set_revision<T>(response: &mut Response<T>, revision: &str) -> ServiceResult<()> {
+    let value: MetadataValue<Ascii> = revision.parse().map_err(|_| {
+        ServiceError::InternalServerErrorWithContext("revision is not valid metadata".to_string())
+    })?;
+    response.metadata_mut().insert(REVISION_METADATA_KEY, value);
+
+    Ok(())
+}
+
+/// Sends the revision of every listed template, comma separated in the order of the
+/// listing.
+pub fn set_revisions<T>(response: &mut Response<T>, revisions: &[String]) -> ServiceResult<()> {
+    let value: MetadataValue<Ascii> = revisions.join(",").parse().map_err(|_| {
+        ServiceError::InternalServerErrorWithContext("revisions are not valid metadata".to_string())
+    })?;
+    response
+        .metadata_mut()
+        .insert(REVISIONS_METADATA_KEY, value);
+
+    Ok(())
+}
+
+/// Passes the `List-Unsubscribe` headers of a composed marketing template on as
+/// metadata of the same names, lower cased.
+pub fn set_unsubscribe_headers<T>(
+    response: &mut Response<T>,
+    headers: &[(&'static str, String)],
+) -> ServiceResult<()> {
+    for (name, value) in headers {
+        let key = MetadataKey::<Ascii>::from_bytes(name.to_ascii_lowercase().as_bytes()).map_err(
+            |_| {
+                ServiceError::InternalServerErrorWithContext(format!(
+                    "{} is not a valid metadata key",
+                    name
+                ))
+            },
+        )?;
+        let value: MetadataValue<Ascii> = value.parse().map_err(|_| {
+            ServiceError::InternalServerErrorWithContext(format!(
+                "{} header is not valid metadata",
+                name
+            ))
+        })?;
+        response.metadata_mut().insert(key, value);
+    }
+
+    Ok(())
+}
+
+/// Reports what the sanitiser removed from rich HTML inputs as a JSON array in binary
+/// metadata, since input names are not limited to ASCII. The metadata is left out when
+/// nothing was removed.
+pub fn set_sanitized_inputs<T>(
+    response: &mut Response<T>,
+    sanitized_inputs: &[SanitizedInput],
+) -> ServiceResult<()> {
+    if sanitized_inputs.is_empty() {
+        return Ok(());
+    }
+
+    let value = serde_json::to_vec(sanitized_inputs).map_err(|_| {
+        ServiceError::InternalServerErrorWithContext(
+            "sanitized inputs could not be serialised".to_string(),
+        )
+    })?;
+    response.metadata_mut().insert_bin(
+        SANITIZED_INPUTS_METADATA_KEY,
+        MetadataValue::from_bytes(&value),
+    );
+
+    Ok(())
+}
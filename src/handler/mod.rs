@@ -1,5 +1,11 @@
+pub mod admin;
+pub mod metadata;
 pub mod templating;
 
+pub mod templating_admin {
+    tonic::include_proto!("templating_admin");
+}
+
 #[cfg(test)]
 pub mod test {
     use std::sync::Arc;
@@ -11,37 +17,66 @@ pub mod test {
     use sqlx::PgPool;
     use tonic::Request;
 
-    use crate::{
+    use templating::{
         repository::{
-            input::{DynInputRepositoryTrait, InputRepository},
-            template::{DynTemplateRepositoryTrait, TemplateRepository},
+            template::{DynTemplateRepositoryTrait, TemplateFields, GLOBAL_TENANT},
+            template_override::DynTemplateOverrideRepositoryTrait,
         },
-        service::templating::{DynTemplatingServiceTrait, TemplatingService},
+        service::{
+            idempotency::IdempotencyService,
+            locale::LocaleSettings,
+            templating::{DynTemplatingServiceTrait, TemplatingService},
+        },
+        storage::Repositories,
     };
 
-    use super::templating::RequestHandler;
+    use super::{
+        admin::AdminRequestHandler,
+        metadata::{
+            ENGINE_METADATA_KEY, EXPECTED_REVISION_METADATA_KEY, IDEMPOTENCY_KEY_METADATA_KEY,
+            INLINE_CSS_METADATA_KEY, LOCALE_METADATA_KEY, NAMESPACE_METADATA_KEY,
+            REVISIONS_METADATA_KEY, REVISION_METADATA_KEY, SANITIZED_INPUTS_METADATA_KEY,
+            SCOPE_METADATA_KEY, TENANT_METADATA_KEY,
+        },
+        templating::RequestHandler,
+        templating_admin::{
+            templating_admin_server::TemplatingAdmin, CloneTemplateRequest, ConflictStrategy,
+            EnsureOutcome, EnsureTemplateRequest, ExportTemplatesRequest, GetStylesheetRequest,
+            GetTemplateRequest, GetTemplateSettingsRequest, ImportAction, ImportTemplatesRequest,
+            ListMessagesRequest, ListTemplatesRequest, ListTrashedTemplatesRequest, Message,
+            MoveTemplateRequest, RemoveJsonSchemaRequest, RemoveMessageRequest,
+            RemoveOverrideRequest, RemoveStylesheetRequest, RenameTemplateRequest,
+            RestoreTemplateRequest, SetJsonSchemaRequest, SetOverrideRequest, SetStylesheetRequest,
+            SetTemplateSettingsRequest, SetVariantRequest, TemplateInput as AdminTemplateInput,
+            UpdateTemplateRequest,
+        },
+    };
 
     struct AllTraits {
         handler: RequestHandler,
+        admin_handler: AdminRequestHandler,
         templates_repository: DynTemplateRepositoryTrait,
+        override_repository: DynTemplateOverrideRepositoryTrait,
     }
 
     fn initialize_handler(pool: PgPool) -> AllTraits {
-        let inputs_repository =
-            Arc::new(InputRepository::new(pool.clone())) as DynInputRepositoryTrait;
-        let templates_repository = Arc::new(TemplateRepository::new(
-            pool.clone(),
-            inputs_repository.clone(),
-        )) as DynTemplateRepositoryTrait;
+        let repositories = Repositories::postgres(pool);
         let templating_service = Arc::new(TemplatingService::new(
-            templates_repository.clone(),
-            inputs_repository,
+            &repositories,
+            LocaleSettings::new("en", &["en".to_string(), "ms".to_string()])
+                .expect("test locales are valid"),
         )) as DynTemplatingServiceTrait;
-        let handler = RequestHandler::new(templating_service.clone());
+        let handler = RequestHandler::new(
+            templating_service.clone(),
+            IdempotencyService::new(repositories.idempotency_key_repository),
+        );
+        let admin_handler = AdminRequestHandler::new(templating_service);
 
         AllTraits {
-            templates_repository,
+            templates_repository: repositories.template_repository,
+            override_repository: repositories.override_repository,
             handler,
+            admin_handler,
         }
     }
 
@@ -67,7 +102,7 @@ pub mod test {
 
         let created_template = all_traits
             .templates_repository
-            .get_template(template_name)
+            .get_template(GLOBAL_TENANT, "", template_name)
             .await?;
 
         assert_eq!(created_template.unwrap().description, template_description);
@@ -89,10 +124,16 @@ pub mod test {
         all_traits
             .templates_repository
             .add_template(
+                GLOBAL_TENANT,
+                "",
                 template_name,
-                template_description,
-                "template body {{input_name}}",
-                &input,
+                TemplateFields {
+                    description: template_description,
+                    body: "template body {{input_name}}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
             )
             .await?;
 
@@ -104,7 +145,7 @@ pub mod test {
 
         let get_template = all_traits
             .templates_repository
-            .get_template(template_name)
+            .get_template(GLOBAL_TENANT, "", template_name)
             .await?;
 
         assert!(get_template.is_none());
@@ -125,10 +166,16 @@ pub mod test {
         all_traits
             .templates_repository
             .add_template(
+                GLOBAL_TENANT,
+                "",
                 template_name,
-                "template_description",
-                "template body {input_name}",
-                &input,
+                TemplateFields {
+                    description: "template_description",
+                    body: "template body {input_name}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
             )
             .await?;
 
@@ -150,6 +197,54 @@ pub mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn list_namespace_templates_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let input = vec![TemplateInput {
+            name: "input_name".to_string(),
+            default_value: "default_value".to_string(),
+        }];
+
+        for namespace in ["auth", "billing"] {
+            all_traits
+                .templates_repository
+                .add_template(
+                    GLOBAL_TENANT,
+                    namespace,
+                    "welcome",
+                    TemplateFields {
+                        description: "template_description",
+                        body: "template body {{input_name}}",
+                        engine: "handlebars",
+                        content_type: "html",
+                        inputs: &input,
+                    },
+                )
+                .await?;
+        }
+
+        let mut request = Request::new(ListTemplateRequest {
+            offset: 0,
+            limit: 10,
+        });
+        request
+            .metadata_mut()
+            .insert(NAMESPACE_METADATA_KEY, "auth".parse()?);
+
+        let templates_list = all_traits
+            .handler
+            .list_templates(request)
+            .await?
+            .into_inner()
+            .templates;
+
+        assert_eq!(templates_list.len(), 1);
+        assert_eq!(templates_list.first().unwrap().name, "auth/welcome");
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn compose_test(pool: PgPool) -> anyhow::Result<()> {
         let all_traits = initialize_handler(pool);
@@ -164,10 +259,16 @@ pub mod test {
         all_traits
             .templates_repository
             .add_template(
+                GLOBAL_TENANT,
+                "",
                 template_name,
-                "template_description",
-                "composed text: {{input_name}}",
-                &input,
+                TemplateFields {
+                    description: "template_description",
+                    body: "composed text: {{input_name}}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
             )
             .await?;
 
@@ -206,4 +307,974 @@ pub mod test {
 
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn add_tenant_template_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let mut request = Request::new(AddTemplateRequest {
+            name: template_name.to_string(),
+            description: "template_description".to_string(),
+            body: "<p>{{input_name}}</p>".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        });
+        request
+            .metadata_mut()
+            .insert(TENANT_METADATA_KEY, "tenant".parse()?);
+
+        all_traits.handler.add_template(request).await?;
+
+        let tenant_template = all_traits
+            .templates_repository
+            .get_template("tenant", "", template_name)
+            .await?;
+        let global_template = all_traits
+            .templates_repository
+            .get_template(GLOBAL_TENANT, "", template_name)
+            .await?;
+
+        assert!(tenant_template.is_some());
+        assert!(global_template.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn compose_scope_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let input = vec![TemplateInput {
+            name: "input_name".to_string(),
+            default_value: "default_value".to_string(),
+        }];
+
+        let template = all_traits
+            .templates_repository
+            .add_template(
+                GLOBAL_TENANT,
+                "",
+                template_name,
+                TemplateFields {
+                    description: "template_description",
+                    body: "composed text: {{input_name}}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
+            )
+            .await?;
+        all_traits
+            .override_repository
+            .set_override(
+                template.id,
+                "product",
+                Some("product text: {{input_name}}"),
+                &[],
+            )
+            .await?;
+
+        let mut request = Request::new(ComposeRequest {
+            name: template_name.to_string(),
+            input_values: vec![],
+        });
+        request
+            .metadata_mut()
+            .insert(SCOPE_METADATA_KEY, "product".parse()?);
+
+        let composed_text = all_traits
+            .handler
+            .compose(request)
+            .await?
+            .into_inner()
+            .result;
+
+        assert_eq!(composed_text, "product text: default_value");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn set_override_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let request = Request::new(AddTemplateRequest {
+            name: template_name.to_string(),
+            description: "template_description".to_string(),
+            body: "composed text: {{input_name}}".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        });
+        all_traits.handler.add_template(request).await?;
+
+        let request = Request::new(SetOverrideRequest {
+            name: template_name.to_string(),
+            scope: "product".to_string(),
+            body: Some("product text: {{input_name}}".to_string()),
+            template_inputs: vec![],
+        });
+        all_traits.admin_handler.set_override(request).await?;
+
+        let compose_request = || {
+            let mut request = Request::new(ComposeRequest {
+                name: template_name.to_string(),
+                input_values: vec![],
+            });
+            request
+                .metadata_mut()
+                .insert(SCOPE_METADATA_KEY, "product".parse().unwrap());
+            request
+        };
+        let composed_text = all_traits
+            .handler
+            .compose(compose_request())
+            .await?
+            .into_inner()
+            .result;
+
+        assert_eq!(composed_text, "product text: default_value");
+
+        let request = Request::new(RemoveOverrideRequest {
+            name: template_name.to_string(),
+            scope: "product".to_string(),
+        });
+        all_traits.admin_handler.remove_override(request).await?;
+
+        let composed_text = all_traits
+            .handler
+            .compose(compose_request())
+            .await?
+            .into_inner()
+            .result;
+
+        assert_eq!(composed_text, "composed text: default_value");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn set_variant_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let request = Request::new(AddTemplateRequest {
+            name: template_name.to_string(),
+            description: "template_description".to_string(),
+            body: "Hello {{input_name}}".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        });
+        all_traits.handler.add_template(request).await?;
+
+        let request = Request::new(SetVariantRequest {
+            name: template_name.to_string(),
+            locale: "ms".to_string(),
+            body: "Helo {{input_name}}".to_string(),
+        });
+        all_traits.admin_handler.set_variant(request).await?;
+
+        let request = Request::new(ListTemplatesRequest {
+            offset: 0,
+            limit: 10,
+            namespace: None,
+        });
+        let listed_locales = all_traits
+            .admin_handler
+            .list_template_locales(request)
+            .await?
+            .into_inner()
+            .templates;
+
+        assert_eq!(listed_locales.len(), 1);
+        assert_eq!(
+            listed_locales[0].locales,
+            vec!["en".to_string(), "ms".to_string()]
+        );
+        assert!(listed_locales[0].missing_locales.is_empty());
+
+        let mut request = Request::new(ComposeRequest {
+            name: template_name.to_string(),
+            input_values: vec![],
+        });
+        request
+            .metadata_mut()
+            .insert(LOCALE_METADATA_KEY, "ms".parse()?);
+        let composed_text = all_traits
+            .handler
+            .compose(request)
+            .await?
+            .into_inner()
+            .result;
+
+        assert_eq!(composed_text, "Helo default_value");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn set_message_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let request = Request::new(AddTemplateRequest {
+            name: "template_name".to_string(),
+            description: "template_description".to_string(),
+            body: "{{t \"greeting\"}} {{input_name}}".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        });
+        all_traits.handler.add_template(request).await?;
+
+        let request = Request::new(Message {
+            key: "greeting".to_string(),
+            locale: "en".to_string(),
+            value: "Hello".to_string(),
+        });
+        all_traits.admin_handler.set_message(request).await?;
+
+        let request = Request::new(ListMessagesRequest {
+            offset: 0,
+            limit: 10,
+        });
+        let listed_messages = all_traits
+            .admin_handler
+            .list_messages(request)
+            .await?
+            .into_inner();
+
+        assert_eq!(listed_messages.count, 1);
+        assert_eq!(listed_messages.messages[0].value, "Hello");
+
+        let request = Request::new(ComposeRequest {
+            name: "template_name".to_string(),
+            input_values: vec![],
+        });
+        let composed_text = all_traits
+            .handler
+            .compose(request)
+            .await?
+            .into_inner()
+            .result;
+
+        assert_eq!(composed_text, "Hello default_value");
+
+        let request = Request::new(RemoveMessageRequest {
+            key: "greeting".to_string(),
+            locale: "en".to_string(),
+        });
+        all_traits.admin_handler.remove_message(request).await?;
+
+        let request = Request::new(ListMessagesRequest {
+            offset: 0,
+            limit: 10,
+        });
+        let listed_messages = all_traits
+            .admin_handler
+            .list_messages(request)
+            .await?
+            .into_inner();
+
+        assert_eq!(listed_messages.count, 0);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn move_template_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let request = Request::new(AddTemplateRequest {
+            name: "template_name".to_string(),
+            description: "template_description".to_string(),
+            body: "template body {{input_name}}".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        });
+        all_traits.handler.add_template(request).await?;
+
+        let request = Request::new(MoveTemplateRequest {
+            name: "template_name".to_string(),
+            namespace: "auth".to_string(),
+        });
+        let moved_template = all_traits
+            .admin_handler
+            .move_template(request)
+            .await?
+            .into_inner();
+
+        assert_eq!(moved_template.name, "auth/template_name");
+
+        let moved_template = all_traits
+            .templates_repository
+            .get_template(GLOBAL_TENANT, "auth", "template_name")
+            .await?;
+
+        assert!(moved_template.is_some());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn rename_and_clone_template_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let request = Request::new(AddTemplateRequest {
+            name: "template_name".to_string(),
+            description: "template_description".to_string(),
+            body: "template body {{input_name}}".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        });
+        all_traits.handler.add_template(request).await?;
+
+        let request = Request::new(RenameTemplateRequest {
+            name: "template_name".to_string(),
+            new_name: "renamed_template".to_string(),
+        });
+        all_traits.admin_handler.rename_template(request).await?;
+
+        let mut request = Request::new(CloneTemplateRequest {
+            name: "renamed_template".to_string(),
+            new_name: "cloned_template".to_string(),
+        });
+        request
+            .metadata_mut()
+            .insert(TENANT_METADATA_KEY, "tenant".parse()?);
+        let cloned_template = all_traits
+            .admin_handler
+            .clone_template(request)
+            .await?
+            .into_inner();
+
+        assert_eq!(cloned_template.name, "cloned_template");
+        assert_eq!(cloned_template.template_inputs.len(), 1);
+
+        let original_template = all_traits
+            .templates_repository
+            .get_template(GLOBAL_TENANT, "", "template_name")
+            .await?;
+        let renamed_template = all_traits
+            .templates_repository
+            .get_template(GLOBAL_TENANT, "", "renamed_template")
+            .await?;
+        let cloned_template = all_traits
+            .templates_repository
+            .get_template("tenant", "", "cloned_template")
+            .await?;
+
+        assert!(original_template.is_none());
+        assert!(renamed_template.is_some());
+        assert!(cloned_template.is_some());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn restore_template_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let request = Request::new(AddTemplateRequest {
+            name: template_name.to_string(),
+            description: "template_description".to_string(),
+            body: "template body {{input_name}}".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        });
+        all_traits.handler.add_template(request).await?;
+
+        let request = Request::new(RemoveTemplateRequest {
+            name: template_name.to_string(),
+        });
+        all_traits.handler.remove_template(request).await?;
+
+        let request = Request::new(ListTrashedTemplatesRequest {
+            offset: 0,
+            limit: 10,
+        });
+        let trashed_templates = all_traits
+            .admin_handler
+            .list_trashed_templates(request)
+            .await?
+            .into_inner();
+
+        assert_eq!(trashed_templates.count, 1);
+        assert_eq!(trashed_templates.templates[0].name, template_name);
+
+        let request = Request::new(RestoreTemplateRequest {
+            name: template_name.to_string(),
+        });
+        all_traits.admin_handler.restore_template(request).await?;
+
+        let restored_template = all_traits
+            .templates_repository
+            .get_template(GLOBAL_TENANT, "", template_name)
+            .await?;
+
+        assert!(restored_template.is_some());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn update_template_revision_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let request = Request::new(AddTemplateRequest {
+            name: template_name.to_string(),
+            description: "template_description".to_string(),
+            body: "template body {{input_name}}".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        });
+        all_traits.handler.add_template(request).await?;
+
+        let request = Request::new(GetTemplateRequest {
+            name: template_name.to_string(),
+        });
+        let fetched_template = all_traits.admin_handler.get_template(request).await?;
+        let revision = fetched_template
+            .metadata()
+            .get(REVISION_METADATA_KEY)
+            .expect("fetched template carries a revision")
+            .clone();
+
+        let update_request = UpdateTemplateRequest {
+            name: template_name.to_string(),
+            description: "updated_description".to_string(),
+            body: "updated body {{input_name}}".to_string(),
+            template_inputs: fetched_template.into_inner().template_inputs,
+            engine: None,
+            content_type: None,
+        };
+        let mut request = Request::new(update_request.clone());
+        request
+            .metadata_mut()
+            .insert(EXPECTED_REVISION_METADATA_KEY, revision.clone());
+        let updated_template = all_traits.admin_handler.update_template(request).await?;
+        let updated_revision = updated_template
+            .metadata()
+            .get(REVISION_METADATA_KEY)
+            .expect("updated template carries a revision")
+            .clone();
+
+        assert_ne!(updated_revision, revision);
+        assert_eq!(
+            updated_template.into_inner().description,
+            "updated_description"
+        );
+
+        let mut request = Request::new(update_request);
+        request
+            .metadata_mut()
+            .insert(EXPECTED_REVISION_METADATA_KEY, revision);
+        let stale_update = all_traits.admin_handler.update_template(request).await;
+
+        assert!(stale_update.is_err());
+
+        let request = Request::new(ListTemplateRequest {
+            offset: 0,
+            limit: 10,
+        });
+        let listed_templates = all_traits.handler.list_templates(request).await?;
+
+        assert_eq!(
+            listed_templates.metadata().get(REVISIONS_METADATA_KEY),
+            Some(&updated_revision)
+        );
+
+        let request = Request::new(ComposeRequest {
+            name: template_name.to_string(),
+            input_values: vec![],
+        });
+        let composed_template = all_traits.handler.compose(request).await?;
+
+        assert_eq!(
+            composed_template.metadata().get(REVISION_METADATA_KEY),
+            Some(&updated_revision)
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn ensure_template_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let ensure_request = EnsureTemplateRequest {
+            name: "template_name".to_string(),
+            description: "template_description".to_string(),
+            body: "template body {{input_name}}".to_string(),
+            template_inputs: vec![AdminTemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+            engine: None,
+            content_type: None,
+        };
+
+        let created_template = all_traits
+            .admin_handler
+            .ensure_template(Request::new(ensure_request.clone()))
+            .await?;
+
+        assert!(created_template
+            .metadata()
+            .get(REVISION_METADATA_KEY)
+            .is_some());
+        assert_eq!(
+            created_template.into_inner().outcome(),
+            EnsureOutcome::Created
+        );
+
+        let unchanged_template = all_traits
+            .admin_handler
+            .ensure_template(Request::new(ensure_request.clone()))
+            .await?
+            .into_inner();
+
+        assert_eq!(unchanged_template.outcome(), EnsureOutcome::Unchanged);
+
+        let updated_template = all_traits
+            .admin_handler
+            .ensure_template(Request::new(EnsureTemplateRequest {
+                engine: Some("minijinja".to_string()),
+                ..ensure_request
+            }))
+            .await?
+            .into_inner();
+
+        assert_eq!(updated_template.outcome(), EnsureOutcome::Updated);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn export_import_templates_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let mut request = Request::new(AddTemplateRequest {
+            name: "template_name".to_string(),
+            description: "template_description".to_string(),
+            body: "template body {{input_name}}".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        });
+        request
+            .metadata_mut()
+            .insert(TENANT_METADATA_KEY, "staging".parse()?);
+        all_traits.handler.add_template(request).await?;
+
+        let mut request = Request::new(ExportTemplatesRequest { names: vec![] });
+        request
+            .metadata_mut()
+            .insert(TENANT_METADATA_KEY, "staging".parse()?);
+        let bundle = all_traits
+            .admin_handler
+            .export_templates(request)
+            .await?
+            .into_inner()
+            .bundle;
+
+        let import_request = |dry_run| -> anyhow::Result<Request<ImportTemplatesRequest>> {
+            let mut request = Request::new(ImportTemplatesRequest {
+                bundle: bundle.clone(),
+                strategy: ConflictStrategy::Skip.into(),
+                dry_run,
+            });
+            request
+                .metadata_mut()
+                .insert(TENANT_METADATA_KEY, "production".parse()?);
+            Ok(request)
+        };
+        let dry_run_report = all_traits
+            .admin_handler
+            .import_templates(import_request(true)?)
+            .await?
+            .into_inner();
+
+        assert!(dry_run_report.dry_run);
+        assert_eq!(dry_run_report.changes[0].action(), ImportAction::Create);
+        assert!(all_traits
+            .templates_repository
+            .get_template("production", "", "template_name")
+            .await?
+            .is_none());
+
+        all_traits
+            .admin_handler
+            .import_templates(import_request(false)?)
+            .await?;
+
+        assert!(all_traits
+            .templates_repository
+            .get_template("production", "", "template_name")
+            .await?
+            .is_some());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn list_template_engines_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let mut request = Request::new(AddTemplateRequest {
+            name: "template_name".to_string(),
+            description: "template_description".to_string(),
+            body: "Hello {{ input_name }}".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        });
+        request
+            .metadata_mut()
+            .insert(ENGINE_METADATA_KEY, "tera".parse()?);
+        all_traits.handler.add_template(request).await?;
+
+        let request = Request::new(ListTemplatesRequest {
+            offset: 0,
+            limit: 10,
+            namespace: None,
+        });
+        let listed_engines = all_traits
+            .admin_handler
+            .list_template_engines(request)
+            .await?
+            .into_inner()
+            .templates;
+
+        assert_eq!(listed_engines.len(), 1);
+        assert_eq!(listed_engines[0].name, "template_name");
+        assert_eq!(listed_engines[0].engine, "tera");
+        assert_eq!(listed_engines[0].content_type, "html");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn stylesheet_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let request = Request::new(AddTemplateRequest {
+            name: "template_name".to_string(),
+            description: "template_description".to_string(),
+            body: "<p>{{input_name}}</p>".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        });
+        all_traits.handler.add_template(request).await?;
+
+        let request = Request::new(SetStylesheetRequest {
+            css: "p { color: red; }".to_string(),
+        });
+        all_traits.admin_handler.set_stylesheet(request).await?;
+
+        let stylesheet = all_traits
+            .admin_handler
+            .get_stylesheet(Request::new(GetStylesheetRequest {}))
+            .await?
+            .into_inner();
+
+        assert_eq!(stylesheet.tenant, GLOBAL_TENANT);
+        assert_eq!(stylesheet.css, "p { color: red; }");
+
+        let mut request = Request::new(ComposeRequest {
+            name: "template_name".to_string(),
+            input_values: vec![],
+        });
+        request
+            .metadata_mut()
+            .insert(INLINE_CSS_METADATA_KEY, "true".parse()?);
+        let composed_text = all_traits
+            .handler
+            .compose(request)
+            .await?
+            .into_inner()
+            .result;
+
+        assert!(composed_text.contains("style=\"color: red;\""));
+
+        let request = Request::new(SetStylesheetRequest {
+            css: " ".to_string(),
+        });
+        let invalid_stylesheet = all_traits.admin_handler.set_stylesheet(request).await;
+
+        assert!(invalid_stylesheet.is_err());
+
+        all_traits
+            .admin_handler
+            .remove_stylesheet(Request::new(RemoveStylesheetRequest {}))
+            .await?;
+        let removed_stylesheet = all_traits
+            .admin_handler
+            .get_stylesheet(Request::new(GetStylesheetRequest {}))
+            .await;
+
+        assert!(removed_stylesheet.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn json_schema_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let request = Request::new(EnsureTemplateRequest {
+            name: "template_name".to_string(),
+            description: "template_description".to_string(),
+            body: "{\"count\": \"{{input_name}}\"}".to_string(),
+            template_inputs: vec![AdminTemplateInput {
+                name: "input_name".to_string(),
+                default_value: "many".to_string(),
+            }],
+            engine: None,
+            content_type: Some("json".to_string()),
+        });
+        all_traits.admin_handler.ensure_template(request).await?;
+
+        let request = Request::new(SetJsonSchemaRequest {
+            name: "template_name".to_string(),
+            json_schema: "{\"properties\": {\"count\": {\"type\": \"integer\"}}}".to_string(),
+        });
+        all_traits.admin_handler.set_json_schema(request).await?;
+
+        let request = Request::new(ComposeRequest {
+            name: "template_name".to_string(),
+            input_values: vec![],
+        });
+        let invalid_output = all_traits.handler.compose(request).await;
+
+        assert!(invalid_output.is_err());
+
+        let request = Request::new(SetJsonSchemaRequest {
+            name: "template_name".to_string(),
+            json_schema: "{".to_string(),
+        });
+        let invalid_schema = all_traits.admin_handler.set_json_schema(request).await;
+
+        assert!(invalid_schema.is_err());
+
+        let request = Request::new(RemoveJsonSchemaRequest {
+            name: "template_name".to_string(),
+        });
+        all_traits.admin_handler.remove_json_schema(request).await?;
+
+        let request = Request::new(ComposeRequest {
+            name: "template_name".to_string(),
+            input_values: vec![],
+        });
+        let composed_text = all_traits
+            .handler
+            .compose(request)
+            .await?
+            .into_inner()
+            .result;
+
+        assert_eq!(composed_text, "{\"count\": \"many\"}");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn template_settings_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let request = Request::new(AddTemplateRequest {
+            name: "template_name".to_string(),
+            description: "template_description".to_string(),
+            body: "<div>{{{biografía}}}</div>".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "biografía".to_string(),
+                default_value: "".to_string(),
+            }],
+        });
+        all_traits.handler.add_template(request).await?;
+
+        let request = Request::new(SetTemplateSettingsRequest {
+            name: "template_name".to_string(),
+            settings: "{\"rich_html_inputs\": [\"biografía\"]}".to_string(),
+        });
+        all_traits
+            .admin_handler
+            .set_template_settings(request)
+            .await?;
+
+        let request = Request::new(GetTemplateSettingsRequest {
+            name: "template_name".to_string(),
+        });
+        let settings = all_traits
+            .admin_handler
+            .get_template_settings(request)
+            .await?
+            .into_inner()
+            .settings;
+
+        assert_eq!(settings, "{\"rich_html_inputs\":[\"biografía\"]}");
+
+        let request = Request::new(ComposeRequest {
+            name: "template_name".to_string(),
+            input_values: vec![InputValue {
+                name: "biografía".to_string(),
+                value: "<p>Hola</p><script>alert(1)</script>".to_string(),
+            }],
+        });
+        let composed = all_traits.handler.compose(request).await?;
+        let sanitized_inputs = composed
+            .metadata()
+            .get_bin(SANITIZED_INPUTS_METADATA_KEY)
+            .expect("the sanitized inputs are reported")
+            .to_bytes()?;
+        let sanitized_inputs: serde_json::Value = serde_json::from_slice(&sanitized_inputs)?;
+
+        assert_eq!(composed.into_inner().result, "<div><p>Hola</p></div>");
+        assert_eq!(sanitized_inputs[0]["name"], "biografía");
+
+        let request = Request::new(SetTemplateSettingsRequest {
+            name: "template_name".to_string(),
+            settings: "{\"unknown\": true}".to_string(),
+        });
+        let invalid_settings = all_traits
+            .admin_handler
+            .set_template_settings(request)
+            .await;
+
+        assert!(invalid_settings.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn remove_template_revision_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let request = Request::new(AddTemplateRequest {
+            name: template_name.to_string(),
+            description: "template_description".to_string(),
+            body: "template body {{input_name}}".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        });
+
+        let response = all_traits.handler.add_template(request).await?;
+        let revision = response
+            .metadata()
+            .get(REVISION_METADATA_KEY)
+            .expect("created template carries a revision")
+            .clone();
+
+        let mut request = Request::new(RemoveTemplateRequest {
+            name: template_name.to_string(),
+        });
+        request
+            .metadata_mut()
+            .insert(EXPECTED_REVISION_METADATA_KEY, "0".parse()?);
+        let stale_remove = all_traits.handler.remove_template(request).await;
+
+        assert!(stale_remove.is_err());
+
+        let mut request = Request::new(RemoveTemplateRequest {
+            name: template_name.to_string(),
+        });
+        request
+            .metadata_mut()
+            .insert(EXPECTED_REVISION_METADATA_KEY, revision);
+        all_traits.handler.remove_template(request).await?;
+
+        let get_template = all_traits
+            .templates_repository
+            .get_template(GLOBAL_TENANT, "", template_name)
+            .await?;
+
+        assert!(get_template.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn add_template_idempotency_key_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let add_request = AddTemplateRequest {
+            name: "template_name".to_string(),
+            description: "template_description".to_string(),
+            body: "template body {{input_name}}".to_string(),
+            template_inputs: vec![TemplateInput {
+                name: "input_name".to_string(),
+                default_value: "default_value".to_string(),
+            }],
+        };
+
+        let mut request = Request::new(add_request.clone());
+        request
+            .metadata_mut()
+            .insert(IDEMPOTENCY_KEY_METADATA_KEY, "provision-1".parse()?);
+        let created_template = all_traits.handler.add_template(request).await?;
+
+        let mut request = Request::new(add_request.clone());
+        request
+            .metadata_mut()
+            .insert(IDEMPOTENCY_KEY_METADATA_KEY, "provision-1".parse()?);
+        let replayed_template = all_traits.handler.add_template(request).await?;
+
+        assert_eq!(
+            replayed_template.metadata().get(REVISION_METADATA_KEY),
+            created_template.metadata().get(REVISION_METADATA_KEY)
+        );
+        assert_eq!(
+            replayed_template.into_inner(),
+            created_template.into_inner()
+        );
+
+        let mut request = Request::new(AddTemplateRequest {
+            description: "other_description".to_string(),
+            ..add_request.clone()
+        });
+        request
+            .metadata_mut()
+            .insert(IDEMPOTENCY_KEY_METADATA_KEY, "provision-1".parse()?);
+        let reused_key = all_traits.handler.add_template(request).await;
+
+        assert!(reused_key.is_err());
+
+        let mut request = Request::new(add_request);
+        request
+            .metadata_mut()
+            .insert(IDEMPOTENCY_KEY_METADATA_KEY, "provision-1".parse()?);
+        request
+            .metadata_mut()
+            .insert(ENGINE_METADATA_KEY, "minijinja".parse()?);
+        let reused_key_with_engine = all_traits.handler.add_template(request).await;
+
+        assert!(reused_key_with_engine.is_err());
+
+        Ok(())
+    }
 }
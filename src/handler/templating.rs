@@ -4,15 +4,37 @@ use madtofan_microservice_common::templating::{
 };
 use tonic::{Request, Response, Status};
 
-use crate::service::templating::DynTemplatingServiceTrait;
+use templating::service::{
+    compose::parse_compose_output,
+    content::parse_content_type,
+    css::parse_inline_css,
+    engine::parse_engine,
+    idempotency::{fingerprint, IdempotencyService},
+    templating::{ComposeOptions, DynTemplatingServiceTrait, TemplateDefinition},
+};
+
+use super::metadata::{
+    get_metadata_value, get_tenant, set_revision, set_revisions, set_sanitized_inputs,
+    set_unsubscribe_headers, CONTENT_TYPE_METADATA_KEY, ENGINE_METADATA_KEY,
+    EXPECTED_REVISION_METADATA_KEY, IDEMPOTENCY_KEY_METADATA_KEY, INLINE_CSS_METADATA_KEY,
+    LAYOUT_METADATA_KEY, LOCALE_METADATA_KEY, NAMESPACE_METADATA_KEY, OUTPUT_METADATA_KEY,
+    SCOPE_METADATA_KEY,
+};
 
 pub struct RequestHandler {
     templating_service: DynTemplatingServiceTrait,
+    idempotency_service: IdempotencyService,
 }
 
 impl RequestHandler {
-    pub fn new(templating_service: DynTemplatingServiceTrait) -> Self {
-        Self { templating_service }
+    pub fn new(
+        templating_service: DynTemplatingServiceTrait,
+        idempotency_service: IdempotencyService,
+    ) -> Self {
+        Self {
+            templating_service,
+            idempotency_service,
+        }
     }
 }
 
@@ -22,50 +44,140 @@ impl Templating for RequestHandler {
         &self,
         request: Request<AddTemplateRequest>,
     ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let idempotency_key = get_metadata_value(&request, IDEMPOTENCY_KEY_METADATA_KEY)?;
+        let engine = get_metadata_value(&request, ENGINE_METADATA_KEY)?
+            .map(|engine| parse_engine(&engine))
+            .transpose()?
+            .unwrap_or_default();
+        let content_type = get_metadata_value(&request, CONTENT_TYPE_METADATA_KEY)?
+            .map(|content_type| parse_content_type(&content_type))
+            .transpose()?
+            .unwrap_or_default();
         let req = request.into_inner();
+        let fingerprint = fingerprint(
+            &tenant,
+            &req,
+            &[
+                (ENGINE_METADATA_KEY, engine.as_str()),
+                (CONTENT_TYPE_METADATA_KEY, content_type.as_str()),
+            ],
+        );
+
+        if let Some(key) = &idempotency_key {
+            if let Some(replayed_template) = self
+                .idempotency_service
+                .replay(&tenant, key, &fingerprint)
+                .await?
+            {
+                let mut response = Response::new(replayed_template.template);
+                set_revision(&mut response, &replayed_template.revision)?;
+
+                return Ok(response);
+            }
+        }
 
         let added_template = self
             .templating_service
-            .add_template(req.name, req.description, req.body, req.template_inputs)
+            .add_template(
+                tenant.clone(),
+                TemplateDefinition {
+                    name: req.name.clone(),
+                    description: req.description.clone(),
+                    body: req.body.clone(),
+                    engine,
+                    content_type,
+                    inputs: req.template_inputs.clone(),
+                },
+            )
             .await?;
 
-        Ok(Response::new(added_template))
+        if let Some(key) = &idempotency_key {
+            self.idempotency_service
+                .record(&tenant, key, &fingerprint, &added_template)
+                .await?;
+        }
+
+        let mut response = Response::new(added_template.template);
+        set_revision(&mut response, &added_template.revision)?;
+
+        Ok(response)
     }
 
     async fn remove_template(
         &self,
         request: Request<RemoveTemplateRequest>,
     ) -> Result<Response<TemplateResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let expected_revision = get_metadata_value(&request, EXPECTED_REVISION_METADATA_KEY)?;
         let req = request.into_inner();
 
-        let removed_template = self.templating_service.remove_template(req.name).await?;
+        let removed_template = self
+            .templating_service
+            .remove_template(tenant, req.name, expected_revision)
+            .await?;
+
+        let mut response = Response::new(removed_template.template);
+        set_revision(&mut response, &removed_template.revision)?;
 
-        Ok(Response::new(removed_template))
+        Ok(response)
     }
 
     async fn list_templates(
         &self,
         request: Request<ListTemplateRequest>,
     ) -> Result<Response<ListTemplateResponse>, Status> {
-        let list_templates_response = self
+        let tenant = get_tenant(&request)?;
+        let namespace = get_metadata_value(&request, NAMESPACE_METADATA_KEY)?;
+
+        let listed_templates = self
             .templating_service
-            .list_templates(request.into_inner())
+            .list_templates(tenant, request.into_inner(), namespace)
             .await?;
+        let revisions = listed_templates
+            .templates
+            .iter()
+            .map(|template| template.revision.clone())
+            .collect::<Vec<String>>();
+
+        let mut response = Response::new(listed_templates.into_list_template_response());
+        set_revisions(&mut response, &revisions)?;
 
-        Ok(Response::new(list_templates_response))
+        Ok(response)
     }
 
     async fn compose(
         &self,
         request: Request<ComposeRequest>,
     ) -> Result<Response<ComposeResponse>, Status> {
+        let tenant = get_tenant(&request)?;
+        let options = ComposeOptions {
+            scope: get_metadata_value(&request, SCOPE_METADATA_KEY)?,
+            locale: get_metadata_value(&request, LOCALE_METADATA_KEY)?,
+            output: get_metadata_value(&request, OUTPUT_METADATA_KEY)?
+                .map(|output| parse_compose_output(&output))
+                .transpose()?
+                .unwrap_or_default(),
+            layout: get_metadata_value(&request, LAYOUT_METADATA_KEY)?,
+            inline_css: get_metadata_value(&request, INLINE_CSS_METADATA_KEY)?
+                .map(|inline_css| parse_inline_css(&inline_css))
+                .transpose()?
+                .unwrap_or_default(),
+        };
         let req = request.into_inner();
 
-        let result = self
+        let composed = self
             .templating_service
-            .compose(req.name, req.input_values)
+            .compose_with_report(tenant, req.name, req.input_values, options)
             .await?;
 
-        Ok(Response::new(ComposeResponse { result }))
+        let mut response = Response::new(ComposeResponse {
+            result: composed.output,
+        });
+        set_revision(&mut response, &composed.revision)?;
+        set_sanitized_inputs(&mut response, &composed.sanitized_inputs)?;
+        set_unsubscribe_headers(&mut response, &composed.unsubscribe_headers)?;
+
+        Ok(response)
     }
 }
@@ -0,0 +1,62 @@
+//! The template model, validation and compose engine behind the templating gRPC
+//! service, usable without the server.
+//!
+//! [`service::templating::TemplatingService`] composes templates with the same
+//! semantics as the server on top of any implementation of the repository traits in
+//! [`repository`]. [`storage::Repositories::memory`] keeps everything in process, e.g.
+//! to preview templates offline:
+//!
+//! ```no_run
+//! # async fn preview() -> madtofan_microservice_common::errors::ServiceResult<String> {
+//! use madtofan_microservice_common::templating::{compose_request::InputValue, TemplateInput};
+//! use templating::{
+//!     repository::{memory::InMemoryStore, template::GLOBAL_TENANT},
+//!     service::{
+//!         content::ContentType,
+//!         engine::TemplateEngineKind,
+//!         locale::LocaleSettings,
+//!         templating::{
+//!             ComposeOptions, TemplateDefinition, TemplatingService, TemplatingServiceTrait,
+//!         },
+//!     },
+//!     storage::Repositories,
+//! };
+//!
+//! let service = TemplatingService::new(
+//!     &Repositories::memory(InMemoryStore::new()),
+//!     LocaleSettings::default(),
+//! );
+//! service
+//!     .add_template(
+//!         GLOBAL_TENANT.to_string(),
+//!         TemplateDefinition {
+//!             name: "welcome".to_string(),
+//!             description: "Welcome email".to_string(),
+//!             body: "Hello {{name}}".to_string(),
+//!             engine: TemplateEngineKind::Handlebars,
+//!             content_type: ContentType::Html,
+//!             inputs: vec![TemplateInput {
+//!                 name: "name".to_string(),
+//!                 default_value: "there".to_string(),
+//!             }],
+//!         },
+//!     )
+//!     .await?;
+//! service
+//!     .compose(
+//!         GLOBAL_TENANT.to_string(),
+//!         "welcome".to_string(),
+//!         vec![InputValue {
+//!             name: "name".to_string(),
+//!             value: "Ada".to_string(),
+//!         }],
+//!         ComposeOptions::default(),
+//!     )
+//!     .await
+//! # }
+//! ```
+
+pub mod manifest;
+pub mod repository;
+pub mod service;
+pub mod storage;
@@ -1,28 +1,32 @@
+use std::fs;
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::config::AppConfig;
+use crate::config::{AppCommand, AppConfig};
+use crate::handler::admin::AdminRequestHandler;
 use crate::handler::templating::RequestHandler;
-use crate::repository::input::{DynInputRepositoryTrait, InputRepository};
-use crate::repository::template::{DynTemplateRepositoryTrait, TemplateRepository};
-use crate::seed::SeedService;
-use crate::service::templating::{DynTemplatingServiceTrait, TemplatingService};
+use crate::handler::templating_admin::templating_admin_server::TemplatingAdminServer;
+use crate::purge::PurgeService;
 use clap::Parser;
 use dotenv::dotenv;
-use madtofan_microservice_common::{
-    repository::connection_pool::ServiceConnectionManager,
-    templating::templating_server::TemplatingServer,
-};
+use madtofan_microservice_common::templating::templating_server::TemplatingServer;
+use templating::manifest::ManifestService;
+use templating::repository::filesystem::FileTemplateStore;
+use templating::service::bundle::ImportOptions;
+use templating::service::idempotency::IdempotencyService;
+use templating::service::limits::RenderLimits;
+use templating::service::links::ClickTracking;
+use templating::service::locale::LocaleSettings;
+use templating::service::templating::{DynTemplatingServiceTrait, TemplatingService};
+use templating::storage::{Repositories, StorageBackend};
 use tonic::transport::Server;
-use tracing::error;
 use tracing::log::info;
 use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
 mod config;
 mod handler;
-mod repository;
-mod seed;
-mod service;
+mod purge;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -35,48 +39,153 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    info!("Environment loaded and configuration parsed, initializing Postgres connection...");
-    let pg_pool = ServiceConnectionManager::new_pool(&config.database_url)
+    info!(
+        "Environment loaded and configuration parsed, initializing {:?} connection...",
+        config.storage_backend
+    );
+    let repositories = match config.storage_backend {
+        StorageBackend::Filesystem => {
+            let template_dir = config
+                .template_dir
+                .clone()
+                .expect("TEMPLATE_DIR has to be set for the filesystem backend");
+            info!("serving templates from {:?}", template_dir);
+            let store = FileTemplateStore::open(template_dir)
+                .expect("could not load the template directory");
+            if config.template_dir_hot_reload {
+                store
+                    .watch()
+                    .expect("could not watch the template directory");
+            }
+            Repositories::filesystem(store)
+        }
+        storage_backend => Repositories::connect(
+            storage_backend,
+            config
+                .database_url
+                .as_deref()
+                .expect("DATABASE_URL has to be set for the database backends"),
+            config.run_migrations,
+        )
         .await
-        .expect("could not initialize the database connection pool");
-
-    if config.run_migrations {
-        info!("migrations enabled, running...");
-        sqlx::migrate!()
-            .run(&pg_pool)
-            .await
-            .unwrap_or_else(|err| error!("There was an error during migration: {:?}", err));
-    }
-    info!("Database configured! initializing repositories...");
+        .expect("could not initialize the database connection pool"),
+    };
+    info!("Storage configured! initializing repositories...");
 
     let app_host = &config.service_url;
     let app_port = &config.service_port;
     let app_url = format!("{}:{}", app_host, app_port).parse().unwrap();
-    let inputs_repository =
-        Arc::new(InputRepository::new(pg_pool.clone())) as DynInputRepositoryTrait;
-    let template_repository = Arc::new(TemplateRepository::new(pg_pool, inputs_repository.clone()))
-        as DynTemplateRepositoryTrait;
 
     info!("Repositories initialized, Initializing Services");
-    let templating_service = Arc::new(TemplatingService::new(
-        template_repository.clone(),
-        inputs_repository.clone(),
-    )) as DynTemplatingServiceTrait;
+    let locale_settings = LocaleSettings::new(&config.default_locale, &config.supported_locales)
+        .expect("could not parse the configured locales");
+    let render_limits = RenderLimits::new(
+        Duration::from_millis(config.render_timeout_ms),
+        config.render_max_output_bytes,
+        config.render_max_partial_depth,
+    );
+    let mut templating_service =
+        TemplatingService::new(&repositories, locale_settings).with_render_limits(render_limits);
+    if let (Some(click_tracking_url), Some(click_tracking_key)) =
+        (&config.click_tracking_url, &config.click_tracking_key)
+    {
+        info!("tracking link clicks through {:?}", click_tracking_url);
+        templating_service = templating_service.with_click_tracking(
+            ClickTracking::new(click_tracking_url, click_tracking_key)
+                .expect("could not configure click tracking"),
+        );
+    }
+    let templating_service = Arc::new(templating_service) as DynTemplatingServiceTrait;
 
     info!("Services initialized, Initializing Handler");
-    let request_handler = RequestHandler::new(templating_service.clone());
+    let request_handler = RequestHandler::new(
+        templating_service.clone(),
+        IdempotencyService::new(repositories.idempotency_key_repository.clone()),
+    );
+    let admin_request_handler = AdminRequestHandler::new(templating_service.clone());
+
+    let manifest_service = ManifestService::new(
+        templating_service.clone(),
+        config.manifest_dir.clone(),
+        config.manifest_sync_mode,
+    );
+    match &config.command {
+        Some(AppCommand::SyncManifest) => {
+            manifest_service
+                .sync()
+                .await
+                .expect("unexpected error occurred while syncing the manifest");
+            return Ok(());
+        }
+        Some(AppCommand::Export {
+            output,
+            tenant,
+            names,
+        }) => {
+            let bundle = templating_service
+                .export_templates(tenant.clone(), names.clone())
+                .await
+                .expect("unexpected error occurred while exporting templates");
+            fs::write(output, serde_json::to_string_pretty(&bundle)?)?;
+            info!(
+                "exported {} templates to {:?}",
+                bundle.templates.len(),
+                output
+            );
+            return Ok(());
+        }
+        Some(AppCommand::Import {
+            input,
+            tenant,
+            strategy,
+            dry_run,
+        }) => {
+            let bundle = serde_json::from_str(&fs::read_to_string(input)?)?;
+            let report = templating_service
+                .import_templates(
+                    tenant.clone(),
+                    bundle,
+                    ImportOptions {
+                        strategy: *strategy,
+                        dry_run: *dry_run,
+                    },
+                )
+                .await
+                .expect("unexpected error occurred while importing templates");
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        None => {}
+    }
 
     if config.seed {
-        info!("seeding enabled, creating test data...");
-        SeedService::new(templating_service, template_repository)
-            .seed()
+        info!("seeding enabled, syncing the manifest...");
+        manifest_service
+            .sync()
             .await
-            .expect("unexpected error occurred while seeding application data");
+            .expect("unexpected error occurred while syncing the manifest");
     }
 
+    info!(
+        "starting trash purge, retaining trashed templates for {} days",
+        config.trash_retention_days
+    );
+    tokio::spawn(
+        PurgeService::new(
+            repositories.template_repository,
+            repositories.idempotency_key_repository,
+            config.trash_retention_days,
+            config.idempotency_key_retention_hours,
+            config.trash_purge_interval_seconds,
+        )
+        .expect("could not configure the trash purge")
+        .run(),
+    );
+
     info!("Service ready for request at {:#?}!", app_url);
     Server::builder()
         .add_service(TemplatingServer::new(request_handler))
+        .add_service(TemplatingAdminServer::new(admin_request_handler))
         .serve(app_url)
         .await?;
     Ok(())
@@ -0,0 +1,301 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use madtofan_microservice_common::{
+    errors::{ServiceError, ServiceResult},
+    templating::{ListTemplateRequest, TemplateInput},
+};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    repository::template::GLOBAL_TENANT,
+    service::{
+        content::ContentType,
+        engine::TemplateEngineKind,
+        namespace::NAMESPACE_SEPARATOR,
+        templating::{DynTemplatingServiceTrait, EnsureOutcome, TemplateDefinition},
+    },
+};
+
+const BODY_EXTENSION: &str = "hbs";
+const LIST_PAGE_SIZE: i64 = 100;
+
+/// How a manifest is applied to templates that already exist.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestSyncMode {
+    /// Only creates the templates that do not exist yet.
+    CreateOnly,
+    /// Also updates existing templates whose definition changed.
+    Update,
+    /// Also moves templates missing from the manifest to the trash.
+    Prune,
+}
+
+#[derive(Deserialize, Debug)]
+struct ManifestInput {
+    name: String,
+    #[serde(default)]
+    default_value: String,
+}
+
+/// A template definition, `registration.yaml` describes the template `registration`
+/// and its body is read from `registration.hbs` next to it unless `body` names
+/// another file, written for `engine` (Handlebars by default) and rendering
+/// `content_type` (HTML by default). Subdirectories become namespaces.
+#[derive(Deserialize, Debug)]
+struct ManifestFile {
+    name: Option<String>,
+    #[serde(default = "default_tenant")]
+    tenant: String,
+    #[serde(default)]
+    description: String,
+    body: Option<PathBuf>,
+    #[serde(default)]
+    engine: TemplateEngineKind,
+    #[serde(default)]
+    content_type: ContentType,
+    #[serde(default)]
+    inputs: Vec<ManifestInput>,
+}
+
+fn default_tenant() -> String {
+    GLOBAL_TENANT.to_string()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestTemplate {
+    pub tenant: String,
+    pub name: String,
+    pub description: String,
+    pub body: String,
+    pub engine: TemplateEngineKind,
+    pub content_type: ContentType,
+    pub inputs: Vec<TemplateInput>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncReport {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+}
+
+/// Reads every `.yaml`, `.yml`, `.toml` and `.json` template definition under `dir`.
+pub fn load_manifest(dir: &Path) -> ServiceResult<Vec<ManifestTemplate>> {
+    let mut files = vec![];
+    collect_files(dir, &mut files)?;
+    files.sort();
+
+    files
+        .iter()
+        .filter(|file| {
+            matches!(
+                file.extension().and_then(|extension| extension.to_str()),
+                Some("yaml" | "yml" | "toml" | "json")
+            )
+        })
+        .map(|file| load_template(dir, file))
+        .collect()
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> ServiceResult<()> {
+    let entries = fs::read_dir(dir).map_err(|err| manifest_error(dir, err))?;
+    for entry in entries {
+        let path = entry.map_err(|err| manifest_error(dir, err))?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn load_template(dir: &Path, file: &Path) -> ServiceResult<ManifestTemplate> {
+    let source = fs::read_to_string(file).map_err(|err| manifest_error(file, err))?;
+    let manifest_file: ManifestFile = match file.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&source).map_err(|err| manifest_error(file, err))?,
+        Some("json") => serde_json::from_str(&source).map_err(|err| manifest_error(file, err))?,
+        _ => serde_yaml::from_str(&source).map_err(|err| manifest_error(file, err))?,
+    };
+
+    let body_file = match &manifest_file.body {
+        Some(body) => file.with_file_name(body),
+        None => file.with_extension(BODY_EXTENSION),
+    };
+    let body = fs::read_to_string(&body_file).map_err(|err| manifest_error(&body_file, err))?;
+
+    let name = match manifest_file.name {
+        Some(name) => name,
+        None => file
+            .strip_prefix(dir)
+            .unwrap_or(file)
+            .with_extension("")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(&NAMESPACE_SEPARATOR.to_string()),
+    };
+
+    Ok(ManifestTemplate {
+        tenant: manifest_file.tenant,
+        name,
+        description: manifest_file.description,
+        body: body.strip_suffix('\n').unwrap_or(&body).to_string(),
+        engine: manifest_file.engine,
+        content_type: manifest_file.content_type,
+        inputs: manifest_file
+            .inputs
+            .into_iter()
+            .map(|input| TemplateInput {
+                name: input.name,
+                default_value: input.default_value,
+            })
+            .collect(),
+    })
+}
+
+fn manifest_error(path: &Path, err: impl std::fmt::Display) -> ServiceError {
+    ServiceError::BadRequest(format!("invalid manifest {:?}: {}", path, err))
+}
+
+pub struct ManifestService {
+    templating_service: DynTemplatingServiceTrait,
+    manifest_dir: PathBuf,
+    sync_mode: ManifestSyncMode,
+}
+
+impl ManifestService {
+    pub fn new(
+        templating_service: DynTemplatingServiceTrait,
+        manifest_dir: PathBuf,
+        sync_mode: ManifestSyncMode,
+    ) -> Self {
+        Self {
+            templating_service,
+            manifest_dir,
+            sync_mode,
+        }
+    }
+
+    pub async fn sync(&self) -> ServiceResult<SyncReport> {
+        let templates = load_manifest(&self.manifest_dir)?;
+        info!(
+            "syncing {} manifest templates from {:?} in {:?} mode",
+            templates.len(),
+            &self.manifest_dir,
+            self.sync_mode
+        );
+
+        let mut report = SyncReport::default();
+        for template in &templates {
+            if self.sync_mode == ManifestSyncMode::CreateOnly {
+                let existing_template = self
+                    .templating_service
+                    .get_template(template.tenant.clone(), template.name.clone())
+                    .await;
+                match existing_template {
+                    Ok(_) => {
+                        report.unchanged += 1;
+                        continue;
+                    }
+                    Err(ServiceError::NotFound(_)) => {}
+                    Err(err) => return Err(err),
+                }
+            }
+
+            let ensured_template = self
+                .templating_service
+                .ensure_template(
+                    template.tenant.clone(),
+                    TemplateDefinition {
+                        name: template.name.clone(),
+                        description: template.description.clone(),
+                        body: template.body.clone(),
+                        engine: template.engine,
+                        content_type: template.content_type,
+                        inputs: template.inputs.clone(),
+                    },
+                )
+                .await?;
+            match ensured_template.outcome {
+                EnsureOutcome::Created => report.created += 1,
+                EnsureOutcome::Updated => report.updated += 1,
+                EnsureOutcome::Unchanged => report.unchanged += 1,
+            }
+        }
+
+        if self.sync_mode == ManifestSyncMode::Prune {
+            report.removed = self.prune(&templates).await?;
+        }
+
+        info!("manifest synced: {:?}", &report);
+
+        Ok(report)
+    }
+
+    /// Trashes the templates of the manifest's tenants that the manifest does not
+    /// define, the global tenant is always covered.
+    async fn prune(&self, templates: &[ManifestTemplate]) -> ServiceResult<usize> {
+        let mut tenants = templates
+            .iter()
+            .map(|template| template.tenant.clone())
+            .collect::<HashSet<String>>();
+        tenants.insert(GLOBAL_TENANT.to_string());
+
+        let mut removed = 0;
+        for tenant in tenants {
+            let known_names = templates
+                .iter()
+                .filter(|template| template.tenant == tenant)
+                .map(|template| template.name.as_str())
+                .collect::<HashSet<&str>>();
+
+            let mut unknown_names = vec![];
+            let mut offset = 0;
+            loop {
+                let listed_templates = self
+                    .templating_service
+                    .list_templates(
+                        tenant.clone(),
+                        ListTemplateRequest {
+                            offset,
+                            limit: LIST_PAGE_SIZE,
+                        },
+                        None,
+                    )
+                    .await?;
+                unknown_names.extend(
+                    listed_templates
+                        .templates
+                        .iter()
+                        .map(|listed| &listed.template.name)
+                        .filter(|name| !known_names.contains(name.as_str()))
+                        .cloned(),
+                );
+
+                offset += LIST_PAGE_SIZE;
+                if offset >= listed_templates.count {
+                    break;
+                }
+            }
+
+            for name in unknown_names {
+                info!("pruning template {:?} missing from the manifest", &name);
+                self.templating_service
+                    .remove_template(tenant.clone(), name, None)
+                    .await?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+use sqlx::types::time::OffsetDateTime;
+use tracing::{error, info};
+
+use templating::repository::{
+    idempotency_key::DynIdempotencyKeyRepositoryTrait, template::DynTemplateRepositoryTrait,
+};
+
+/// Permanently deletes templates that have been in the trash for longer than the
+/// retention period, together with their inputs, overrides and variants, and forgets
+/// expired idempotency keys.
+pub struct PurgeService {
+    template_repository: DynTemplateRepositoryTrait,
+    idempotency_key_repository: DynIdempotencyKeyRepositoryTrait,
+    retention: Duration,
+    idempotency_key_retention: Duration,
+    interval: Duration,
+}
+
+impl PurgeService {
+    pub fn new(
+        template_repository: DynTemplateRepositoryTrait,
+        idempotency_key_repository: DynIdempotencyKeyRepositoryTrait,
+        retention_days: u64,
+        idempotency_key_retention_hours: u64,
+        interval_seconds: u64,
+    ) -> anyhow::Result<Self> {
+        if interval_seconds == 0 {
+            bail!("the trash purge interval cannot be zero");
+        }
+        let retention = retention_days
+            .checked_mul(24 * 60 * 60)
+            .map(Duration::from_secs)
+            .filter(|retention| cutoff(*retention).is_ok())
+            .context("the trash retention is too long")?;
+        let idempotency_key_retention = idempotency_key_retention_hours
+            .checked_mul(60 * 60)
+            .map(Duration::from_secs)
+            .filter(|retention| cutoff(*retention).is_ok())
+            .context("the idempotency key retention is too long")?;
+
+        Ok(Self {
+            template_repository,
+            idempotency_key_repository,
+            retention,
+            idempotency_key_retention,
+            interval: Duration::from_secs(interval_seconds),
+        })
+    }
+
+    pub async fn purge(&self) -> ServiceResult<u64> {
+        let deleted_before = cutoff(self.retention)?;
+        let purged_count = self
+            .template_repository
+            .purge_templates(deleted_before)
+            .await?;
+
+        info!("purged {} trashed templates", purged_count);
+
+        let created_before = cutoff(self.idempotency_key_retention)?;
+        let expired_count = self
+            .idempotency_key_repository
+            .purge_idempotency_keys(created_before)
+            .await?;
+
+        info!("purged {} expired idempotency keys", expired_count);
+
+        Ok(purged_count)
+    }
+
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            if let Err(purge_error) = self.purge().await {
+                error!(
+                    "There was an error while purging the trash: {:?}",
+                    purge_error
+                );
+            }
+        }
+    }
+}
+
+/// The time `retention` ago, failing instead of overflowing for retentions reaching
+/// past the dates the database can store.
+fn cutoff(retention: Duration) -> ServiceResult<OffsetDateTime> {
+    i64::try_from(retention.as_secs())
+        .ok()
+        .and_then(|retention| {
+            OffsetDateTime::now_utc()
+                .unix_timestamp()
+                .checked_sub(retention)
+        })
+        .and_then(|cutoff| OffsetDateTime::from_unix_timestamp(cutoff).ok())
+        .ok_or_else(|| {
+            ServiceError::InternalServerErrorWithContext(
+                "retention reaches past the earliest representable date".to_string(),
+            )
+        })
+}
@@ -0,0 +1,888 @@
+//! Behaviour every template and input repository backend has to share, each case runs
+//! against the Postgres, SQLite, in-memory and filesystem repositories.
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+use madtofan_microservice_common::templating::TemplateInput;
+use sqlx::{sqlite::SqlitePoolOptions, types::time::OffsetDateTime, PgPool};
+
+use super::{
+    filesystem::{FileInputRepository, FileTemplateRepository, FileTemplateStore},
+    input::{DynInputRepositoryTrait, InputRepository},
+    memory::{InMemoryInputRepository, InMemoryStore, InMemoryTemplateRepository},
+    sqlite::{input::SqliteInputRepository, template::SqliteTemplateRepository},
+    template::{DynTemplateRepositoryTrait, TemplateFields, TemplateRepository, GLOBAL_TENANT},
+};
+
+pub struct Backend {
+    templates_repository: DynTemplateRepositoryTrait,
+    inputs_repository: DynInputRepositoryTrait,
+}
+
+fn postgres_backend(pool: PgPool) -> Backend {
+    let inputs_repository = Arc::new(InputRepository::new(pool.clone())) as DynInputRepositoryTrait;
+    let templates_repository = Arc::new(TemplateRepository::new(pool, inputs_repository.clone()))
+        as DynTemplateRepositoryTrait;
+
+    Backend {
+        templates_repository,
+        inputs_repository,
+    }
+}
+
+async fn sqlite_backend() -> anyhow::Result<Backend> {
+    // Every connection to `sqlite::memory:` opens its own database, so the pool keeps
+    // exactly one connection alive for the whole test.
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await?;
+    sqlx::migrate!("./migrations_sqlite").run(&pool).await?;
+
+    let inputs_repository =
+        Arc::new(SqliteInputRepository::new(pool.clone())) as DynInputRepositoryTrait;
+    let templates_repository = Arc::new(SqliteTemplateRepository::new(
+        pool,
+        inputs_repository.clone(),
+    )) as DynTemplateRepositoryTrait;
+
+    Ok(Backend {
+        templates_repository,
+        inputs_repository,
+    })
+}
+
+fn memory_backend() -> Backend {
+    let store = InMemoryStore::new();
+
+    Backend {
+        templates_repository: Arc::new(InMemoryTemplateRepository::new(store.clone()))
+            as DynTemplateRepositoryTrait,
+        inputs_repository: Arc::new(InMemoryInputRepository::new(store)) as DynInputRepositoryTrait,
+    }
+}
+
+fn filesystem_backend(template_dir: &Path) -> anyhow::Result<Backend> {
+    let store = FileTemplateStore::open(template_dir.to_path_buf())?;
+
+    Ok(Backend {
+        templates_repository: Arc::new(FileTemplateRepository::new(store.clone()))
+            as DynTemplateRepositoryTrait,
+        inputs_repository: Arc::new(FileInputRepository::new(store)) as DynInputRepositoryTrait,
+    })
+}
+
+macro_rules! conformance_tests {
+    ($($case:ident),* $(,)?) => {
+        mod postgres {
+            $(
+                #[sqlx::test]
+                async fn $case(pool: sqlx::PgPool) -> anyhow::Result<()> {
+                    super::$case(super::postgres_backend(pool)).await
+                }
+            )*
+        }
+
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $case() -> anyhow::Result<()> {
+                    super::$case(super::sqlite_backend().await?).await
+                }
+            )*
+        }
+
+        mod memory {
+            $(
+                #[tokio::test]
+                async fn $case() -> anyhow::Result<()> {
+                    super::$case(super::memory_backend()).await
+                }
+            )*
+        }
+
+        mod filesystem {
+            $(
+                #[tokio::test]
+                async fn $case() -> anyhow::Result<()> {
+                    let template_dir = std::env::temp_dir().join(format!(
+                        "templating-conformance-{}-{}",
+                        std::process::id(),
+                        stringify!($case)
+                    ));
+                    let result =
+                        super::$case(super::filesystem_backend(&template_dir)?).await;
+                    std::fs::remove_dir_all(template_dir)?;
+                    result
+                }
+            )*
+        }
+    };
+}
+
+conformance_tests!(
+    remove_template_test,
+    get_template_test,
+    remove_inputs_test,
+    namespace_scoped_name_test,
+    namespace_wildcard_test,
+    tenant_isolation_test,
+    clone_template_test,
+    trash_template_test,
+    unique_name_test,
+    update_template_test,
+    purge_cascade_test,
+    list_order_test,
+    rename_restore_revision_test,
+);
+
+async fn remove_template_test(traits: Backend) -> anyhow::Result<()> {
+    let template_to_remove_name = "template_to_remove";
+    let template_to_remove_body = "template_to_remove_body";
+    let inputs = vec![
+        TemplateInput {
+            name: "input1".to_string(),
+            default_value: "default_value1".to_string(),
+        },
+        TemplateInput {
+            name: "input2".to_string(),
+            default_value: "default_value2".to_string(),
+        },
+    ];
+
+    traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "",
+            "template1",
+            TemplateFields {
+                description: "description1",
+                body: "body1",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+    traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "",
+            template_to_remove_name,
+            TemplateFields {
+                description: "description_to_remove",
+                body: template_to_remove_body,
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+
+    let removed_template = traits
+        .templates_repository
+        .remove_template(GLOBAL_TENANT, "", template_to_remove_name, None)
+        .await?;
+
+    let templates_list = traits
+        .templates_repository
+        .list_templates(GLOBAL_TENANT, None, false, 0, 10)
+        .await?;
+
+    assert_eq!(templates_list.len(), 1);
+    assert_eq!(removed_template.unwrap().body, template_to_remove_body);
+
+    Ok(())
+}
+
+async fn get_template_test(traits: Backend) -> anyhow::Result<()> {
+    let template_to_get_name = "template_to_get";
+    let template_to_get_body = "template_to_get_body";
+    let inputs = vec![
+        TemplateInput {
+            name: "input1".to_string(),
+            default_value: "default_value1".to_string(),
+        },
+        TemplateInput {
+            name: "input2".to_string(),
+            default_value: "default_value2".to_string(),
+        },
+    ];
+
+    traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "",
+            template_to_get_name,
+            TemplateFields {
+                description: "description",
+                body: template_to_get_body,
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+
+    let get_template = traits
+        .templates_repository
+        .get_template(GLOBAL_TENANT, "", template_to_get_name)
+        .await?;
+
+    assert_eq!(get_template.unwrap().body, template_to_get_body);
+
+    Ok(())
+}
+
+async fn remove_inputs_test(traits: Backend) -> anyhow::Result<()> {
+    let inputs = vec![
+        TemplateInput {
+            name: "input1".to_string(),
+            default_value: "default_value1".to_string(),
+        },
+        TemplateInput {
+            name: "input2".to_string(),
+            default_value: "default_value2".to_string(),
+        },
+    ];
+
+    let template = traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "",
+            "name",
+            TemplateFields {
+                description: "descriptions",
+                body: "body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+
+    traits.inputs_repository.remove_inputs(template.id).await?;
+
+    let template_inputs = traits
+        .inputs_repository
+        .get_template_inputs(template.id)
+        .await;
+
+    assert_eq!(template_inputs.unwrap().len(), 0);
+
+    Ok(())
+}
+
+async fn namespace_scoped_name_test(traits: Backend) -> anyhow::Result<()> {
+    let inputs = vec![TemplateInput {
+        name: "input1".to_string(),
+        default_value: "default_value1".to_string(),
+    }];
+
+    traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "auth",
+            "welcome",
+            TemplateFields {
+                description: "description",
+                body: "auth body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+    traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "billing",
+            "welcome",
+            TemplateFields {
+                description: "description",
+                body: "billing body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+    traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "billing/invoices",
+            "paid",
+            TemplateFields {
+                description: "description",
+                body: "invoice body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+    traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "billing-archive",
+            "welcome",
+            TemplateFields {
+                description: "description",
+                body: "archive body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+
+    let auth_template = traits
+        .templates_repository
+        .get_template(GLOBAL_TENANT, "auth", "welcome")
+        .await?;
+    let billing_templates = traits
+        .templates_repository
+        .list_templates(GLOBAL_TENANT, Some("billing"), false, 0, 10)
+        .await?;
+
+    let billing_count = traits
+        .templates_repository
+        .get_templates_count(GLOBAL_TENANT, Some("billing"), false)
+        .await?;
+    let mut billing_paths = billing_templates
+        .iter()
+        .map(|template| template.path())
+        .collect::<Vec<String>>();
+    billing_paths.sort();
+
+    assert_eq!(auth_template.unwrap().body, "auth body");
+    assert_eq!(billing_count, 2);
+    assert_eq!(
+        billing_paths,
+        vec!["billing/invoices/paid", "billing/welcome"]
+    );
+
+    Ok(())
+}
+
+async fn namespace_wildcard_test(traits: Backend) -> anyhow::Result<()> {
+    let inputs = vec![TemplateInput {
+        name: "input1".to_string(),
+        default_value: "default_value1".to_string(),
+    }];
+
+    for namespace in [
+        "mail_out",
+        "mail_out/digests",
+        "mailXout",
+        "mailXout/digests",
+    ] {
+        traits
+            .templates_repository
+            .add_template(
+                GLOBAL_TENANT,
+                namespace,
+                "weekly",
+                TemplateFields {
+                    description: "description",
+                    body: "body",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &inputs,
+                },
+            )
+            .await?;
+    }
+
+    let templates = traits
+        .templates_repository
+        .list_templates(GLOBAL_TENANT, Some("mail_out"), false, 0, 10)
+        .await?;
+    let count = traits
+        .templates_repository
+        .get_templates_count(GLOBAL_TENANT, Some("mail_out"), false)
+        .await?;
+    let mut paths = templates
+        .iter()
+        .map(|template| template.path())
+        .collect::<Vec<String>>();
+    paths.sort();
+
+    assert_eq!(count, 2);
+    assert_eq!(paths, vec!["mail_out/digests/weekly", "mail_out/weekly"]);
+
+    Ok(())
+}
+
+async fn tenant_isolation_test(traits: Backend) -> anyhow::Result<()> {
+    let inputs = vec![TemplateInput {
+        name: "input1".to_string(),
+        default_value: "default_value1".to_string(),
+    }];
+
+    traits
+        .templates_repository
+        .add_template(
+            "tenant_a",
+            "",
+            "welcome",
+            TemplateFields {
+                description: "description",
+                body: "a body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+    traits
+        .templates_repository
+        .add_template(
+            "tenant_b",
+            "",
+            "welcome",
+            TemplateFields {
+                description: "description",
+                body: "b body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+
+    let tenant_a_template = traits
+        .templates_repository
+        .get_template("tenant_a", "", "welcome")
+        .await?;
+    let global_template = traits
+        .templates_repository
+        .get_template(GLOBAL_TENANT, "", "welcome")
+        .await?;
+    let tenant_b_count = traits
+        .templates_repository
+        .get_templates_count("tenant_b", None, false)
+        .await?;
+
+    assert_eq!(tenant_a_template.unwrap().body, "a body");
+    assert!(global_template.is_none());
+    assert_eq!(tenant_b_count, 1);
+
+    Ok(())
+}
+
+async fn clone_template_test(traits: Backend) -> anyhow::Result<()> {
+    let inputs = vec![
+        TemplateInput {
+            name: "input1".to_string(),
+            default_value: "default_value1".to_string(),
+        },
+        TemplateInput {
+            name: "input2".to_string(),
+            default_value: "default_value2".to_string(),
+        },
+    ];
+
+    let source_template = traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "",
+            "source",
+            TemplateFields {
+                description: "description",
+                body: "body",
+                engine: "tera",
+                content_type: "text",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+
+    let cloned_template = traits
+        .templates_repository
+        .clone_template(source_template.id, "tenant", "copies", "clone")
+        .await?;
+
+    assert_eq!(cloned_template.cloned_from, Some(source_template.id));
+    assert_eq!(cloned_template.path(), "copies/clone");
+    assert_eq!(cloned_template.body, "body");
+    assert_eq!(cloned_template.engine, "tera");
+    assert_eq!(cloned_template.content_type, "text");
+    assert_eq!(cloned_template.inputs.len(), 2);
+
+    Ok(())
+}
+
+async fn trash_template_test(traits: Backend) -> anyhow::Result<()> {
+    let inputs = vec![TemplateInput {
+        name: "input1".to_string(),
+        default_value: "default_value1".to_string(),
+    }];
+
+    let template = traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "",
+            "name",
+            TemplateFields {
+                description: "description",
+                body: "body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+    traits
+        .templates_repository
+        .remove_template(GLOBAL_TENANT, "", "name", None)
+        .await?;
+
+    let trashed_count = traits
+        .templates_repository
+        .get_templates_count(GLOBAL_TENANT, None, true)
+        .await?;
+    let trashed_template = traits
+        .templates_repository
+        .get_template(GLOBAL_TENANT, "", "name")
+        .await?;
+
+    assert_eq!(trashed_count, 1);
+    assert!(trashed_template.is_none());
+
+    let restored_template = traits
+        .templates_repository
+        .restore_template(GLOBAL_TENANT, "", "name")
+        .await?
+        .unwrap();
+
+    assert_eq!(restored_template.id, template.id);
+    assert_eq!(restored_template.inputs.len(), 1);
+
+    traits
+        .templates_repository
+        .remove_template(GLOBAL_TENANT, "", "name", None)
+        .await?;
+    let purged_count = traits
+        .templates_repository
+        .purge_templates(OffsetDateTime::now_utc())
+        .await?;
+    let restored_template = traits
+        .templates_repository
+        .restore_template(GLOBAL_TENANT, "", "name")
+        .await?;
+
+    assert_eq!(purged_count, 1);
+    assert!(restored_template.is_none());
+
+    Ok(())
+}
+
+async fn unique_name_test(traits: Backend) -> anyhow::Result<()> {
+    let inputs = vec![TemplateInput {
+        name: "input1".to_string(),
+        default_value: "default_value1".to_string(),
+    }];
+
+    traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "",
+            "name",
+            TemplateFields {
+                description: "description",
+                body: "body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+    let duplicate_template = traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "",
+            "name",
+            TemplateFields {
+                description: "description",
+                body: "body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await;
+
+    assert!(duplicate_template.is_err());
+
+    traits
+        .templates_repository
+        .remove_template(GLOBAL_TENANT, "", "name", None)
+        .await?;
+    let replacement_template = traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "",
+            "name",
+            TemplateFields {
+                description: "description",
+                body: "new body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+    let restored_template = traits
+        .templates_repository
+        .restore_template(GLOBAL_TENANT, "", "name")
+        .await;
+
+    assert_eq!(replacement_template.body, "new body");
+    assert!(restored_template.is_err());
+
+    Ok(())
+}
+
+async fn update_template_test(traits: Backend) -> anyhow::Result<()> {
+    let inputs = vec![TemplateInput {
+        name: "input1".to_string(),
+        default_value: "default_value1".to_string(),
+    }];
+    let updated_inputs = vec![TemplateInput {
+        name: "input2".to_string(),
+        default_value: "default_value2".to_string(),
+    }];
+
+    let template = traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "",
+            "name",
+            TemplateFields {
+                description: "description",
+                body: "body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+    let updated_template = traits
+        .templates_repository
+        .update_template(
+            GLOBAL_TENANT,
+            "",
+            "name",
+            TemplateFields {
+                description: "updated description",
+                body: "updated body",
+                engine: "liquid",
+                content_type: "json",
+                inputs: &updated_inputs,
+            },
+            Some(template.updated_at),
+        )
+        .await?
+        .unwrap();
+
+    assert_eq!(updated_template.body, "updated body");
+    assert_eq!(updated_template.engine, "liquid");
+    assert_eq!(updated_template.content_type, "json");
+    assert_eq!(updated_template.inputs.len(), 1);
+    assert_eq!(updated_template.inputs[0].name, "input2");
+    assert_ne!(updated_template.updated_at, template.updated_at);
+
+    let stale_update = traits
+        .templates_repository
+        .update_template(
+            GLOBAL_TENANT,
+            "",
+            "name",
+            TemplateFields {
+                description: "stale description",
+                body: "stale body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+            Some(template.updated_at),
+        )
+        .await?;
+    let stale_remove = traits
+        .templates_repository
+        .remove_template(GLOBAL_TENANT, "", "name", Some(template.updated_at))
+        .await?;
+
+    assert!(stale_update.is_none());
+    assert!(stale_remove.is_none());
+
+    Ok(())
+}
+
+async fn purge_cascade_test(traits: Backend) -> anyhow::Result<()> {
+    let inputs = vec![TemplateInput {
+        name: "input1".to_string(),
+        default_value: "default_value1".to_string(),
+    }];
+
+    let template = traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "",
+            "name",
+            TemplateFields {
+                description: "description",
+                body: "body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+    let cloned_template = traits
+        .templates_repository
+        .clone_template(template.id, GLOBAL_TENANT, "", "clone")
+        .await?;
+    traits
+        .templates_repository
+        .remove_template(GLOBAL_TENANT, "", "name", None)
+        .await?;
+    traits
+        .templates_repository
+        .purge_templates(OffsetDateTime::now_utc() + Duration::from_secs(1))
+        .await?;
+
+    let purged_inputs = traits
+        .inputs_repository
+        .get_template_inputs(template.id)
+        .await?;
+    let clone = traits
+        .templates_repository
+        .get_template(GLOBAL_TENANT, "", "clone")
+        .await?
+        .unwrap();
+
+    assert!(purged_inputs.is_empty());
+    assert_eq!(clone.id, cloned_template.id);
+    assert_eq!(clone.cloned_from, None);
+    assert_eq!(clone.inputs.len(), 1);
+
+    Ok(())
+}
+
+async fn list_order_test(traits: Backend) -> anyhow::Result<()> {
+    let inputs = vec![TemplateInput {
+        name: "input".to_string(),
+        default_value: "default_value".to_string(),
+    }];
+    for (namespace, name) in [
+        ("", "charlie"),
+        ("mail", "alpha"),
+        ("", "bravo"),
+        ("", "alpha"),
+    ] {
+        traits
+            .templates_repository
+            .add_template(
+                GLOBAL_TENANT,
+                namespace,
+                name,
+                TemplateFields {
+                    description: "description",
+                    body: "body",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &inputs,
+                },
+            )
+            .await?;
+    }
+
+    let mut listed = vec![];
+    for offset in 0..5 {
+        for template in traits
+            .templates_repository
+            .list_templates(GLOBAL_TENANT, None, false, offset, 1)
+            .await?
+        {
+            listed.push((template.namespace, template.name));
+        }
+    }
+
+    assert_eq!(
+        listed,
+        vec![
+            ("".to_string(), "alpha".to_string()),
+            ("".to_string(), "bravo".to_string()),
+            ("".to_string(), "charlie".to_string()),
+            ("mail".to_string(), "alpha".to_string()),
+        ]
+    );
+
+    Ok(())
+}
+
+async fn rename_restore_revision_test(traits: Backend) -> anyhow::Result<()> {
+    let inputs = vec![TemplateInput {
+        name: "input".to_string(),
+        default_value: "default_value".to_string(),
+    }];
+    let template = traits
+        .templates_repository
+        .add_template(
+            GLOBAL_TENANT,
+            "",
+            "name",
+            TemplateFields {
+                description: "description",
+                body: "body",
+                engine: "handlebars",
+                content_type: "html",
+                inputs: &inputs,
+            },
+        )
+        .await?;
+
+    let renamed_template = traits
+        .templates_repository
+        .rename_template(GLOBAL_TENANT, "", "name", "", "new_name")
+        .await?
+        .unwrap();
+
+    assert_eq!(renamed_template.id, template.id);
+    assert!(renamed_template.updated_at > template.updated_at);
+
+    traits
+        .templates_repository
+        .remove_template(GLOBAL_TENANT, "", "new_name", None)
+        .await?;
+    let restored_template = traits
+        .templates_repository
+        .restore_template(GLOBAL_TENANT, "", "new_name")
+        .await?
+        .unwrap();
+
+    assert_eq!(restored_template.id, template.id);
+    assert!(restored_template.updated_at > renamed_template.updated_at);
+    assert_eq!(restored_template.created_at, template.created_at);
+
+    Ok(())
+}
@@ -0,0 +1,1659 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock, Weak},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use madtofan_microservice_common::templating::TemplateInput;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use sqlx::types::time::OffsetDateTime;
+use tracing::{error, info};
+
+use crate::service::{
+    content::ContentType,
+    engine::TemplateEngineKind,
+    namespace::{is_within_namespace, NAMESPACE_SEPARATOR},
+};
+
+use super::{
+    idempotency_key::{IdempotencyKeyEntity, IdempotencyKeyRepositoryTrait},
+    input::{InputEntity, InputRepositoryTrait},
+    message::{MessageEntity, MessageRepositoryTrait},
+    stylesheet::{StylesheetEntity, StylesheetRepositoryTrait},
+    template::{TemplateFields, TemplateInputsEntity, TemplateRepositoryTrait},
+    template_override::{
+        OverrideInputEntity, TemplateOverrideEntity, TemplateOverrideInputsEntity,
+        TemplateOverrideRepositoryTrait,
+    },
+    template_schema::{TemplateSchemaEntity, TemplateSchemaRepositoryTrait},
+    template_settings::{TemplateSettingsEntity, TemplateSettingsRepositoryTrait},
+    template_variant::{
+        TemplateVariantEntity, TemplateVariantRepositoryTrait, VariantLocaleEntity,
+    },
+};
+
+const METADATA_EXTENSION: &str = "yaml";
+const BODY_EXTENSION: &str = "hbs";
+const TRASH_DIR: &str = ".trash";
+const STATE_FILE: &str = ".state.json";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct TemplateMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<i64>,
+    #[serde(default)]
+    description: String,
+    #[serde(default = "default_engine")]
+    engine: String,
+    #[serde(default = "default_content_type")]
+    content_type: String,
+    #[serde(default)]
+    inputs: Vec<MetadataInput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cloned_from: Option<i64>,
+    /// Written by the store on every change to the template, so revisions do not depend
+    /// on file times. Hand-written files without them fall back to their file times until
+    /// they are first loaded.
+    #[serde(
+        default,
+        with = "super::timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    created_at: Option<OffsetDateTime>,
+    #[serde(
+        default,
+        with = "super::timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    updated_at: Option<OffsetDateTime>,
+}
+
+fn default_engine() -> String {
+    TemplateEngineKind::default().as_str().to_string()
+}
+
+fn default_content_type() -> String {
+    ContentType::default().as_str().to_string()
+}
+
+/// Everything the store keeps next to the templates in `.state.json`: the last id handed
+/// out, so ids are never reused, and what the other repositories store about templates
+/// and tenants.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+struct StoreState {
+    #[serde(default)]
+    last_id: i64,
+    #[serde(default)]
+    overrides: Vec<TemplateOverrideEntity>,
+    #[serde(default)]
+    override_inputs: Vec<OverrideInputEntity>,
+    #[serde(default)]
+    variants: Vec<TemplateVariantEntity>,
+    #[serde(default)]
+    schemas: Vec<TemplateSchemaEntity>,
+    #[serde(default)]
+    settings: Vec<TemplateSettingsEntity>,
+    #[serde(default)]
+    messages: Vec<MessageEntity>,
+    #[serde(default)]
+    stylesheets: Vec<StylesheetEntity>,
+    #[serde(default)]
+    idempotency_keys: Vec<IdempotencyKeyEntity>,
+    #[serde(skip)]
+    last_write_at: Option<OffsetDateTime>,
+}
+
+impl StoreState {
+    fn next_id(&mut self) -> i64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    /// Every write gets a later timestamp than the previous one so revisions always change.
+    fn write_timestamp(&mut self) -> OffsetDateTime {
+        let mut timestamp = now();
+        if let Some(last_write_at) = self.last_write_at {
+            if timestamp <= last_write_at {
+                timestamp = last_write_at + Duration::from_micros(1);
+            }
+        }
+        self.last_write_at = Some(timestamp);
+        timestamp
+    }
+
+    fn with_override_inputs(
+        &self,
+        template_override: &TemplateOverrideEntity,
+    ) -> TemplateOverrideInputsEntity {
+        TemplateOverrideInputsEntity::new(
+            template_override.clone(),
+            self.override_inputs
+                .iter()
+                .filter(|input| input.override_id == template_override.id)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Drops what is stored about purged templates, like the foreign keys of the database
+    /// backends do.
+    fn purge_templates(&mut self, template_ids: &[i64]) {
+        let override_ids = self
+            .overrides
+            .iter()
+            .filter(|template_override| template_ids.contains(&template_override.template_id))
+            .map(|template_override| template_override.id)
+            .collect::<Vec<i64>>();
+        self.override_inputs
+            .retain(|input| !override_ids.contains(&input.override_id));
+        self.overrides
+            .retain(|template_override| !template_ids.contains(&template_override.template_id));
+        self.variants
+            .retain(|variant| !template_ids.contains(&variant.template_id));
+        self.schemas
+            .retain(|schema| !template_ids.contains(&schema.template_id));
+        self.settings
+            .retain(|settings| !template_ids.contains(&settings.template_id));
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct MetadataInput {
+    name: String,
+    #[serde(default)]
+    default_value: String,
+}
+
+/// Templates stored as a directory tree, `<tenant>/<namespace>/<name>.yaml` holds the
+/// description, engine, content type and inputs of a template and `<name>.hbs` next to it
+/// holds its body.
+/// Trashed templates are moved under `.trash/<deleted at>/`. Every template gets an id
+/// stored in its metadata file, templates written by hand get one when they are first
+/// loaded. Tenants, namespaces and
+/// names can only use ASCII letters, digits, `_` and `-`.
+/// Overrides, variants, schemas, settings, messages, stylesheets and idempotency keys are
+/// kept in `.state.json` at the root.
+///
+/// The tree is cached in memory, it is reloaded after every write and, when watched,
+/// whenever a file under the directory changes.
+pub struct FileTemplateStore {
+    root: PathBuf,
+    templates: RwLock<Vec<TemplateInputsEntity>>,
+    state: Mutex<StoreState>,
+    write_lock: Mutex<()>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl FileTemplateStore {
+    pub fn open(root: PathBuf) -> anyhow::Result<Arc<Self>> {
+        fs::create_dir_all(&root)
+            .with_context(|| format!("could not create the template directory {:?}", &root))?;
+        let root = fs::canonicalize(&root)
+            .with_context(|| format!("could not resolve the template directory {:?}", &root))?;
+
+        let state_path = root.join(STATE_FILE);
+        let state = match state_path.exists() {
+            true => serde_json::from_str(
+                &fs::read_to_string(&state_path)
+                    .with_context(|| format!("could not read {:?}", &state_path))?,
+            )
+            .with_context(|| format!("invalid store state {:?}", &state_path))?,
+            false => StoreState::default(),
+        };
+
+        let store = Arc::new(Self {
+            root,
+            templates: RwLock::new(vec![]),
+            state: Mutex::new(state),
+            write_lock: Mutex::new(()),
+            watcher: Mutex::new(None),
+        });
+        store.reload()?;
+
+        Ok(store)
+    }
+
+    /// Reloads the templates whenever a file under the directory changes.
+    pub fn watch(self: &Arc<Self>) -> anyhow::Result<()> {
+        let store: Weak<Self> = Arc::downgrade(self);
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => {
+                        error!("template directory watch failed: {:?}", err);
+                        return;
+                    }
+                };
+                if event.paths.iter().all(|path| {
+                    path.file_name()
+                        .is_some_and(|name| name.to_string_lossy().starts_with(STATE_FILE))
+                }) {
+                    return;
+                }
+                if let Some(store) = store.upgrade() {
+                    if let Err(err) = store.reload() {
+                        error!("could not reload the template directory: {:?}", err);
+                    }
+                }
+            })
+            .context("could not watch the template directory")?;
+        watcher
+            .watch(&self.root, RecursiveMode::Recursive)
+            .context("could not watch the template directory")?;
+
+        info!("watching {:?} for template changes", &self.root);
+        *self.watcher.lock().unwrap() = Some(watcher);
+
+        Ok(())
+    }
+
+    pub fn reload(&self) -> anyhow::Result<()> {
+        let mut templates = vec![];
+        for tenant_dir in read_dirs(&self.root)? {
+            let tenant = file_name(&tenant_dir);
+            if tenant == TRASH_DIR {
+                continue;
+            }
+            load_templates(&tenant_dir, &tenant, None, &mut templates)?;
+        }
+
+        let trash_dir = self.root.join(TRASH_DIR);
+        if trash_dir.is_dir() {
+            for deleted_dir in read_dirs(&trash_dir)? {
+                let deleted_at = parse_micros(&file_name(&deleted_dir))?;
+                for tenant_dir in read_dirs(&deleted_dir)? {
+                    let tenant = file_name(&tenant_dir);
+                    load_templates(&tenant_dir, &tenant, Some(deleted_at), &mut templates)?;
+                }
+            }
+        }
+
+        self.assign_ids(&mut templates)?;
+        templates.sort_by(|a, b| (&a.namespace, &a.name, a.id).cmp(&(&b.namespace, &b.name, b.id)));
+        *self.templates.write().unwrap() = templates;
+
+        Ok(())
+    }
+
+    /// Templates loaded without an id get the next one, which is written back into their
+    /// metadata file.
+    fn assign_ids(&self, templates: &mut [TemplateInputsEntity]) -> anyhow::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let mut next_state = state.clone();
+        next_state.last_id = templates
+            .iter()
+            .map(|template| template.id)
+            .max()
+            .unwrap_or_default()
+            .max(state.last_id);
+
+        for template in templates.iter_mut().filter(|template| template.id == 0) {
+            let path = self.template_metadata_path(template)?;
+            let mut metadata = read_metadata(&path)?;
+            let id = match metadata.id {
+                Some(id) => id,
+                None => {
+                    let id = next_state.next_id();
+                    metadata.id = Some(id);
+                    metadata.created_at.get_or_insert(template.created_at);
+                    metadata.updated_at.get_or_insert(template.updated_at);
+                    fs::write(&path, serde_yaml::to_string(&metadata)?).with_context(|| {
+                        format!("could not write the template metadata {:?}", &path)
+                    })?;
+                    id
+                }
+            };
+            template.id = id;
+            template
+                .inputs
+                .iter_mut()
+                .for_each(|input| input.template_id = id);
+        }
+
+        if next_state.last_id != state.last_id {
+            self.save_state(&next_state)?;
+            *state = next_state;
+        }
+
+        Ok(())
+    }
+
+    fn next_id(&self) -> anyhow::Result<i64> {
+        self.update_state(|state| Ok(state.next_id()))
+    }
+
+    fn read_state<T>(&self, read: impl FnOnce(&StoreState) -> T) -> T {
+        read(&self.state.lock().unwrap())
+    }
+
+    /// Applies the update to a copy of the state, which replaces the state once it is
+    /// saved.
+    fn update_state<T>(
+        &self,
+        update: impl FnOnce(&mut StoreState) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let mut state = self.state.lock().unwrap();
+        let mut next_state = state.clone();
+        let result = update(&mut next_state)?;
+        self.save_state(&next_state)?;
+        *state = next_state;
+
+        Ok(result)
+    }
+
+    /// The state is written next to the file it replaces and renamed over it, so it is
+    /// never left half written.
+    fn save_state(&self, state: &StoreState) -> anyhow::Result<()> {
+        let path = self.root.join(STATE_FILE);
+        let partial_path = self.root.join(format!("{}.partial", STATE_FILE));
+        fs::write(&partial_path, serde_json::to_string(state)?)
+            .with_context(|| format!("could not write the store state {:?}", &partial_path))?;
+        fs::rename(&partial_path, &path)
+            .with_context(|| format!("could not write the store state {:?}", &path))
+    }
+
+    fn check_template(&self, template_id: i64) -> anyhow::Result<()> {
+        if self.find(|template| template.id == template_id).is_none() {
+            bail!("template {} does not exist", template_id);
+        }
+
+        Ok(())
+    }
+
+    fn templates(&self) -> Vec<TemplateInputsEntity> {
+        self.templates.read().unwrap().clone()
+    }
+
+    fn find(
+        &self,
+        predicate: impl Fn(&TemplateInputsEntity) -> bool,
+    ) -> Option<TemplateInputsEntity> {
+        self.templates
+            .read()
+            .unwrap()
+            .iter()
+            .find(|template| predicate(template))
+            .cloned()
+    }
+
+    fn find_live(&self, tenant: &str, namespace: &str, name: &str) -> Option<TemplateInputsEntity> {
+        self.find(|template| {
+            template.tenant == tenant
+                && template.namespace == namespace
+                && template.name == name
+                && template.deleted_at.is_none()
+        })
+    }
+
+    fn metadata_path(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        deleted_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<PathBuf> {
+        check_segment(tenant)?;
+        check_segment(name)?;
+        let mut path = match deleted_at {
+            Some(deleted_at) => self
+                .root
+                .join(TRASH_DIR)
+                .join(format_micros(deleted_at))
+                .join(tenant),
+            None => self.root.join(tenant),
+        };
+        namespace
+            .split(NAMESPACE_SEPARATOR)
+            .filter(|segment| !segment.is_empty())
+            .try_for_each(|segment| {
+                check_segment(segment)?;
+                path.push(segment);
+                Ok::<(), anyhow::Error>(())
+            })?;
+        path.push(format!("{}.{}", name, METADATA_EXTENSION));
+        self.check_within_root(&path)?;
+
+        Ok(path)
+    }
+
+    /// Symlinked directories could still lead out of the root, so the part of the path
+    /// that already exists is resolved and has to stay within it.
+    fn check_within_root(&self, path: &Path) -> anyhow::Result<()> {
+        let existing_path = path
+            .ancestors()
+            .find(|ancestor| ancestor.exists())
+            .unwrap_or(&self.root);
+        let resolved_path = fs::canonicalize(existing_path)
+            .with_context(|| format!("could not resolve {:?}", existing_path))?;
+        if !resolved_path.starts_with(&self.root) {
+            bail!("{:?} is outside of the template directory", path);
+        }
+
+        Ok(())
+    }
+
+    fn template_metadata_path(&self, template: &TemplateInputsEntity) -> anyhow::Result<PathBuf> {
+        self.metadata_path(
+            &template.tenant,
+            &template.namespace,
+            &template.name,
+            template.deleted_at,
+        )
+    }
+
+    /// Every write to a template gets a later timestamp than the write before it.
+    fn write_timestamp(&self) -> OffsetDateTime {
+        self.state.lock().unwrap().write_timestamp()
+    }
+
+    fn write(
+        &self,
+        path: &Path,
+        id: i64,
+        fields: TemplateFields<'_>,
+        cloned_from: Option<i64>,
+        created_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<()> {
+        let updated_at = self.write_timestamp();
+        let metadata = TemplateMetadata {
+            id: Some(id),
+            description: fields.description.to_string(),
+            engine: fields.engine.to_string(),
+            content_type: fields.content_type.to_string(),
+            inputs: fields
+                .inputs
+                .iter()
+                .map(|input| MetadataInput {
+                    name: input.name.clone(),
+                    default_value: input.default_value.clone(),
+                })
+                .collect(),
+            cloned_from,
+            created_at: Some(created_at.unwrap_or(updated_at)),
+            updated_at: Some(updated_at),
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("could not create the directory {:?}", parent))?;
+        }
+        fs::write(path, serde_yaml::to_string(&metadata)?)
+            .with_context(|| format!("could not write the template metadata {:?}", path))?;
+        fs::write(path.with_extension(BODY_EXTENSION), fields.body)
+            .with_context(|| format!("could not write the template body of {:?}", path))?;
+
+        self.reload()
+    }
+
+    /// Moves the files of a template, bumping its `updated_at` when `touch` is set as the
+    /// database backends do for renames and restores.
+    fn move_files(&self, from: &Path, to: &Path, touch: bool) -> anyhow::Result<()> {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("could not create the directory {:?}", parent))?;
+        }
+        fs::rename(from, to)
+            .with_context(|| format!("could not move the template metadata {:?}", from))?;
+        fs::rename(
+            from.with_extension(BODY_EXTENSION),
+            to.with_extension(BODY_EXTENSION),
+        )
+        .with_context(|| format!("could not move the template body of {:?}", from))?;
+        if touch {
+            self.touch(to)?;
+        }
+
+        self.reload()
+    }
+
+    /// Bumps the `updated_at` recorded in the template metadata without reloading.
+    fn touch(&self, path: &Path) -> anyhow::Result<()> {
+        let mut metadata = read_metadata(path)?;
+        metadata.updated_at = Some(self.write_timestamp());
+        fs::write(path, serde_yaml::to_string(&metadata)?)
+            .with_context(|| format!("could not write the template metadata {:?}", path))
+    }
+}
+
+/// Tenants, namespace segments and names become file and directory names, so they are
+/// limited to characters that cannot point anywhere else, such as `..`.
+fn check_segment(segment: &str) -> anyhow::Result<()> {
+    if segment.is_empty()
+        || !segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        bail!("{:?} cannot be stored in the template directory", segment);
+    }
+
+    Ok(())
+}
+
+fn read_dirs(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut dirs = vec![];
+    for entry in fs::read_dir(dir).with_context(|| format!("could not read {:?}", dir))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            dirs.push(path);
+        }
+    }
+
+    Ok(dirs)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn load_templates(
+    dir: &Path,
+    tenant: &str,
+    deleted_at: Option<OffsetDateTime>,
+    templates: &mut Vec<TemplateInputsEntity>,
+) -> anyhow::Result<()> {
+    let mut pending = vec![(dir.to_path_buf(), String::new())];
+    while let Some((dir, namespace)) = pending.pop() {
+        for entry in fs::read_dir(&dir).with_context(|| format!("could not read {:?}", &dir))? {
+            let path = entry?.path();
+            if path.is_dir() {
+                let child_namespace = match namespace.is_empty() {
+                    true => file_name(&path),
+                    false => format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, file_name(&path)),
+                };
+                pending.push((path, child_namespace));
+            } else if path.extension().and_then(|ext| ext.to_str()) == Some(METADATA_EXTENSION) {
+                templates.push(load_template(&path, tenant, &namespace, deleted_at)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn load_template(
+    path: &Path,
+    tenant: &str,
+    namespace: &str,
+    deleted_at: Option<OffsetDateTime>,
+) -> anyhow::Result<TemplateInputsEntity> {
+    let metadata = read_metadata(path)?;
+    let body_path = path.with_extension(BODY_EXTENSION);
+    let body = fs::read_to_string(&body_path).unwrap_or_default();
+
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    // Templates without an id get one once the whole tree is loaded.
+    let id = metadata.id.unwrap_or_default();
+    let modified_at = modified_at(path)?.max(modified_at(&body_path).unwrap_or(UNIX_EPOCH));
+    let updated_at = metadata
+        .updated_at
+        .unwrap_or_else(|| to_offset_date_time(modified_at));
+    let created_at = match metadata.created_at {
+        Some(created_at) => created_at,
+        None => to_offset_date_time(fs::metadata(path)?.created().unwrap_or(modified_at)),
+    };
+
+    Ok(TemplateInputsEntity {
+        id,
+        created_at,
+        updated_at,
+        name,
+        description: metadata.description,
+        body,
+        engine: metadata.engine,
+        content_type: metadata.content_type,
+        namespace: namespace.to_string(),
+        tenant: tenant.to_string(),
+        cloned_from: metadata.cloned_from,
+        deleted_at,
+        inputs: metadata
+            .inputs
+            .into_iter()
+            .enumerate()
+            .map(|(index, input)| InputEntity {
+                id: index as i64 + 1,
+                name: input.name,
+                default_value: input.default_value,
+                template_id: id,
+            })
+            .collect(),
+    })
+}
+
+fn modified_at(path: &Path) -> anyhow::Result<SystemTime> {
+    Ok(fs::metadata(path)?.modified()?)
+}
+
+fn read_metadata(path: &Path) -> anyhow::Result<TemplateMetadata> {
+    serde_yaml::from_str(
+        &fs::read_to_string(path).with_context(|| format!("could not read {:?}", path))?,
+    )
+    .with_context(|| format!("invalid template metadata {:?}", path))
+}
+
+/// Timestamps are kept to the microsecond, like Postgres does, for revisions to match.
+fn to_offset_date_time(time: SystemTime) -> OffsetDateTime {
+    let micros = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_micros() as i128)
+        .unwrap_or_default();
+    OffsetDateTime::from_unix_timestamp_nanos(micros * 1_000).unwrap_or(OffsetDateTime::UNIX_EPOCH)
+}
+
+fn format_micros(time: OffsetDateTime) -> String {
+    (time.unix_timestamp_nanos() / 1_000).to_string()
+}
+
+fn parse_micros(micros: &str) -> anyhow::Result<OffsetDateTime> {
+    let micros = micros
+        .parse::<i128>()
+        .with_context(|| format!("invalid trash directory {:?}", micros))?;
+    Ok(OffsetDateTime::from_unix_timestamp_nanos(micros * 1_000)?)
+}
+
+fn now() -> OffsetDateTime {
+    to_offset_date_time(SystemTime::now())
+}
+
+#[derive(Clone)]
+pub struct FileTemplateRepository {
+    store: Arc<FileTemplateStore>,
+}
+
+impl FileTemplateRepository {
+    pub fn new(store: Arc<FileTemplateStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TemplateRepositoryTrait for FileTemplateRepository {
+    async fn list_templates(
+        &self,
+        tenant: &str,
+        namespace: Option<&str>,
+        trashed: bool,
+        offset: i64,
+        limit: i64,
+    ) -> anyhow::Result<Vec<TemplateInputsEntity>> {
+        Ok(self
+            .store
+            .templates()
+            .into_iter()
+            .filter(|template| {
+                template.tenant == tenant
+                    && is_within_namespace(&template.namespace, namespace)
+                    && template.deleted_at.is_some() == trashed
+            })
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn get_templates_count(
+        &self,
+        tenant: &str,
+        namespace: Option<&str>,
+        trashed: bool,
+    ) -> anyhow::Result<i64> {
+        Ok(self
+            .list_templates(tenant, namespace, trashed, 0, i64::MAX)
+            .await?
+            .len() as i64)
+    }
+
+    async fn get_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        Ok(self.store.find_live(tenant, namespace, name))
+    }
+
+    async fn add_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        fields: TemplateFields<'_>,
+    ) -> anyhow::Result<TemplateInputsEntity> {
+        let _guard = self.store.write_lock.lock().unwrap();
+        if self.store.find_live(tenant, namespace, name).is_some() {
+            bail!("template {:?} already exists", name);
+        }
+
+        let path = self.store.metadata_path(tenant, namespace, name, None)?;
+        self.store
+            .write(&path, self.store.next_id()?, fields, None, None)?;
+
+        self.store
+            .find_live(tenant, namespace, name)
+            .context("an unexpected error occured while obtaining the newly created template")
+    }
+
+    async fn update_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        fields: TemplateFields<'_>,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let _guard = self.store.write_lock.lock().unwrap();
+        let template = match self.store.find_live(tenant, namespace, name) {
+            Some(template)
+                if expected_updated_at.is_none()
+                    || expected_updated_at == Some(template.updated_at) =>
+            {
+                template
+            }
+            _ => return Ok(None),
+        };
+
+        let path = self.store.template_metadata_path(&template)?;
+        self.store.write(
+            &path,
+            template.id,
+            fields,
+            template.cloned_from,
+            Some(template.created_at),
+        )?;
+
+        Ok(self.store.find_live(tenant, namespace, name))
+    }
+
+    async fn remove_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let _guard = self.store.write_lock.lock().unwrap();
+        let template = match self.store.find_live(tenant, namespace, name) {
+            Some(template)
+                if expected_updated_at.is_none()
+                    || expected_updated_at == Some(template.updated_at) =>
+            {
+                template
+            }
+            _ => return Ok(None),
+        };
+
+        self.store.move_files(
+            &self.store.template_metadata_path(&template)?,
+            &self
+                .store
+                .metadata_path(tenant, namespace, name, Some(now()))?,
+            false,
+        )?;
+
+        Ok(Some(template))
+    }
+
+    async fn restore_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let _guard = self.store.write_lock.lock().unwrap();
+        let trashed_template = self
+            .store
+            .templates()
+            .into_iter()
+            .filter(|template| {
+                template.tenant == tenant
+                    && template.namespace == namespace
+                    && template.name == name
+                    && template.deleted_at.is_some()
+            })
+            .max_by_key(|template| template.deleted_at);
+
+        match trashed_template {
+            Some(template) => {
+                if self.store.find_live(tenant, namespace, name).is_some() {
+                    bail!("template {:?} already exists", name);
+                }
+                self.store.move_files(
+                    &self.store.template_metadata_path(&template)?,
+                    &self.store.metadata_path(tenant, namespace, name, None)?,
+                    true,
+                )?;
+
+                Ok(self.store.find_live(tenant, namespace, name))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn purge_templates(&self, deleted_before: OffsetDateTime) -> anyhow::Result<u64> {
+        let _guard = self.store.write_lock.lock().unwrap();
+        let purged_templates = self
+            .store
+            .templates()
+            .into_iter()
+            .filter(|template| {
+                template
+                    .deleted_at
+                    .is_some_and(|deleted_at| deleted_at < deleted_before)
+            })
+            .collect::<Vec<TemplateInputsEntity>>();
+        let purged_ids = purged_templates
+            .iter()
+            .map(|template| template.id)
+            .collect::<Vec<i64>>();
+
+        let mut deleted_dirs = purged_templates
+            .iter()
+            .filter_map(|template| template.deleted_at)
+            .collect::<Vec<OffsetDateTime>>();
+        deleted_dirs.sort();
+        deleted_dirs.dedup();
+        for deleted_at in deleted_dirs {
+            let deleted_dir = self
+                .store
+                .root
+                .join(TRASH_DIR)
+                .join(format_micros(deleted_at));
+            fs::remove_dir_all(&deleted_dir)
+                .with_context(|| format!("could not purge {:?}", &deleted_dir))?;
+        }
+        self.store.update_state(|state| {
+            state.purge_templates(&purged_ids);
+            Ok(())
+        })?;
+        self.store.reload()?;
+
+        let clones = self.store.templates().into_iter().filter(|template| {
+            template
+                .cloned_from
+                .is_some_and(|cloned_from| purged_ids.contains(&cloned_from))
+        });
+        for clone in clones {
+            let path = self.store.template_metadata_path(&clone)?;
+            let mut metadata = read_metadata(&path)?;
+            metadata.cloned_from = None;
+            fs::write(&path, serde_yaml::to_string(&metadata)?)
+                .with_context(|| format!("could not write the template metadata {:?}", &path))?;
+        }
+        self.store.reload()?;
+
+        Ok(purged_templates.len() as u64)
+    }
+
+    async fn rename_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        new_namespace: &str,
+        new_name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let _guard = self.store.write_lock.lock().unwrap();
+        let template = match self.store.find_live(tenant, namespace, name) {
+            Some(template) => template,
+            None => return Ok(None),
+        };
+        if self
+            .store
+            .find_live(tenant, new_namespace, new_name)
+            .is_some()
+        {
+            bail!("template {:?} already exists", new_name);
+        }
+
+        self.store.move_files(
+            &self.store.template_metadata_path(&template)?,
+            &self
+                .store
+                .metadata_path(tenant, new_namespace, new_name, None)?,
+            true,
+        )?;
+
+        Ok(self.store.find_live(tenant, new_namespace, new_name))
+    }
+
+    async fn clone_template(
+        &self,
+        template_id: i64,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<TemplateInputsEntity> {
+        let _guard = self.store.write_lock.lock().unwrap();
+        let source_template = self
+            .store
+            .find(|template| template.id == template_id)
+            .context("an unexpected error occured while obtaining the template to clone")?;
+        if self.store.find_live(tenant, namespace, name).is_some() {
+            bail!("template {:?} already exists", name);
+        }
+
+        let inputs = source_template
+            .inputs
+            .into_iter()
+            .map(TemplateInput::from)
+            .collect::<Vec<TemplateInput>>();
+        self.store.write(
+            &self.store.metadata_path(tenant, namespace, name, None)?,
+            self.store.next_id()?,
+            TemplateFields {
+                description: &source_template.description,
+                body: &source_template.body,
+                engine: &source_template.engine,
+                content_type: &source_template.content_type,
+                inputs: &inputs,
+            },
+            Some(template_id),
+            None,
+        )?;
+
+        self.store
+            .find_live(tenant, namespace, name)
+            .context("an unexpected error occured while obtaining the cloned template")
+    }
+
+    async fn touch_template(&self, template_id: i64) -> anyhow::Result<()> {
+        let _guard = self.store.write_lock.lock().unwrap();
+        if let Some(template) = self.store.find(|template| template.id == template_id) {
+            self.store
+                .touch(&self.store.template_metadata_path(&template)?)?;
+            self.store.reload()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Inputs of the templates in a [`FileTemplateStore`], kept in the template metadata.
+#[derive(Clone)]
+pub struct FileInputRepository {
+    store: Arc<FileTemplateStore>,
+}
+
+impl FileInputRepository {
+    pub fn new(store: Arc<FileTemplateStore>) -> Self {
+        Self { store }
+    }
+
+    fn set_inputs(
+        &self,
+        template_id: i64,
+        update: impl FnOnce(&mut Vec<TemplateInput>),
+    ) -> anyhow::Result<TemplateInputsEntity> {
+        let _guard = self.store.write_lock.lock().unwrap();
+        let template = self
+            .store
+            .find(|template| template.id == template_id)
+            .with_context(|| format!("template {} does not exist", template_id))?;
+
+        let mut inputs = template
+            .inputs
+            .iter()
+            .cloned()
+            .map(TemplateInput::from)
+            .collect::<Vec<TemplateInput>>();
+        update(&mut inputs);
+        self.store.write(
+            &self.store.template_metadata_path(&template)?,
+            template.id,
+            TemplateFields {
+                description: &template.description,
+                body: &template.body,
+                engine: &template.engine,
+                content_type: &template.content_type,
+                inputs: &inputs,
+            },
+            template.cloned_from,
+            Some(template.created_at),
+        )?;
+
+        self.store
+            .find(|template| template.id == template_id)
+            .context("an unexpected error occured while obtaining the template inputs")
+    }
+}
+
+#[async_trait]
+impl InputRepositoryTrait for FileInputRepository {
+    async fn get_template_inputs(&self, template_id: i64) -> anyhow::Result<Vec<InputEntity>> {
+        Ok(self
+            .store
+            .find(|template| template.id == template_id)
+            .map(|template| template.inputs)
+            .unwrap_or_default())
+    }
+
+    async fn add_inputs(
+        &self,
+        inputs: &[TemplateInput],
+        template_id: i64,
+    ) -> anyhow::Result<Vec<InputEntity>> {
+        let existing_count = self.get_template_inputs(template_id).await?.len();
+        let template = self.set_inputs(template_id, |template_inputs| {
+            template_inputs.extend_from_slice(inputs)
+        })?;
+
+        Ok(template.inputs.into_iter().skip(existing_count).collect())
+    }
+
+    async fn remove_inputs(&self, template_id: i64) -> anyhow::Result<Option<InputEntity>> {
+        let removed_input = self
+            .get_template_inputs(template_id)
+            .await?
+            .into_iter()
+            .next();
+        self.set_inputs(template_id, |template_inputs| template_inputs.clear())?;
+
+        Ok(removed_input)
+    }
+}
+
+/// Overrides of the templates in a [`FileTemplateStore`], kept in its state file.
+#[derive(Clone)]
+pub struct FileTemplateOverrideRepository {
+    store: Arc<FileTemplateStore>,
+}
+
+impl FileTemplateOverrideRepository {
+    pub fn new(store: Arc<FileTemplateStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TemplateOverrideRepositoryTrait for FileTemplateOverrideRepository {
+    async fn get_overrides(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Vec<TemplateOverrideInputsEntity>> {
+        let mut overrides = self.store.read_state(|state| {
+            state
+                .overrides
+                .iter()
+                .filter(|template_override| template_override.template_id == template_id)
+                .map(|template_override| state.with_override_inputs(template_override))
+                .collect::<Vec<TemplateOverrideInputsEntity>>()
+        });
+        overrides.sort_by(|a, b| a.scope.cmp(&b.scope));
+
+        Ok(overrides)
+    }
+
+    async fn get_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+    ) -> anyhow::Result<Option<TemplateOverrideInputsEntity>> {
+        Ok(self.store.read_state(|state| {
+            state
+                .overrides
+                .iter()
+                .find(|template_override| {
+                    template_override.template_id == template_id && template_override.scope == scope
+                })
+                .map(|template_override| state.with_override_inputs(template_override))
+        }))
+    }
+
+    async fn set_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+        body: Option<&str>,
+        override_inputs: &[TemplateInput],
+    ) -> anyhow::Result<TemplateOverrideInputsEntity> {
+        self.store.check_template(template_id)?;
+        self.store.update_state(|state| {
+            let timestamp = state.write_timestamp();
+            let index = match state.overrides.iter().position(|template_override| {
+                template_override.template_id == template_id && template_override.scope == scope
+            }) {
+                Some(index) => {
+                    let template_override = &mut state.overrides[index];
+                    template_override.body = body.map(str::to_string);
+                    template_override.updated_at = timestamp;
+                    index
+                }
+                None => {
+                    let template_override = TemplateOverrideEntity {
+                        id: state.next_id(),
+                        scope: scope.to_string(),
+                        body: body.map(str::to_string),
+                        template_id,
+                        created_at: timestamp,
+                        updated_at: timestamp,
+                    };
+                    state.overrides.push(template_override);
+                    state.overrides.len() - 1
+                }
+            };
+
+            let override_id = state.overrides[index].id;
+            state
+                .override_inputs
+                .retain(|input| input.override_id != override_id);
+            for input in override_inputs {
+                let override_input = OverrideInputEntity {
+                    id: state.next_id(),
+                    name: input.name.clone(),
+                    default_value: input.default_value.clone(),
+                    override_id,
+                };
+                state.override_inputs.push(override_input);
+            }
+
+            Ok(state.with_override_inputs(&state.overrides[index]))
+        })
+    }
+
+    async fn remove_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+    ) -> anyhow::Result<Option<TemplateOverrideInputsEntity>> {
+        self.store.update_state(|state| {
+            let index = match state.overrides.iter().position(|template_override| {
+                template_override.template_id == template_id && template_override.scope == scope
+            }) {
+                Some(index) => index,
+                None => return Ok(None),
+            };
+
+            let removed_override = state.with_override_inputs(&state.overrides[index]);
+            state.overrides.remove(index);
+            state
+                .override_inputs
+                .retain(|input| input.override_id != removed_override.id);
+
+            Ok(Some(removed_override))
+        })
+    }
+}
+
+/// Locale variants of the templates in a [`FileTemplateStore`], kept in its state file.
+#[derive(Clone)]
+pub struct FileTemplateVariantRepository {
+    store: Arc<FileTemplateStore>,
+}
+
+impl FileTemplateVariantRepository {
+    pub fn new(store: Arc<FileTemplateStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TemplateVariantRepositoryTrait for FileTemplateVariantRepository {
+    async fn get_variants(&self, template_id: i64) -> anyhow::Result<Vec<TemplateVariantEntity>> {
+        Ok(self.store.read_state(|state| {
+            state
+                .variants
+                .iter()
+                .filter(|variant| variant.template_id == template_id)
+                .cloned()
+                .collect()
+        }))
+    }
+
+    async fn get_variant_locales(
+        &self,
+        template_ids: &[i64],
+    ) -> anyhow::Result<Vec<VariantLocaleEntity>> {
+        let mut locales = self.store.read_state(|state| {
+            state
+                .variants
+                .iter()
+                .filter(|variant| template_ids.contains(&variant.template_id))
+                .map(|variant| VariantLocaleEntity {
+                    template_id: variant.template_id,
+                    locale: variant.locale.clone(),
+                })
+                .collect::<Vec<VariantLocaleEntity>>()
+        });
+        locales.sort_by(|a, b| a.locale.cmp(&b.locale));
+
+        Ok(locales)
+    }
+
+    async fn set_variant(
+        &self,
+        template_id: i64,
+        locale: &str,
+        body: &str,
+    ) -> anyhow::Result<TemplateVariantEntity> {
+        self.store.check_template(template_id)?;
+        self.store.update_state(|state| {
+            let timestamp = state.write_timestamp();
+            match state
+                .variants
+                .iter_mut()
+                .find(|variant| variant.template_id == template_id && variant.locale == locale)
+            {
+                Some(variant) => {
+                    variant.body = body.to_string();
+                    variant.updated_at = timestamp;
+                    Ok(variant.clone())
+                }
+                None => {
+                    let variant = TemplateVariantEntity {
+                        id: state.next_id(),
+                        locale: locale.to_string(),
+                        body: body.to_string(),
+                        template_id,
+                        created_at: timestamp,
+                        updated_at: timestamp,
+                    };
+                    state.variants.push(variant.clone());
+                    Ok(variant)
+                }
+            }
+        })
+    }
+
+    async fn remove_variant(
+        &self,
+        template_id: i64,
+        locale: &str,
+    ) -> anyhow::Result<Option<TemplateVariantEntity>> {
+        self.store.update_state(|state| {
+            Ok(state
+                .variants
+                .iter()
+                .position(|variant| variant.template_id == template_id && variant.locale == locale)
+                .map(|index| state.variants.remove(index)))
+        })
+    }
+}
+
+/// JSON schemas of the templates in a [`FileTemplateStore`], kept in its state file.
+#[derive(Clone)]
+pub struct FileTemplateSchemaRepository {
+    store: Arc<FileTemplateStore>,
+}
+
+impl FileTemplateSchemaRepository {
+    pub fn new(store: Arc<FileTemplateStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TemplateSchemaRepositoryTrait for FileTemplateSchemaRepository {
+    async fn get_schema(&self, template_id: i64) -> anyhow::Result<Option<TemplateSchemaEntity>> {
+        Ok(self.store.read_state(|state| {
+            state
+                .schemas
+                .iter()
+                .find(|schema| schema.template_id == template_id)
+                .cloned()
+        }))
+    }
+
+    async fn set_schema(
+        &self,
+        template_id: i64,
+        json_schema: &str,
+    ) -> anyhow::Result<TemplateSchemaEntity> {
+        self.store.check_template(template_id)?;
+        self.store.update_state(|state| {
+            let timestamp = state.write_timestamp();
+            match state
+                .schemas
+                .iter_mut()
+                .find(|schema| schema.template_id == template_id)
+            {
+                Some(schema) => {
+                    schema.json_schema = json_schema.to_string();
+                    schema.updated_at = timestamp;
+                    Ok(schema.clone())
+                }
+                None => {
+                    let schema = TemplateSchemaEntity {
+                        id: state.next_id(),
+                        json_schema: json_schema.to_string(),
+                        template_id,
+                        created_at: timestamp,
+                        updated_at: timestamp,
+                    };
+                    state.schemas.push(schema.clone());
+                    Ok(schema)
+                }
+            }
+        })
+    }
+
+    async fn remove_schema(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Option<TemplateSchemaEntity>> {
+        self.store.update_state(|state| {
+            Ok(state
+                .schemas
+                .iter()
+                .position(|schema| schema.template_id == template_id)
+                .map(|index| state.schemas.remove(index)))
+        })
+    }
+}
+
+/// Settings of the templates in a [`FileTemplateStore`], kept in its state file.
+#[derive(Clone)]
+pub struct FileTemplateSettingsRepository {
+    store: Arc<FileTemplateStore>,
+}
+
+impl FileTemplateSettingsRepository {
+    pub fn new(store: Arc<FileTemplateStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TemplateSettingsRepositoryTrait for FileTemplateSettingsRepository {
+    async fn get_settings(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Option<TemplateSettingsEntity>> {
+        Ok(self.store.read_state(|state| {
+            state
+                .settings
+                .iter()
+                .find(|settings| settings.template_id == template_id)
+                .cloned()
+        }))
+    }
+
+    async fn set_settings(
+        &self,
+        template_id: i64,
+        settings: &str,
+    ) -> anyhow::Result<TemplateSettingsEntity> {
+        self.store.check_template(template_id)?;
+        self.store.update_state(|state| {
+            let timestamp = state.write_timestamp();
+            match state
+                .settings
+                .iter_mut()
+                .find(|existing| existing.template_id == template_id)
+            {
+                Some(existing) => {
+                    existing.settings = settings.to_string();
+                    existing.updated_at = timestamp;
+                    Ok(existing.clone())
+                }
+                None => {
+                    let entity = TemplateSettingsEntity {
+                        id: state.next_id(),
+                        settings: settings.to_string(),
+                        template_id,
+                        created_at: timestamp,
+                        updated_at: timestamp,
+                    };
+                    state.settings.push(entity.clone());
+                    Ok(entity)
+                }
+            }
+        })
+    }
+}
+
+/// Message catalog of the tenants in a [`FileTemplateStore`], kept in its state file.
+#[derive(Clone)]
+pub struct FileMessageRepository {
+    store: Arc<FileTemplateStore>,
+}
+
+impl FileMessageRepository {
+    pub fn new(store: Arc<FileTemplateStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl MessageRepositoryTrait for FileMessageRepository {
+    async fn list_messages(
+        &self,
+        tenant: &str,
+        offset: i64,
+        limit: i64,
+    ) -> anyhow::Result<Vec<MessageEntity>> {
+        let mut messages = self.store.read_state(|state| {
+            state
+                .messages
+                .iter()
+                .filter(|message| message.tenant == tenant)
+                .cloned()
+                .collect::<Vec<MessageEntity>>()
+        });
+        messages.sort_by(|a, b| (&a.key, &a.locale).cmp(&(&b.key, &b.locale)));
+
+        Ok(messages
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn get_messages_count(&self, tenant: &str) -> anyhow::Result<i64> {
+        Ok(self.store.read_state(|state| {
+            state
+                .messages
+                .iter()
+                .filter(|message| message.tenant == tenant)
+                .count() as i64
+        }))
+    }
+
+    async fn get_messages(
+        &self,
+        tenants: &[String],
+        locales: &[String],
+    ) -> anyhow::Result<Vec<MessageEntity>> {
+        Ok(self.store.read_state(|state| {
+            state
+                .messages
+                .iter()
+                .filter(|message| {
+                    tenants.contains(&message.tenant) && locales.contains(&message.locale)
+                })
+                .cloned()
+                .collect()
+        }))
+    }
+
+    async fn set_message(
+        &self,
+        tenant: &str,
+        key: &str,
+        locale: &str,
+        value: &str,
+    ) -> anyhow::Result<MessageEntity> {
+        self.store.update_state(|state| {
+            let timestamp = state.write_timestamp();
+            match state.messages.iter_mut().find(|message| {
+                message.tenant == tenant && message.key == key && message.locale == locale
+            }) {
+                Some(message) => {
+                    message.value = value.to_string();
+                    message.updated_at = timestamp;
+                    Ok(message.clone())
+                }
+                None => {
+                    let message = MessageEntity {
+                        id: state.next_id(),
+                        tenant: tenant.to_string(),
+                        key: key.to_string(),
+                        locale: locale.to_string(),
+                        value: value.to_string(),
+                        created_at: timestamp,
+                        updated_at: timestamp,
+                    };
+                    state.messages.push(message.clone());
+                    Ok(message)
+                }
+            }
+        })
+    }
+
+    async fn remove_message(
+        &self,
+        tenant: &str,
+        key: &str,
+        locale: &str,
+    ) -> anyhow::Result<Option<MessageEntity>> {
+        self.store.update_state(|state| {
+            Ok(state
+                .messages
+                .iter()
+                .position(|message| {
+                    message.tenant == tenant && message.key == key && message.locale == locale
+                })
+                .map(|index| state.messages.remove(index)))
+        })
+    }
+}
+
+/// Stylesheets of the tenants in a [`FileTemplateStore`], kept in its state file.
+#[derive(Clone)]
+pub struct FileStylesheetRepository {
+    store: Arc<FileTemplateStore>,
+}
+
+impl FileStylesheetRepository {
+    pub fn new(store: Arc<FileTemplateStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl StylesheetRepositoryTrait for FileStylesheetRepository {
+    async fn get_stylesheet(&self, tenant: &str) -> anyhow::Result<Option<StylesheetEntity>> {
+        Ok(self.store.read_state(|state| {
+            state
+                .stylesheets
+                .iter()
+                .find(|stylesheet| stylesheet.tenant == tenant)
+                .cloned()
+        }))
+    }
+
+    async fn set_stylesheet(&self, tenant: &str, css: &str) -> anyhow::Result<StylesheetEntity> {
+        self.store.update_state(|state| {
+            let timestamp = state.write_timestamp();
+            match state
+                .stylesheets
+                .iter_mut()
+                .find(|stylesheet| stylesheet.tenant == tenant)
+            {
+                Some(stylesheet) => {
+                    stylesheet.css = css.to_string();
+                    stylesheet.updated_at = timestamp;
+                    Ok(stylesheet.clone())
+                }
+                None => {
+                    let stylesheet = StylesheetEntity {
+                        id: state.next_id(),
+                        tenant: tenant.to_string(),
+                        css: css.to_string(),
+                        created_at: timestamp,
+                        updated_at: timestamp,
+                    };
+                    state.stylesheets.push(stylesheet.clone());
+                    Ok(stylesheet)
+                }
+            }
+        })
+    }
+
+    async fn remove_stylesheet(&self, tenant: &str) -> anyhow::Result<Option<StylesheetEntity>> {
+        self.store.update_state(|state| {
+            Ok(state
+                .stylesheets
+                .iter()
+                .position(|stylesheet| stylesheet.tenant == tenant)
+                .map(|index| state.stylesheets.remove(index)))
+        })
+    }
+}
+
+/// Idempotency keys of the tenants in a [`FileTemplateStore`], kept in its state file.
+#[derive(Clone)]
+pub struct FileIdempotencyKeyRepository {
+    store: Arc<FileTemplateStore>,
+}
+
+impl FileIdempotencyKeyRepository {
+    pub fn new(store: Arc<FileTemplateStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl IdempotencyKeyRepositoryTrait for FileIdempotencyKeyRepository {
+    async fn get_idempotency_key(
+        &self,
+        tenant: &str,
+        key: &str,
+    ) -> anyhow::Result<Option<IdempotencyKeyEntity>> {
+        Ok(self.store.read_state(|state| {
+            state
+                .idempotency_keys
+                .iter()
+                .find(|idempotency_key| {
+                    idempotency_key.tenant == tenant && idempotency_key.key == key
+                })
+                .cloned()
+        }))
+    }
+
+    async fn add_idempotency_key(
+        &self,
+        tenant: &str,
+        key: &str,
+        request: &[u8],
+        response: &[u8],
+    ) -> anyhow::Result<()> {
+        self.store.update_state(|state| {
+            if state.idempotency_keys.iter().any(|idempotency_key| {
+                idempotency_key.tenant == tenant && idempotency_key.key == key
+            }) {
+                return Ok(());
+            }
+
+            let idempotency_key = IdempotencyKeyEntity {
+                id: state.next_id(),
+                tenant: tenant.to_string(),
+                key: key.to_string(),
+                request: request.to_vec(),
+                response: response.to_vec(),
+                created_at: state.write_timestamp(),
+            };
+            state.idempotency_keys.push(idempotency_key);
+
+            Ok(())
+        })
+    }
+
+    async fn purge_idempotency_keys(&self, created_before: OffsetDateTime) -> anyhow::Result<u64> {
+        self.store.update_state(|state| {
+            let key_count = state.idempotency_keys.len();
+            state
+                .idempotency_keys
+                .retain(|idempotency_key| idempotency_key.created_at >= created_before);
+
+            Ok((key_count - state.idempotency_keys.len()) as u64)
+        })
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::repository::connection_pool::ServiceConnectionPool;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, types::time::OffsetDateTime, FromRow};
+
+/// The request fingerprint and encoded response of a create call made with an
+/// idempotency key.
+#[derive(FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct IdempotencyKeyEntity {
+    pub id: i64,
+    pub tenant: String,
+    pub key: String,
+    pub request: Vec<u8>,
+    pub response: Vec<u8>,
+    #[serde(with = "super::timestamp")]
+    pub created_at: OffsetDateTime,
+}
+
+#[async_trait]
+pub trait IdempotencyKeyRepositoryTrait {
+    async fn get_idempotency_key(
+        &self,
+        tenant: &str,
+        key: &str,
+    ) -> anyhow::Result<Option<IdempotencyKeyEntity>>;
+    /// Keeps the first response recorded for the key.
+    async fn add_idempotency_key(
+        &self,
+        tenant: &str,
+        key: &str,
+        request: &[u8],
+        response: &[u8],
+    ) -> anyhow::Result<()>;
+    async fn purge_idempotency_keys(&self, created_before: OffsetDateTime) -> anyhow::Result<u64>;
+}
+
+pub type DynIdempotencyKeyRepositoryTrait = Arc<dyn IdempotencyKeyRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct IdempotencyKeyRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl IdempotencyKeyRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IdempotencyKeyRepositoryTrait for IdempotencyKeyRepository {
+    async fn get_idempotency_key(
+        &self,
+        tenant: &str,
+        key: &str,
+    ) -> anyhow::Result<Option<IdempotencyKeyEntity>> {
+        query_as!(
+            IdempotencyKeyEntity,
+            r#"
+                select
+                    id,
+                    tenant,
+                    key,
+                    request,
+                    response,
+                    created_at
+                from idempotency_keys
+                where tenant = $1::varchar
+                    and key = $2::varchar
+            "#,
+            tenant,
+            key,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining the idempotency key")
+    }
+
+    async fn add_idempotency_key(
+        &self,
+        tenant: &str,
+        key: &str,
+        request: &[u8],
+        response: &[u8],
+    ) -> anyhow::Result<()> {
+        query!(
+            r#"
+                insert into idempotency_keys (
+                        tenant,
+                        key,
+                        request,
+                        response
+                    )
+                values (
+                        $1::varchar,
+                        $2::varchar,
+                        $3::bytea,
+                        $4::bytea
+                    )
+                on conflict (tenant, key) do nothing
+            "#,
+            tenant,
+            key,
+            request,
+            response,
+        )
+        .execute(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the idempotency key")?;
+
+        Ok(())
+    }
+
+    async fn purge_idempotency_keys(&self, created_before: OffsetDateTime) -> anyhow::Result<u64> {
+        let purge_result = query!(
+            r#"
+                delete from idempotency_keys
+                where created_at < $1::timestamptz
+            "#,
+            created_before,
+        )
+        .execute(&self.pool)
+        .await
+        .context("an unexpected error occured while purging idempotency keys")?;
+
+        Ok(purge_result.rows_affected())
+    }
+}
@@ -6,9 +6,10 @@ use madtofan_microservice_common::{
     repository::connection_pool::ServiceConnectionPool, templating::TemplateInput,
 };
 use mockall::automock;
-use sqlx::{query_as, FromRow, Type};
+use serde::Deserialize;
+use sqlx::{query_as, FromRow, PgExecutor, Type};
 
-#[derive(FromRow, Type, Debug, Eq, PartialEq, Clone)]
+#[derive(FromRow, Type, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct InputEntity {
     pub id: i64,
     pub name: String,
@@ -28,7 +29,7 @@ impl From<InputEntity> for TemplateInput {
 #[automock]
 #[async_trait]
 pub trait InputRepositoryTrait {
-    async fn get_template_inputs(&self, template_name: &str) -> anyhow::Result<Vec<InputEntity>>;
+    async fn get_template_inputs(&self, template_id: i64) -> anyhow::Result<Vec<InputEntity>>;
     async fn add_inputs(
         &self,
         inputs: &[TemplateInput],
@@ -52,7 +53,7 @@ impl InputRepository {
 
 #[async_trait]
 impl InputRepositoryTrait for InputRepository {
-    async fn get_template_inputs(&self, template_name: &str) -> anyhow::Result<Vec<InputEntity>> {
+    async fn get_template_inputs(&self, template_id: i64) -> anyhow::Result<Vec<InputEntity>> {
         query_as!(
             InputEntity,
             r#"
@@ -62,11 +63,9 @@ impl InputRepositoryTrait for InputRepository {
                     i.default_value as default_value,
                     i.template_id as template_id
                 from inputs as i
-                join templates as t
-                on i.template_id = t.id
-                where t.name = $1::varchar
+                where i.template_id = $1::bigint
             "#,
-            template_name
+            template_id
         )
         .fetch_all(&self.pool)
         .await
@@ -78,17 +77,35 @@ impl InputRepositoryTrait for InputRepository {
         inputs: &[TemplateInput],
         template_id: i64,
     ) -> anyhow::Result<Vec<InputEntity>> {
-        let mut names: Vec<String> = Vec::new();
-        let mut default_values: Vec<String> = Vec::new();
-        let mut template_ids: Vec<i64> = Vec::new();
-        inputs.to_owned().iter().cloned().for_each(|template| {
-            names.push(template.name);
-            default_values.push(template.default_value);
-            template_ids.push(template_id);
-        });
-        query_as!(
-            InputEntity,
-            r#"
+        insert_inputs(&self.pool, inputs, template_id).await
+    }
+
+    async fn remove_inputs(&self, template_id: i64) -> anyhow::Result<Option<InputEntity>> {
+        delete_inputs(&self.pool, template_id).await
+    }
+}
+
+/// Inserts the inputs of the template, on the pool or on the transaction of a write
+/// that replaces them along with the template.
+pub(super) async fn insert_inputs<'e, E>(
+    executor: E,
+    inputs: &[TemplateInput],
+    template_id: i64,
+) -> anyhow::Result<Vec<InputEntity>>
+where
+    E: PgExecutor<'e>,
+{
+    let mut names: Vec<String> = Vec::new();
+    let mut default_values: Vec<String> = Vec::new();
+    let mut template_ids: Vec<i64> = Vec::new();
+    inputs.to_owned().iter().cloned().for_each(|template| {
+        names.push(template.name);
+        default_values.push(template.default_value);
+        template_ids.push(template_id);
+    });
+    query_as!(
+        InputEntity,
+        r#"
                 insert into inputs (
                         name,
                         default_value,
@@ -101,28 +118,33 @@ impl InputRepositoryTrait for InputRepository {
                     )
                 returning *
             "#,
-            &names,
-            &default_values,
-            &template_ids
-        )
-        .fetch_all(&self.pool)
-        .await
-        .context("an unexpected error occured while creating the input")
-    }
+        &names,
+        &default_values,
+        &template_ids
+    )
+    .fetch_all(executor)
+    .await
+    .context("an unexpected error occured while creating the input")
+}
 
-    async fn remove_inputs(&self, template_id: i64) -> anyhow::Result<Option<InputEntity>> {
-        query_as!(
-            InputEntity,
-            r#"
+pub(super) async fn delete_inputs<'e, E>(
+    executor: E,
+    template_id: i64,
+) -> anyhow::Result<Option<InputEntity>>
+where
+    E: PgExecutor<'e>,
+{
+    query_as!(
+        InputEntity,
+        r#"
                 delete from inputs 
                 where 
                     template_id = $1::bigint 
                 returning *
             "#,
-            template_id,
-        )
-        .fetch_optional(&self.pool)
-        .await
-        .context("an unexpected error occured while removing the input")
-    }
+        template_id,
+    )
+    .fetch_optional(executor)
+    .await
+    .context("an unexpected error occured while removing the input")
 }
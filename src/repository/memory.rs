@@ -0,0 +1,1156 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use madtofan_microservice_common::templating::TemplateInput;
+use sqlx::types::time::OffsetDateTime;
+
+use crate::service::namespace::is_within_namespace;
+
+use super::{
+    idempotency_key::{IdempotencyKeyEntity, IdempotencyKeyRepositoryTrait},
+    input::{InputEntity, InputRepositoryTrait},
+    message::{MessageEntity, MessageRepositoryTrait},
+    stylesheet::{StylesheetEntity, StylesheetRepositoryTrait},
+    template::{TemplateEntity, TemplateFields, TemplateInputsEntity, TemplateRepositoryTrait},
+    template_override::{
+        OverrideInputEntity, TemplateOverrideEntity, TemplateOverrideInputsEntity,
+        TemplateOverrideRepositoryTrait,
+    },
+    template_schema::{TemplateSchemaEntity, TemplateSchemaRepositoryTrait},
+    template_settings::{TemplateSettingsEntity, TemplateSettingsRepositoryTrait},
+    template_variant::{
+        TemplateVariantEntity, TemplateVariantRepositoryTrait, VariantLocaleEntity,
+    },
+};
+
+#[derive(Default)]
+struct MemoryState {
+    templates: Vec<TemplateEntity>,
+    inputs: Vec<InputEntity>,
+    overrides: Vec<TemplateOverrideEntity>,
+    override_inputs: Vec<OverrideInputEntity>,
+    variants: Vec<TemplateVariantEntity>,
+    schemas: Vec<TemplateSchemaEntity>,
+    settings: Vec<TemplateSettingsEntity>,
+    messages: Vec<MessageEntity>,
+    stylesheets: Vec<StylesheetEntity>,
+    idempotency_keys: Vec<IdempotencyKeyEntity>,
+    next_id: i64,
+    last_write_at: Option<OffsetDateTime>,
+}
+
+impl MemoryState {
+    /// Timestamps are kept to the microsecond like Postgres does, and every write gets a
+    /// later one than the previous write so revisions always change.
+    fn write_timestamp(&mut self) -> OffsetDateTime {
+        let now = OffsetDateTime::now_utc();
+        let mut timestamp =
+            OffsetDateTime::from_unix_timestamp_nanos(now.unix_timestamp_nanos() / 1_000 * 1_000)
+                .unwrap_or(now);
+        if let Some(last_write_at) = self.last_write_at {
+            if timestamp <= last_write_at {
+                timestamp = last_write_at + Duration::from_micros(1);
+            }
+        }
+        self.last_write_at = Some(timestamp);
+        timestamp
+    }
+
+    fn find_live(&self, tenant: &str, namespace: &str, name: &str) -> Option<usize> {
+        self.templates.iter().position(|template| {
+            template.tenant == tenant
+                && template.namespace == namespace
+                && template.name == name
+                && template.deleted_at.is_none()
+        })
+    }
+
+    fn with_inputs(&self, template: &TemplateEntity) -> TemplateInputsEntity {
+        TemplateInputsEntity {
+            id: template.id,
+            created_at: template.created_at,
+            updated_at: template.updated_at,
+            name: template.name.clone(),
+            description: template.description.clone(),
+            body: template.body.clone(),
+            engine: template.engine.clone(),
+            content_type: template.content_type.clone(),
+            namespace: template.namespace.clone(),
+            tenant: template.tenant.clone(),
+            cloned_from: template.cloned_from,
+            deleted_at: template.deleted_at,
+            inputs: self
+                .inputs
+                .iter()
+                .filter(|input| input.template_id == template.id)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    fn add_inputs(&mut self, inputs: &[TemplateInput], template_id: i64) -> Vec<InputEntity> {
+        let added_inputs = inputs
+            .iter()
+            .map(|input| {
+                self.next_id += 1;
+                InputEntity {
+                    id: self.next_id,
+                    name: input.name.clone(),
+                    default_value: input.default_value.clone(),
+                    template_id,
+                }
+            })
+            .collect::<Vec<InputEntity>>();
+        self.inputs.extend(added_inputs.iter().cloned());
+        added_inputs
+    }
+
+    fn with_override_inputs(
+        &self,
+        template_override: &TemplateOverrideEntity,
+    ) -> TemplateOverrideInputsEntity {
+        TemplateOverrideInputsEntity::new(
+            template_override.clone(),
+            self.override_inputs
+                .iter()
+                .filter(|input| input.override_id == template_override.id)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    fn remove_inputs(&mut self, template_id: i64) -> Option<InputEntity> {
+        let removed_input = self
+            .inputs
+            .iter()
+            .find(|input| input.template_id == template_id)
+            .cloned();
+        self.inputs.retain(|input| input.template_id != template_id);
+        removed_input
+    }
+}
+
+/// Everything the repositories store, kept in memory and shared by the in-memory
+/// repositories with the same semantics as the Postgres ones: names are unique among
+/// live templates of a tenant and namespace, and purging a template removes its
+/// inputs, overrides, variants, schema and settings.
+#[derive(Default)]
+pub struct InMemoryStore {
+    state: Mutex<MemoryState>,
+}
+
+impl InMemoryStore {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+}
+
+#[derive(Clone)]
+pub struct InMemoryTemplateRepository {
+    store: Arc<InMemoryStore>,
+}
+
+impl InMemoryTemplateRepository {
+    pub fn new(store: Arc<InMemoryStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TemplateRepositoryTrait for InMemoryTemplateRepository {
+    async fn list_templates(
+        &self,
+        tenant: &str,
+        namespace: Option<&str>,
+        trashed: bool,
+        offset: i64,
+        limit: i64,
+    ) -> anyhow::Result<Vec<TemplateInputsEntity>> {
+        let state = self.store.state.lock().unwrap();
+        let mut templates = state
+            .templates
+            .iter()
+            .filter(|template| {
+                template.tenant == tenant
+                    && is_within_namespace(&template.namespace, namespace)
+                    && template.deleted_at.is_some() == trashed
+            })
+            .collect::<Vec<_>>();
+        templates.sort_by(|a, b| (&a.namespace, &a.name, a.id).cmp(&(&b.namespace, &b.name, b.id)));
+        Ok(templates
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|template| state.with_inputs(template))
+            .collect())
+    }
+
+    async fn get_templates_count(
+        &self,
+        tenant: &str,
+        namespace: Option<&str>,
+        trashed: bool,
+    ) -> anyhow::Result<i64> {
+        Ok(self
+            .list_templates(tenant, namespace, trashed, 0, i64::MAX)
+            .await?
+            .len() as i64)
+    }
+
+    async fn get_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let state = self.store.state.lock().unwrap();
+        Ok(state
+            .find_live(tenant, namespace, name)
+            .map(|index| state.with_inputs(&state.templates[index])))
+    }
+
+    async fn add_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        fields: TemplateFields<'_>,
+    ) -> anyhow::Result<TemplateInputsEntity> {
+        let mut state = self.store.state.lock().unwrap();
+        if state.find_live(tenant, namespace, name).is_some() {
+            bail!("template {:?} already exists", name);
+        }
+
+        let timestamp = state.write_timestamp();
+        state.next_id += 1;
+        let template = TemplateEntity {
+            id: state.next_id,
+            created_at: timestamp,
+            updated_at: timestamp,
+            name: name.to_string(),
+            description: fields.description.to_string(),
+            body: fields.body.to_string(),
+            engine: fields.engine.to_string(),
+            content_type: fields.content_type.to_string(),
+            namespace: namespace.to_string(),
+            tenant: tenant.to_string(),
+            cloned_from: None,
+            deleted_at: None,
+        };
+        state.add_inputs(fields.inputs, template.id);
+        let created_template = state.with_inputs(&template);
+        state.templates.push(template);
+
+        Ok(created_template)
+    }
+
+    async fn update_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        fields: TemplateFields<'_>,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let mut state = self.store.state.lock().unwrap();
+        let index = match state.find_live(tenant, namespace, name) {
+            Some(index)
+                if expected_updated_at.is_none()
+                    || expected_updated_at == Some(state.templates[index].updated_at) =>
+            {
+                index
+            }
+            _ => return Ok(None),
+        };
+
+        let timestamp = state.write_timestamp();
+        let template = &mut state.templates[index];
+        template.description = fields.description.to_string();
+        template.body = fields.body.to_string();
+        template.engine = fields.engine.to_string();
+        template.content_type = fields.content_type.to_string();
+        template.updated_at = timestamp;
+        let template_id = template.id;
+        state.remove_inputs(template_id);
+        state.add_inputs(fields.inputs, template_id);
+
+        Ok(Some(state.with_inputs(&state.templates[index])))
+    }
+
+    async fn remove_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let mut state = self.store.state.lock().unwrap();
+        let index = match state.find_live(tenant, namespace, name) {
+            Some(index)
+                if expected_updated_at.is_none()
+                    || expected_updated_at == Some(state.templates[index].updated_at) =>
+            {
+                index
+            }
+            _ => return Ok(None),
+        };
+
+        let removed_template = state.with_inputs(&state.templates[index]);
+        let timestamp = state.write_timestamp();
+        state.templates[index].deleted_at = Some(timestamp);
+
+        Ok(Some(removed_template))
+    }
+
+    async fn restore_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let mut state = self.store.state.lock().unwrap();
+        let index = state
+            .templates
+            .iter()
+            .enumerate()
+            .filter(|(_, template)| {
+                template.tenant == tenant
+                    && template.namespace == namespace
+                    && template.name == name
+                    && template.deleted_at.is_some()
+            })
+            .max_by_key(|(_, template)| template.deleted_at)
+            .map(|(index, _)| index);
+
+        match index {
+            Some(index) => {
+                if state.find_live(tenant, namespace, name).is_some() {
+                    bail!("template {:?} already exists", name);
+                }
+                let timestamp = state.write_timestamp();
+                state.templates[index].deleted_at = None;
+                state.templates[index].updated_at = timestamp;
+
+                Ok(Some(state.with_inputs(&state.templates[index])))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn purge_templates(&self, deleted_before: OffsetDateTime) -> anyhow::Result<u64> {
+        let mut state = self.store.state.lock().unwrap();
+        let purged_ids = state
+            .templates
+            .iter()
+            .filter(|template| {
+                template
+                    .deleted_at
+                    .is_some_and(|deleted_at| deleted_at < deleted_before)
+            })
+            .map(|template| template.id)
+            .collect::<Vec<i64>>();
+
+        state
+            .templates
+            .retain(|template| !purged_ids.contains(&template.id));
+        state
+            .inputs
+            .retain(|input| !purged_ids.contains(&input.template_id));
+        let purged_override_ids = state
+            .overrides
+            .iter()
+            .filter(|template_override| purged_ids.contains(&template_override.template_id))
+            .map(|template_override| template_override.id)
+            .collect::<Vec<i64>>();
+        state
+            .overrides
+            .retain(|template_override| !purged_override_ids.contains(&template_override.id));
+        state
+            .override_inputs
+            .retain(|input| !purged_override_ids.contains(&input.override_id));
+        state
+            .variants
+            .retain(|variant| !purged_ids.contains(&variant.template_id));
+        state
+            .schemas
+            .retain(|schema| !purged_ids.contains(&schema.template_id));
+        state
+            .settings
+            .retain(|settings| !purged_ids.contains(&settings.template_id));
+        state.templates.iter_mut().for_each(|template| {
+            if template
+                .cloned_from
+                .is_some_and(|cloned_from| purged_ids.contains(&cloned_from))
+            {
+                template.cloned_from = None;
+            }
+        });
+
+        Ok(purged_ids.len() as u64)
+    }
+
+    async fn rename_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        new_namespace: &str,
+        new_name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let mut state = self.store.state.lock().unwrap();
+        let index = match state.find_live(tenant, namespace, name) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        if state.find_live(tenant, new_namespace, new_name).is_some() {
+            bail!("template {:?} already exists", new_name);
+        }
+
+        let timestamp = state.write_timestamp();
+        let template = &mut state.templates[index];
+        template.namespace = new_namespace.to_string();
+        template.name = new_name.to_string();
+        template.updated_at = timestamp;
+
+        Ok(Some(state.with_inputs(&state.templates[index])))
+    }
+
+    async fn clone_template(
+        &self,
+        template_id: i64,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<TemplateInputsEntity> {
+        let mut state = self.store.state.lock().unwrap();
+        let source_template = state
+            .templates
+            .iter()
+            .find(|template| template.id == template_id)
+            .map(|template| state.with_inputs(template))
+            .context("an unexpected error occured while cloning the template")?;
+        if state.find_live(tenant, namespace, name).is_some() {
+            bail!("template {:?} already exists", name);
+        }
+
+        let timestamp = state.write_timestamp();
+        state.next_id += 1;
+        let template = TemplateEntity {
+            id: state.next_id,
+            created_at: timestamp,
+            updated_at: timestamp,
+            name: name.to_string(),
+            description: source_template.description,
+            body: source_template.body,
+            engine: source_template.engine,
+            content_type: source_template.content_type,
+            namespace: namespace.to_string(),
+            tenant: tenant.to_string(),
+            cloned_from: Some(template_id),
+            deleted_at: None,
+        };
+        let inputs = source_template
+            .inputs
+            .into_iter()
+            .map(TemplateInput::from)
+            .collect::<Vec<TemplateInput>>();
+        state.add_inputs(&inputs, template.id);
+        let cloned_template = state.with_inputs(&template);
+        state.templates.push(template);
+
+        Ok(cloned_template)
+    }
+
+    async fn touch_template(&self, template_id: i64) -> anyhow::Result<()> {
+        let mut state = self.store.state.lock().unwrap();
+        let timestamp = state.write_timestamp();
+        if let Some(template) = state
+            .templates
+            .iter_mut()
+            .find(|template| template.id == template_id)
+        {
+            template.updated_at = timestamp;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct InMemoryInputRepository {
+    store: Arc<InMemoryStore>,
+}
+
+impl InMemoryInputRepository {
+    pub fn new(store: Arc<InMemoryStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl InputRepositoryTrait for InMemoryInputRepository {
+    async fn get_template_inputs(&self, template_id: i64) -> anyhow::Result<Vec<InputEntity>> {
+        Ok(self
+            .store
+            .state
+            .lock()
+            .unwrap()
+            .inputs
+            .iter()
+            .filter(|input| input.template_id == template_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn add_inputs(
+        &self,
+        inputs: &[TemplateInput],
+        template_id: i64,
+    ) -> anyhow::Result<Vec<InputEntity>> {
+        Ok(self
+            .store
+            .state
+            .lock()
+            .unwrap()
+            .add_inputs(inputs, template_id))
+    }
+
+    async fn remove_inputs(&self, template_id: i64) -> anyhow::Result<Option<InputEntity>> {
+        Ok(self.store.state.lock().unwrap().remove_inputs(template_id))
+    }
+}
+
+#[derive(Clone)]
+pub struct InMemoryTemplateOverrideRepository {
+    store: Arc<InMemoryStore>,
+}
+
+impl InMemoryTemplateOverrideRepository {
+    pub fn new(store: Arc<InMemoryStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TemplateOverrideRepositoryTrait for InMemoryTemplateOverrideRepository {
+    async fn get_overrides(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Vec<TemplateOverrideInputsEntity>> {
+        let state = self.store.state.lock().unwrap();
+        let mut overrides = state
+            .overrides
+            .iter()
+            .filter(|template_override| template_override.template_id == template_id)
+            .map(|template_override| state.with_override_inputs(template_override))
+            .collect::<Vec<TemplateOverrideInputsEntity>>();
+        overrides.sort_by(|a, b| a.scope.cmp(&b.scope));
+
+        Ok(overrides)
+    }
+
+    async fn get_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+    ) -> anyhow::Result<Option<TemplateOverrideInputsEntity>> {
+        let state = self.store.state.lock().unwrap();
+        Ok(state
+            .overrides
+            .iter()
+            .find(|template_override| {
+                template_override.template_id == template_id && template_override.scope == scope
+            })
+            .map(|template_override| state.with_override_inputs(template_override)))
+    }
+
+    async fn set_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+        body: Option<&str>,
+        override_inputs: &[TemplateInput],
+    ) -> anyhow::Result<TemplateOverrideInputsEntity> {
+        let mut state = self.store.state.lock().unwrap();
+        if !state
+            .templates
+            .iter()
+            .any(|template| template.id == template_id)
+        {
+            bail!("template {} does not exist", template_id);
+        }
+
+        let timestamp = state.write_timestamp();
+        let index = match state.overrides.iter().position(|template_override| {
+            template_override.template_id == template_id && template_override.scope == scope
+        }) {
+            Some(index) => {
+                let template_override = &mut state.overrides[index];
+                template_override.body = body.map(str::to_string);
+                template_override.updated_at = timestamp;
+                index
+            }
+            None => {
+                state.next_id += 1;
+                let template_override = TemplateOverrideEntity {
+                    id: state.next_id,
+                    scope: scope.to_string(),
+                    body: body.map(str::to_string),
+                    template_id,
+                    created_at: timestamp,
+                    updated_at: timestamp,
+                };
+                state.overrides.push(template_override);
+                state.overrides.len() - 1
+            }
+        };
+
+        let override_id = state.overrides[index].id;
+        state
+            .override_inputs
+            .retain(|input| input.override_id != override_id);
+        for input in override_inputs {
+            state.next_id += 1;
+            let override_input = OverrideInputEntity {
+                id: state.next_id,
+                name: input.name.clone(),
+                default_value: input.default_value.clone(),
+                override_id,
+            };
+            state.override_inputs.push(override_input);
+        }
+
+        Ok(state.with_override_inputs(&state.overrides[index]))
+    }
+
+    async fn remove_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+    ) -> anyhow::Result<Option<TemplateOverrideInputsEntity>> {
+        let mut state = self.store.state.lock().unwrap();
+        let index = match state.overrides.iter().position(|template_override| {
+            template_override.template_id == template_id && template_override.scope == scope
+        }) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+
+        let removed_override = state.with_override_inputs(&state.overrides[index]);
+        state.overrides.remove(index);
+        state
+            .override_inputs
+            .retain(|input| input.override_id != removed_override.id);
+
+        Ok(Some(removed_override))
+    }
+}
+
+#[derive(Clone)]
+pub struct InMemoryTemplateVariantRepository {
+    store: Arc<InMemoryStore>,
+}
+
+impl InMemoryTemplateVariantRepository {
+    pub fn new(store: Arc<InMemoryStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TemplateVariantRepositoryTrait for InMemoryTemplateVariantRepository {
+    async fn get_variants(&self, template_id: i64) -> anyhow::Result<Vec<TemplateVariantEntity>> {
+        Ok(self
+            .store
+            .state
+            .lock()
+            .unwrap()
+            .variants
+            .iter()
+            .filter(|variant| variant.template_id == template_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_variant_locales(
+        &self,
+        template_ids: &[i64],
+    ) -> anyhow::Result<Vec<VariantLocaleEntity>> {
+        let mut locales = self
+            .store
+            .state
+            .lock()
+            .unwrap()
+            .variants
+            .iter()
+            .filter(|variant| template_ids.contains(&variant.template_id))
+            .map(|variant| VariantLocaleEntity {
+                template_id: variant.template_id,
+                locale: variant.locale.clone(),
+            })
+            .collect::<Vec<VariantLocaleEntity>>();
+        locales.sort_by(|a, b| a.locale.cmp(&b.locale));
+
+        Ok(locales)
+    }
+
+    async fn set_variant(
+        &self,
+        template_id: i64,
+        locale: &str,
+        body: &str,
+    ) -> anyhow::Result<TemplateVariantEntity> {
+        let mut state = self.store.state.lock().unwrap();
+        if !state
+            .templates
+            .iter()
+            .any(|template| template.id == template_id)
+        {
+            bail!("template {} does not exist", template_id);
+        }
+
+        let timestamp = state.write_timestamp();
+        match state
+            .variants
+            .iter_mut()
+            .find(|variant| variant.template_id == template_id && variant.locale == locale)
+        {
+            Some(variant) => {
+                variant.body = body.to_string();
+                variant.updated_at = timestamp;
+                Ok(variant.clone())
+            }
+            None => {
+                state.next_id += 1;
+                let variant = TemplateVariantEntity {
+                    id: state.next_id,
+                    locale: locale.to_string(),
+                    body: body.to_string(),
+                    template_id,
+                    created_at: timestamp,
+                    updated_at: timestamp,
+                };
+                state.variants.push(variant.clone());
+                Ok(variant)
+            }
+        }
+    }
+
+    async fn remove_variant(
+        &self,
+        template_id: i64,
+        locale: &str,
+    ) -> anyhow::Result<Option<TemplateVariantEntity>> {
+        let mut state = self.store.state.lock().unwrap();
+        Ok(state
+            .variants
+            .iter()
+            .position(|variant| variant.template_id == template_id && variant.locale == locale)
+            .map(|index| state.variants.remove(index)))
+    }
+}
+
+#[derive(Clone)]
+pub struct InMemoryTemplateSchemaRepository {
+    store: Arc<InMemoryStore>,
+}
+
+impl InMemoryTemplateSchemaRepository {
+    pub fn new(store: Arc<InMemoryStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TemplateSchemaRepositoryTrait for InMemoryTemplateSchemaRepository {
+    async fn get_schema(&self, template_id: i64) -> anyhow::Result<Option<TemplateSchemaEntity>> {
+        Ok(self
+            .store
+            .state
+            .lock()
+            .unwrap()
+            .schemas
+            .iter()
+            .find(|schema| schema.template_id == template_id)
+            .cloned())
+    }
+
+    async fn set_schema(
+        &self,
+        template_id: i64,
+        json_schema: &str,
+    ) -> anyhow::Result<TemplateSchemaEntity> {
+        let mut state = self.store.state.lock().unwrap();
+        if !state
+            .templates
+            .iter()
+            .any(|template| template.id == template_id)
+        {
+            bail!("template {} does not exist", template_id);
+        }
+
+        let timestamp = state.write_timestamp();
+        match state
+            .schemas
+            .iter_mut()
+            .find(|schema| schema.template_id == template_id)
+        {
+            Some(schema) => {
+                schema.json_schema = json_schema.to_string();
+                schema.updated_at = timestamp;
+                Ok(schema.clone())
+            }
+            None => {
+                state.next_id += 1;
+                let schema = TemplateSchemaEntity {
+                    id: state.next_id,
+                    json_schema: json_schema.to_string(),
+                    template_id,
+                    created_at: timestamp,
+                    updated_at: timestamp,
+                };
+                state.schemas.push(schema.clone());
+                Ok(schema)
+            }
+        }
+    }
+
+    async fn remove_schema(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Option<TemplateSchemaEntity>> {
+        let mut state = self.store.state.lock().unwrap();
+        Ok(state
+            .schemas
+            .iter()
+            .position(|schema| schema.template_id == template_id)
+            .map(|index| state.schemas.remove(index)))
+    }
+}
+
+#[derive(Clone)]
+pub struct InMemoryTemplateSettingsRepository {
+    store: Arc<InMemoryStore>,
+}
+
+impl InMemoryTemplateSettingsRepository {
+    pub fn new(store: Arc<InMemoryStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TemplateSettingsRepositoryTrait for InMemoryTemplateSettingsRepository {
+    async fn get_settings(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Option<TemplateSettingsEntity>> {
+        Ok(self
+            .store
+            .state
+            .lock()
+            .unwrap()
+            .settings
+            .iter()
+            .find(|settings| settings.template_id == template_id)
+            .cloned())
+    }
+
+    async fn set_settings(
+        &self,
+        template_id: i64,
+        settings: &str,
+    ) -> anyhow::Result<TemplateSettingsEntity> {
+        let mut state = self.store.state.lock().unwrap();
+        if !state
+            .templates
+            .iter()
+            .any(|template| template.id == template_id)
+        {
+            bail!("template {} does not exist", template_id);
+        }
+
+        let timestamp = state.write_timestamp();
+        match state
+            .settings
+            .iter_mut()
+            .find(|existing| existing.template_id == template_id)
+        {
+            Some(existing) => {
+                existing.settings = settings.to_string();
+                existing.updated_at = timestamp;
+                Ok(existing.clone())
+            }
+            None => {
+                state.next_id += 1;
+                let entity = TemplateSettingsEntity {
+                    id: state.next_id,
+                    settings: settings.to_string(),
+                    template_id,
+                    created_at: timestamp,
+                    updated_at: timestamp,
+                };
+                state.settings.push(entity.clone());
+                Ok(entity)
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct InMemoryMessageRepository {
+    store: Arc<InMemoryStore>,
+}
+
+impl InMemoryMessageRepository {
+    pub fn new(store: Arc<InMemoryStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl MessageRepositoryTrait for InMemoryMessageRepository {
+    async fn list_messages(
+        &self,
+        tenant: &str,
+        offset: i64,
+        limit: i64,
+    ) -> anyhow::Result<Vec<MessageEntity>> {
+        let mut messages = self
+            .store
+            .state
+            .lock()
+            .unwrap()
+            .messages
+            .iter()
+            .filter(|message| message.tenant == tenant)
+            .cloned()
+            .collect::<Vec<MessageEntity>>();
+        messages.sort_by(|a, b| (&a.key, &a.locale).cmp(&(&b.key, &b.locale)));
+
+        Ok(messages
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn get_messages_count(&self, tenant: &str) -> anyhow::Result<i64> {
+        Ok(self
+            .store
+            .state
+            .lock()
+            .unwrap()
+            .messages
+            .iter()
+            .filter(|message| message.tenant == tenant)
+            .count() as i64)
+    }
+
+    async fn get_messages(
+        &self,
+        tenants: &[String],
+        locales: &[String],
+    ) -> anyhow::Result<Vec<MessageEntity>> {
+        Ok(self
+            .store
+            .state
+            .lock()
+            .unwrap()
+            .messages
+            .iter()
+            .filter(|message| {
+                tenants.contains(&message.tenant) && locales.contains(&message.locale)
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn set_message(
+        &self,
+        tenant: &str,
+        key: &str,
+        locale: &str,
+        value: &str,
+    ) -> anyhow::Result<MessageEntity> {
+        let mut state = self.store.state.lock().unwrap();
+        let timestamp = state.write_timestamp();
+        match state.messages.iter_mut().find(|message| {
+            message.tenant == tenant && message.key == key && message.locale == locale
+        }) {
+            Some(message) => {
+                message.value = value.to_string();
+                message.updated_at = timestamp;
+                Ok(message.clone())
+            }
+            None => {
+                state.next_id += 1;
+                let message = MessageEntity {
+                    id: state.next_id,
+                    tenant: tenant.to_string(),
+                    key: key.to_string(),
+                    locale: locale.to_string(),
+                    value: value.to_string(),
+                    created_at: timestamp,
+                    updated_at: timestamp,
+                };
+                state.messages.push(message.clone());
+                Ok(message)
+            }
+        }
+    }
+
+    async fn remove_message(
+        &self,
+        tenant: &str,
+        key: &str,
+        locale: &str,
+    ) -> anyhow::Result<Option<MessageEntity>> {
+        let mut state = self.store.state.lock().unwrap();
+        Ok(state
+            .messages
+            .iter()
+            .position(|message| {
+                message.tenant == tenant && message.key == key && message.locale == locale
+            })
+            .map(|index| state.messages.remove(index)))
+    }
+}
+
+#[derive(Clone)]
+pub struct InMemoryStylesheetRepository {
+    store: Arc<InMemoryStore>,
+}
+
+impl InMemoryStylesheetRepository {
+    pub fn new(store: Arc<InMemoryStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl StylesheetRepositoryTrait for InMemoryStylesheetRepository {
+    async fn get_stylesheet(&self, tenant: &str) -> anyhow::Result<Option<StylesheetEntity>> {
+        Ok(self
+            .store
+            .state
+            .lock()
+            .unwrap()
+            .stylesheets
+            .iter()
+            .find(|stylesheet| stylesheet.tenant == tenant)
+            .cloned())
+    }
+
+    async fn set_stylesheet(&self, tenant: &str, css: &str) -> anyhow::Result<StylesheetEntity> {
+        let mut state = self.store.state.lock().unwrap();
+        let timestamp = state.write_timestamp();
+        match state
+            .stylesheets
+            .iter_mut()
+            .find(|stylesheet| stylesheet.tenant == tenant)
+        {
+            Some(stylesheet) => {
+                stylesheet.css = css.to_string();
+                stylesheet.updated_at = timestamp;
+                Ok(stylesheet.clone())
+            }
+            None => {
+                state.next_id += 1;
+                let stylesheet = StylesheetEntity {
+                    id: state.next_id,
+                    tenant: tenant.to_string(),
+                    css: css.to_string(),
+                    created_at: timestamp,
+                    updated_at: timestamp,
+                };
+                state.stylesheets.push(stylesheet.clone());
+                Ok(stylesheet)
+            }
+        }
+    }
+
+    async fn remove_stylesheet(&self, tenant: &str) -> anyhow::Result<Option<StylesheetEntity>> {
+        let mut state = self.store.state.lock().unwrap();
+        Ok(state
+            .stylesheets
+            .iter()
+            .position(|stylesheet| stylesheet.tenant == tenant)
+            .map(|index| state.stylesheets.remove(index)))
+    }
+}
+
+#[derive(Clone)]
+pub struct InMemoryIdempotencyKeyRepository {
+    store: Arc<InMemoryStore>,
+}
+
+impl InMemoryIdempotencyKeyRepository {
+    pub fn new(store: Arc<InMemoryStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl IdempotencyKeyRepositoryTrait for InMemoryIdempotencyKeyRepository {
+    async fn get_idempotency_key(
+        &self,
+        tenant: &str,
+        key: &str,
+    ) -> anyhow::Result<Option<IdempotencyKeyEntity>> {
+        Ok(self
+            .store
+            .state
+            .lock()
+            .unwrap()
+            .idempotency_keys
+            .iter()
+            .find(|idempotency_key| idempotency_key.tenant == tenant && idempotency_key.key == key)
+            .cloned())
+    }
+
+    async fn add_idempotency_key(
+        &self,
+        tenant: &str,
+        key: &str,
+        request: &[u8],
+        response: &[u8],
+    ) -> anyhow::Result<()> {
+        let mut state = self.store.state.lock().unwrap();
+        if state
+            .idempotency_keys
+            .iter()
+            .any(|idempotency_key| idempotency_key.tenant == tenant && idempotency_key.key == key)
+        {
+            return Ok(());
+        }
+
+        let timestamp = state.write_timestamp();
+        state.next_id += 1;
+        let idempotency_key = IdempotencyKeyEntity {
+            id: state.next_id,
+            tenant: tenant.to_string(),
+            key: key.to_string(),
+            request: request.to_vec(),
+            response: response.to_vec(),
+            created_at: timestamp,
+        };
+        state.idempotency_keys.push(idempotency_key);
+
+        Ok(())
+    }
+
+    async fn purge_idempotency_keys(&self, created_before: OffsetDateTime) -> anyhow::Result<u64> {
+        let mut state = self.store.state.lock().unwrap();
+        let key_count = state.idempotency_keys.len();
+        state
+            .idempotency_keys
+            .retain(|idempotency_key| idempotency_key.created_at >= created_before);
+
+        Ok((key_count - state.idempotency_keys.len()) as u64)
+    }
+}
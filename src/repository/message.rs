@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::repository::connection_pool::ServiceConnectionPool;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, types::time::OffsetDateTime, FromRow};
+
+#[derive(FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct MessageEntity {
+    pub id: i64,
+    pub tenant: String,
+    pub key: String,
+    pub locale: String,
+    pub value: String,
+    #[serde(with = "super::timestamp")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "super::timestamp")]
+    pub updated_at: OffsetDateTime,
+}
+
+#[async_trait]
+pub trait MessageRepositoryTrait {
+    async fn list_messages(
+        &self,
+        tenant: &str,
+        offset: i64,
+        limit: i64,
+    ) -> anyhow::Result<Vec<MessageEntity>>;
+    async fn get_messages_count(&self, tenant: &str) -> anyhow::Result<i64>;
+    async fn get_messages(
+        &self,
+        tenants: &[String],
+        locales: &[String],
+    ) -> anyhow::Result<Vec<MessageEntity>>;
+    async fn set_message(
+        &self,
+        tenant: &str,
+        key: &str,
+        locale: &str,
+        value: &str,
+    ) -> anyhow::Result<MessageEntity>;
+    async fn remove_message(
+        &self,
+        tenant: &str,
+        key: &str,
+        locale: &str,
+    ) -> anyhow::Result<Option<MessageEntity>>;
+}
+
+pub type DynMessageRepositoryTrait = Arc<dyn MessageRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct MessageRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl MessageRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl MessageRepositoryTrait for MessageRepository {
+    async fn list_messages(
+        &self,
+        tenant: &str,
+        offset: i64,
+        limit: i64,
+    ) -> anyhow::Result<Vec<MessageEntity>> {
+        query_as!(
+            MessageEntity,
+            r#"
+                select
+                    id,
+                    tenant,
+                    key,
+                    locale,
+                    value,
+                    created_at,
+                    updated_at
+                from messages
+                where tenant = $1::varchar
+                order by key, locale
+                limit $2::int
+                offset $3::int
+            "#,
+            tenant,
+            limit as i32,
+            offset as i32,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining messages")
+    }
+
+    async fn get_messages_count(&self, tenant: &str) -> anyhow::Result<i64> {
+        let count_result = query!(
+            r#"
+                select
+                    count(*)
+                from messages
+                where tenant = $1::varchar
+            "#,
+            tenant,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count_result.count.unwrap())
+    }
+
+    async fn get_messages(
+        &self,
+        tenants: &[String],
+        locales: &[String],
+    ) -> anyhow::Result<Vec<MessageEntity>> {
+        query_as!(
+            MessageEntity,
+            r#"
+                select
+                    id,
+                    tenant,
+                    key,
+                    locale,
+                    value,
+                    created_at,
+                    updated_at
+                from messages
+                where tenant = any($1::varchar[])
+                    and locale = any($2::varchar[])
+            "#,
+            tenants,
+            locales,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining messages for locales")
+    }
+
+    async fn set_message(
+        &self,
+        tenant: &str,
+        key: &str,
+        locale: &str,
+        value: &str,
+    ) -> anyhow::Result<MessageEntity> {
+        query_as!(
+            MessageEntity,
+            r#"
+                insert into messages (
+                        tenant,
+                        key,
+                        locale,
+                        value
+                    )
+                values (
+                        $1::varchar,
+                        $2::varchar,
+                        $3::varchar,
+                        $4::varchar
+                    )
+                on conflict (tenant, key, locale) do update
+                set
+                    value = excluded.value,
+                    updated_at = current_timestamp
+                returning *
+            "#,
+            tenant,
+            key,
+            locale,
+            value,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the message")
+    }
+
+    async fn remove_message(
+        &self,
+        tenant: &str,
+        key: &str,
+        locale: &str,
+    ) -> anyhow::Result<Option<MessageEntity>> {
+        query_as!(
+            MessageEntity,
+            r#"
+                delete from messages
+                where
+                    tenant = $1::varchar
+                    and key = $2::varchar
+                    and locale = $3::varchar
+                returning *
+            "#,
+            tenant,
+            key,
+            locale,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the message")
+    }
+}
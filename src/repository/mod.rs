@@ -1,145 +1,345 @@
+pub mod filesystem;
+pub mod idempotency_key;
 pub mod input;
+pub mod memory;
+pub mod message;
+pub mod sqlite;
+pub mod stylesheet;
 pub mod template;
+pub mod template_override;
+pub mod template_schema;
+pub mod template_settings;
+pub mod template_variant;
+mod timestamp;
+
+#[cfg(test)]
+mod conformance;
 
 #[cfg(test)]
 pub mod test {
     use std::sync::Arc;
 
     use madtofan_microservice_common::templating::TemplateInput;
-    use sqlx::PgPool;
+    use sqlx::{types::time::OffsetDateTime, PgPool};
 
     use super::{
+        filesystem::{FileTemplateOverrideRepository, FileTemplateRepository, FileTemplateStore},
         input::{DynInputRepositoryTrait, InputRepository},
-        template::{DynTemplateRepositoryTrait, TemplateRepository},
+        message::{DynMessageRepositoryTrait, MessageRepository},
+        template::{
+            DynTemplateRepositoryTrait, TemplateFields, TemplateRepository,
+            TemplateRepositoryTrait, GLOBAL_TENANT,
+        },
+        template_override::{
+            DynTemplateOverrideRepositoryTrait, TemplateOverrideRepository,
+            TemplateOverrideRepositoryTrait,
+        },
     };
 
     struct AllTraits {
         templates_repository: DynTemplateRepositoryTrait,
-        inputs_repository: DynInputRepositoryTrait,
+        override_repository: DynTemplateOverrideRepositoryTrait,
+        message_repository: DynMessageRepositoryTrait,
     }
 
     fn initialize_handler(pool: PgPool) -> AllTraits {
         let inputs_repository =
             Arc::new(InputRepository::new(pool.clone())) as DynInputRepositoryTrait;
-        let templates_repository = Arc::new(TemplateRepository::new(
-            pool.clone(),
-            inputs_repository.clone(),
-        )) as DynTemplateRepositoryTrait;
+        let templates_repository =
+            Arc::new(TemplateRepository::new(pool.clone(), inputs_repository))
+                as DynTemplateRepositoryTrait;
+        let override_repository = Arc::new(TemplateOverrideRepository::new(pool.clone()))
+            as DynTemplateOverrideRepositoryTrait;
+        let message_repository =
+            Arc::new(MessageRepository::new(pool.clone())) as DynMessageRepositoryTrait;
 
         AllTraits {
             templates_repository,
-            inputs_repository,
+            override_repository,
+            message_repository,
         }
     }
 
     #[sqlx::test]
-    async fn remove_template_test(pool: PgPool) -> anyhow::Result<()> {
+    async fn set_override_test(pool: PgPool) -> anyhow::Result<()> {
         let traits = initialize_handler(pool);
+        let inputs = vec![TemplateInput {
+            name: "logo".to_string(),
+            default_value: "base_logo".to_string(),
+        }];
 
-        let template_to_remove_name = "template_to_remove";
-        let template_to_remove_body = "template_to_remove_body";
-        let inputs = vec![
-            TemplateInput {
-                name: "input1".to_string(),
-                default_value: "default_value1".to_string(),
-            },
-            TemplateInput {
-                name: "input2".to_string(),
-                default_value: "default_value2".to_string(),
-            },
-        ];
+        let template = traits
+            .templates_repository
+            .add_template(
+                GLOBAL_TENANT,
+                "",
+                "name",
+                TemplateFields {
+                    description: "description",
+                    body: "body",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &inputs,
+                },
+            )
+            .await?;
 
         traits
-            .templates_repository
-            .add_template("template1", "description1", "body1", &inputs)
+            .override_repository
+            .set_override(template.id, "product", Some("first body"), &inputs)
             .await?;
         traits
-            .templates_repository
-            .add_template(
-                template_to_remove_name,
-                "description_to_remove",
-                template_to_remove_body,
-                &inputs,
-            )
+            .override_repository
+            .set_override(template.id, "product", None, &[])
             .await?;
 
-        let removed_template = traits
+        let template_override = traits
+            .override_repository
+            .get_override(template.id, "product")
+            .await?
+            .unwrap();
+
+        assert_eq!(template_override.body, None);
+        assert_eq!(template_override.inputs.len(), 0);
+
+        traits
             .templates_repository
-            .remove_template(template_to_remove_name)
+            .remove_template(GLOBAL_TENANT, "", "name", None)
+            .await?;
+        traits
+            .templates_repository
+            .purge_templates(OffsetDateTime::now_utc())
             .await?;
 
-        let templates_list = traits.templates_repository.list_templates(0, 10).await?;
+        let removed_override = traits
+            .override_repository
+            .get_override(template.id, "product")
+            .await?;
 
-        assert_eq!(templates_list.len(), 1);
-        assert_eq!(removed_template.unwrap().body, template_to_remove_body);
+        assert!(removed_override.is_none());
 
         Ok(())
     }
 
     #[sqlx::test]
-    async fn get_template_test(pool: PgPool) -> anyhow::Result<()> {
+    async fn get_messages_test(pool: PgPool) -> anyhow::Result<()> {
         let traits = initialize_handler(pool);
 
-        let template_to_get_name = "template_to_get";
-        let template_to_get_body = "template_to_get_body";
-        let inputs = vec![
-            TemplateInput {
-                name: "input1".to_string(),
-                default_value: "default_value1".to_string(),
-            },
-            TemplateInput {
-                name: "input2".to_string(),
-                default_value: "default_value2".to_string(),
-            },
-        ];
-
         traits
-            .templates_repository
+            .message_repository
+            .set_message(GLOBAL_TENANT, "greeting", "en", "hello")
+            .await?;
+        traits
+            .message_repository
+            .set_message(GLOBAL_TENANT, "greeting", "en", "hi")
+            .await?;
+        traits
+            .message_repository
+            .set_message("tenant", "greeting", "ms", "hai")
+            .await?;
+        traits
+            .message_repository
+            .set_message("other_tenant", "greeting", "ms", "helo")
+            .await?;
+
+        let messages = traits
+            .message_repository
+            .get_messages(
+                &["tenant".to_string(), GLOBAL_TENANT.to_string()],
+                &["ms".to_string(), "en".to_string()],
+            )
+            .await?;
+
+        assert_eq!(messages.len(), 2);
+        assert!(messages.iter().any(|message| message.value == "hi"));
+        assert!(messages.iter().any(|message| message.value == "hai"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn filesystem_template_test() -> anyhow::Result<()> {
+        let template_dir =
+            std::env::temp_dir().join(format!("templating-test-{}", std::process::id()));
+        let store = FileTemplateStore::open(template_dir.clone())?;
+        let templates_repository =
+            Arc::new(FileTemplateRepository::new(store)) as DynTemplateRepositoryTrait;
+        let inputs = vec![TemplateInput {
+            name: "input1".to_string(),
+            default_value: "default_value1".to_string(),
+        }];
+
+        let template = templates_repository
             .add_template(
-                template_to_get_name,
-                "description",
-                template_to_get_body,
-                &inputs,
+                GLOBAL_TENANT,
+                "auth",
+                "name",
+                TemplateFields {
+                    description: "description",
+                    body: "body",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &inputs,
+                },
             )
             .await?;
+        let reloaded_template =
+            FileTemplateRepository::new(FileTemplateStore::open(template_dir.clone())?)
+                .get_template(GLOBAL_TENANT, "auth", "name")
+                .await?
+                .unwrap();
 
-        let get_template = traits
-            .templates_repository
-            .get_template(template_to_get_name)
+        assert_eq!(reloaded_template.id, template.id);
+        assert_eq!(reloaded_template.body, "body");
+        assert_eq!(reloaded_template.inputs.len(), 1);
+
+        templates_repository
+            .remove_template(GLOBAL_TENANT, "auth", "name", None)
+            .await?;
+        let trashed_count = templates_repository
+            .get_templates_count(GLOBAL_TENANT, None, true)
+            .await?;
+        let restored_template = templates_repository
+            .restore_template(GLOBAL_TENANT, "auth", "name")
             .await?;
 
-        assert_eq!(get_template.unwrap().body, template_to_get_body);
+        assert_eq!(trashed_count, 1);
+        assert_eq!(restored_template.unwrap().id, template.id);
+
+        std::fs::remove_dir_all(template_dir)?;
 
         Ok(())
     }
 
-    #[sqlx::test]
-    async fn remove_inputs_test(pool: PgPool) -> anyhow::Result<()> {
-        let traits = initialize_handler(pool);
-        let inputs = vec![
-            TemplateInput {
-                name: "input1".to_string(),
-                default_value: "default_value1".to_string(),
-            },
-            TemplateInput {
-                name: "input2".to_string(),
-                default_value: "default_value2".to_string(),
-            },
-        ];
+    #[tokio::test]
+    async fn filesystem_template_id_test() -> anyhow::Result<()> {
+        let template_dir =
+            std::env::temp_dir().join(format!("templating-id-test-{}", std::process::id()));
+        std::fs::create_dir_all(template_dir.join(GLOBAL_TENANT))?;
+        std::fs::write(
+            template_dir.join(GLOBAL_TENANT).join("welcome.yaml"),
+            "description: description\ninputs:\n  - name: input1\n",
+        )?;
+        std::fs::write(template_dir.join(GLOBAL_TENANT).join("welcome.hbs"), "body")?;
 
-        let template = traits
-            .templates_repository
-            .add_template("name", "descriptions", "body", &inputs)
+        let templates_repository =
+            FileTemplateRepository::new(FileTemplateStore::open(template_dir.clone())?);
+        let template = templates_repository
+            .get_template(GLOBAL_TENANT, "", "welcome")
+            .await?
+            .unwrap();
+        let renamed_template = templates_repository
+            .rename_template(GLOBAL_TENANT, "", "welcome", "auth", "welcome")
+            .await?
+            .unwrap();
+        let reloaded_template =
+            FileTemplateRepository::new(FileTemplateStore::open(template_dir.clone())?)
+                .get_template(GLOBAL_TENANT, "auth", "welcome")
+                .await?
+                .unwrap();
+
+        assert_eq!(renamed_template.id, template.id);
+        assert_eq!(reloaded_template.id, template.id);
+        assert_eq!(reloaded_template.inputs[0].template_id, template.id);
+
+        std::fs::remove_dir_all(template_dir)?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn filesystem_override_test() -> anyhow::Result<()> {
+        let template_dir =
+            std::env::temp_dir().join(format!("templating-override-test-{}", std::process::id()));
+        let store = FileTemplateStore::open(template_dir.clone())?;
+        let templates_repository = FileTemplateRepository::new(store.clone());
+        let override_repository = FileTemplateOverrideRepository::new(store);
+        let inputs = vec![TemplateInput {
+            name: "logo".to_string(),
+            default_value: "base_logo".to_string(),
+        }];
+
+        let template = templates_repository
+            .add_template(
+                GLOBAL_TENANT,
+                "",
+                "name",
+                TemplateFields {
+                    description: "description",
+                    body: "body",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &inputs,
+                },
+            )
+            .await?;
+        override_repository
+            .set_override(template.id, "product", Some("product body"), &inputs)
             .await?;
+        let reloaded_override =
+            FileTemplateOverrideRepository::new(FileTemplateStore::open(template_dir.clone())?)
+                .get_override(template.id, "product")
+                .await?
+                .unwrap();
+
+        assert_eq!(reloaded_override.body.as_deref(), Some("product body"));
+        assert_eq!(reloaded_override.inputs.len(), 1);
+
+        templates_repository
+            .remove_template(GLOBAL_TENANT, "", "name", None)
+            .await?;
+        templates_repository
+            .purge_templates(OffsetDateTime::now_utc())
+            .await?;
+        let purged_override = override_repository
+            .get_override(template.id, "product")
+            .await?;
+
+        assert!(purged_override.is_none());
 
-        traits.inputs_repository.remove_inputs(template.id).await?;
+        std::fs::remove_dir_all(template_dir)?;
 
-        let template_inputs = traits
-            .inputs_repository
-            .get_template_inputs(&template.name)
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn filesystem_path_traversal_test() -> anyhow::Result<()> {
+        let template_dir =
+            std::env::temp_dir().join(format!("templating-traversal-test-{}", std::process::id()));
+        let templates_repository =
+            FileTemplateRepository::new(FileTemplateStore::open(template_dir.join("root"))?);
+        let inputs = vec![TemplateInput {
+            name: "input1".to_string(),
+            default_value: "default_value1".to_string(),
+        }];
+        let fields = TemplateFields {
+            description: "description",
+            body: "body",
+            engine: "handlebars",
+            content_type: "html",
+            inputs: &inputs,
+        };
+
+        let parent_tenant = templates_repository
+            .add_template("..", "", "name", fields)
             .await;
+        let parent_namespace = templates_repository
+            .add_template(GLOBAL_TENANT, "auth/..", "name", fields)
+            .await;
+        let parent_name = templates_repository
+            .add_template(GLOBAL_TENANT, "", "..", fields)
+            .await;
+        let dotted_name = templates_repository
+            .add_template(GLOBAL_TENANT, "", "name.hbs", fields)
+            .await;
+
+        assert!(parent_tenant.is_err());
+        assert!(parent_namespace.is_err());
+        assert!(parent_name.is_err());
+        assert!(dotted_name.is_err());
+        assert_eq!(std::fs::read_dir(&template_dir)?.count(), 1);
 
-        assert_eq!(template_inputs.unwrap().len(), 0);
+        std::fs::remove_dir_all(template_dir)?;
 
         Ok(())
     }
@@ -0,0 +1,100 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use sqlx::{query, query_as, types::time::OffsetDateTime, SqlitePool};
+
+use crate::repository::idempotency_key::{IdempotencyKeyEntity, IdempotencyKeyRepositoryTrait};
+
+use super::{now, timestamp};
+
+#[derive(Clone)]
+pub struct SqliteIdempotencyKeyRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteIdempotencyKeyRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IdempotencyKeyRepositoryTrait for SqliteIdempotencyKeyRepository {
+    async fn get_idempotency_key(
+        &self,
+        tenant: &str,
+        key: &str,
+    ) -> anyhow::Result<Option<IdempotencyKeyEntity>> {
+        query_as::<_, IdempotencyKeyEntity>(
+            r#"
+                select
+                    id,
+                    tenant,
+                    key,
+                    request,
+                    response,
+                    created_at
+                from idempotency_keys
+                where tenant = ?1
+                    and key = ?2
+            "#,
+        )
+        .bind(tenant)
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining the idempotency key")
+    }
+
+    async fn add_idempotency_key(
+        &self,
+        tenant: &str,
+        key: &str,
+        request: &[u8],
+        response: &[u8],
+    ) -> anyhow::Result<()> {
+        query(
+            r#"
+                insert into idempotency_keys (
+                        tenant,
+                        key,
+                        request,
+                        response,
+                        created_at
+                    )
+                values (
+                        ?1,
+                        ?2,
+                        ?3,
+                        ?4,
+                        ?5
+                    )
+                on conflict (tenant, key) do nothing
+            "#,
+        )
+        .bind(tenant)
+        .bind(key)
+        .bind(request)
+        .bind(response)
+        .bind(now())
+        .execute(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the idempotency key")?;
+
+        Ok(())
+    }
+
+    async fn purge_idempotency_keys(&self, created_before: OffsetDateTime) -> anyhow::Result<u64> {
+        let purge_result = query(
+            r#"
+                delete from idempotency_keys
+                where created_at < ?1
+            "#,
+        )
+        .bind(timestamp(created_before))
+        .execute(&self.pool)
+        .await
+        .context("an unexpected error occured while purging idempotency keys")?;
+
+        Ok(purge_result.rows_affected())
+    }
+}
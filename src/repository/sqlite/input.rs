@@ -0,0 +1,105 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::templating::TemplateInput;
+use sqlx::{query_as, SqliteExecutor, SqlitePool};
+
+use crate::repository::input::{InputEntity, InputRepositoryTrait};
+
+use super::json_inputs;
+
+#[derive(Clone)]
+pub struct SqliteInputRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteInputRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl InputRepositoryTrait for SqliteInputRepository {
+    async fn get_template_inputs(&self, template_id: i64) -> anyhow::Result<Vec<InputEntity>> {
+        query_as::<_, InputEntity>(
+            r#"
+                select
+                    i.id as id,
+                    i.name as name,
+                    i.default_value as default_value,
+                    i.template_id as template_id
+                from inputs as i
+                where i.template_id = ?1
+            "#,
+        )
+        .bind(template_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining inputs for template")
+    }
+
+    async fn add_inputs(
+        &self,
+        inputs: &[TemplateInput],
+        template_id: i64,
+    ) -> anyhow::Result<Vec<InputEntity>> {
+        insert_inputs(&self.pool, inputs, template_id).await
+    }
+
+    async fn remove_inputs(&self, template_id: i64) -> anyhow::Result<Option<InputEntity>> {
+        delete_inputs(&self.pool, template_id).await
+    }
+}
+
+/// Inserts the inputs of the template, on the pool or on the transaction of a write
+/// that replaces them along with the template.
+pub(super) async fn insert_inputs<'e, E>(
+    executor: E,
+    inputs: &[TemplateInput],
+    template_id: i64,
+) -> anyhow::Result<Vec<InputEntity>>
+where
+    E: SqliteExecutor<'e>,
+{
+    query_as::<_, InputEntity>(
+        r#"
+            insert into inputs (
+                    name,
+                    default_value,
+                    template_id
+                )
+            select
+                json_extract(value, '$.name'),
+                json_extract(value, '$.default_value'),
+                ?2
+            from json_each(?1)
+            returning *
+        "#,
+    )
+    .bind(json_inputs(inputs))
+    .bind(template_id)
+    .fetch_all(executor)
+    .await
+    .context("an unexpected error occured while creating the input")
+}
+
+pub(super) async fn delete_inputs<'e, E>(
+    executor: E,
+    template_id: i64,
+) -> anyhow::Result<Option<InputEntity>>
+where
+    E: SqliteExecutor<'e>,
+{
+    query_as::<_, InputEntity>(
+        r#"
+            delete from inputs
+            where
+                template_id = ?1
+            returning *
+        "#,
+    )
+    .bind(template_id)
+    .fetch_optional(executor)
+    .await
+    .context("an unexpected error occured while removing the input")
+}
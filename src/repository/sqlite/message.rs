@@ -0,0 +1,160 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use sqlx::{query_as, query_scalar, SqlitePool};
+
+use crate::repository::message::{MessageEntity, MessageRepositoryTrait};
+
+use super::now;
+
+#[derive(Clone)]
+pub struct SqliteMessageRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteMessageRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl MessageRepositoryTrait for SqliteMessageRepository {
+    async fn list_messages(
+        &self,
+        tenant: &str,
+        offset: i64,
+        limit: i64,
+    ) -> anyhow::Result<Vec<MessageEntity>> {
+        query_as::<_, MessageEntity>(
+            r#"
+                select
+                    id,
+                    tenant,
+                    key,
+                    locale,
+                    value,
+                    created_at,
+                    updated_at
+                from messages
+                where tenant = ?1
+                order by key, locale
+                limit ?2
+                offset ?3
+            "#,
+        )
+        .bind(tenant)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining messages")
+    }
+
+    async fn get_messages_count(&self, tenant: &str) -> anyhow::Result<i64> {
+        query_scalar::<_, i64>(
+            r#"
+                select
+                    count(*)
+                from messages
+                where tenant = ?1
+            "#,
+        )
+        .bind(tenant)
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while counting messages")
+    }
+
+    async fn get_messages(
+        &self,
+        tenants: &[String],
+        locales: &[String],
+    ) -> anyhow::Result<Vec<MessageEntity>> {
+        query_as::<_, MessageEntity>(
+            r#"
+                select
+                    id,
+                    tenant,
+                    key,
+                    locale,
+                    value,
+                    created_at,
+                    updated_at
+                from messages
+                where tenant in (select value from json_each(?1))
+                    and locale in (select value from json_each(?2))
+            "#,
+        )
+        .bind(serde_json::to_string(tenants)?)
+        .bind(serde_json::to_string(locales)?)
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining messages for locales")
+    }
+
+    async fn set_message(
+        &self,
+        tenant: &str,
+        key: &str,
+        locale: &str,
+        value: &str,
+    ) -> anyhow::Result<MessageEntity> {
+        query_as::<_, MessageEntity>(
+            r#"
+                insert into messages (
+                        tenant,
+                        key,
+                        locale,
+                        value,
+                        created_at,
+                        updated_at
+                    )
+                values (
+                        ?1,
+                        ?2,
+                        ?3,
+                        ?4,
+                        ?5,
+                        ?5
+                    )
+                on conflict (tenant, key, locale) do update
+                set
+                    value = excluded.value,
+                    updated_at = excluded.updated_at
+                returning *
+            "#,
+        )
+        .bind(tenant)
+        .bind(key)
+        .bind(locale)
+        .bind(value)
+        .bind(now())
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the message")
+    }
+
+    async fn remove_message(
+        &self,
+        tenant: &str,
+        key: &str,
+        locale: &str,
+    ) -> anyhow::Result<Option<MessageEntity>> {
+        query_as::<_, MessageEntity>(
+            r#"
+                delete from messages
+                where
+                    tenant = ?1
+                    and key = ?2
+                    and locale = ?3
+                returning *
+            "#,
+        )
+        .bind(tenant)
+        .bind(key)
+        .bind(locale)
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the message")
+    }
+}
@@ -0,0 +1,68 @@
+//! SQLite implementations of the repository traits, for deployments that do not run
+//! Postgres. The schema lives in `migrations_sqlite`.
+
+use std::str::FromStr;
+
+use anyhow::Context;
+use madtofan_microservice_common::templating::TemplateInput;
+use serde_json::json;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    types::time::{OffsetDateTime, UtcOffset},
+    SqlitePool,
+};
+
+pub mod idempotency_key;
+pub mod input;
+pub mod message;
+pub mod stylesheet;
+pub mod template;
+pub mod template_override;
+pub mod template_schema;
+pub mod template_settings;
+pub mod template_variant;
+
+pub async fn new_pool(database_url: &str) -> anyhow::Result<SqlitePool> {
+    let options = SqliteConnectOptions::from_str(database_url)
+        .context("an unexpected error occured while parsing the sqlite database url")?
+        .create_if_missing(true);
+
+    SqlitePoolOptions::new()
+        .connect_with(options)
+        .await
+        .context("an unexpected error occured while connecting to the sqlite database")
+}
+
+/// Timestamps are stored as fixed-width UTC text down to the microsecond, so they
+/// compare in the same order as the Postgres `timestamptz` columns and survive the
+/// round trip through template revisions.
+fn timestamp(value: OffsetDateTime) -> String {
+    let value = value.to_offset(UtcOffset::UTC);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        value.year(),
+        u8::from(value.month()),
+        value.day(),
+        value.hour(),
+        value.minute(),
+        value.second(),
+        value.microsecond(),
+    )
+}
+
+fn now() -> String {
+    timestamp(OffsetDateTime::now_utc())
+}
+
+/// Inputs are bound as a single JSON array and expanded with `json_each`, which stands
+/// in for the `unnest` inserts of the Postgres repositories.
+fn json_inputs(inputs: &[TemplateInput]) -> String {
+    json!(inputs
+        .iter()
+        .map(|input| json!({
+            "name": input.name,
+            "default_value": input.default_value,
+        }))
+        .collect::<Vec<_>>())
+    .to_string()
+}
@@ -0,0 +1,84 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use sqlx::{query_as, SqlitePool};
+
+use crate::repository::stylesheet::{StylesheetEntity, StylesheetRepositoryTrait};
+
+use super::now;
+
+#[derive(Clone)]
+pub struct SqliteStylesheetRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteStylesheetRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl StylesheetRepositoryTrait for SqliteStylesheetRepository {
+    async fn get_stylesheet(&self, tenant: &str) -> anyhow::Result<Option<StylesheetEntity>> {
+        query_as::<_, StylesheetEntity>(
+            r#"
+                select
+                    id,
+                    tenant,
+                    css,
+                    created_at,
+                    updated_at
+                from stylesheets
+                where tenant = ?1
+            "#,
+        )
+        .bind(tenant)
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining the stylesheet of tenant")
+    }
+
+    async fn set_stylesheet(&self, tenant: &str, css: &str) -> anyhow::Result<StylesheetEntity> {
+        query_as::<_, StylesheetEntity>(
+            r#"
+                insert into stylesheets (
+                        tenant,
+                        css,
+                        created_at,
+                        updated_at
+                    )
+                values (
+                        ?1,
+                        ?2,
+                        ?3,
+                        ?3
+                    )
+                on conflict (tenant) do update
+                set
+                    css = excluded.css,
+                    updated_at = excluded.updated_at
+                returning *
+            "#,
+        )
+        .bind(tenant)
+        .bind(css)
+        .bind(now())
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the stylesheet")
+    }
+
+    async fn remove_stylesheet(&self, tenant: &str) -> anyhow::Result<Option<StylesheetEntity>> {
+        query_as::<_, StylesheetEntity>(
+            r#"
+                delete from stylesheets
+                where tenant = ?1
+                returning *
+            "#,
+        )
+        .bind(tenant)
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the stylesheet")
+    }
+}
@@ -0,0 +1,534 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::templating::TemplateInput;
+use sqlx::{
+    query, query_as, query_scalar, types::time::OffsetDateTime, types::Json, FromRow, SqlitePool,
+};
+
+use crate::repository::{
+    input::{DynInputRepositoryTrait, InputEntity},
+    template::{TemplateEntity, TemplateFields, TemplateInputsEntity, TemplateRepositoryTrait},
+};
+
+use super::{
+    input::{delete_inputs, insert_inputs},
+    now, timestamp,
+};
+
+/// A template with its inputs collected by `json_group_array`, the SQLite counterpart
+/// of the `array_agg` the Postgres repository selects.
+#[derive(FromRow)]
+struct TemplateInputsRow {
+    id: i64,
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
+    name: String,
+    description: String,
+    body: String,
+    engine: String,
+    content_type: String,
+    namespace: String,
+    tenant: String,
+    cloned_from: Option<i64>,
+    deleted_at: Option<OffsetDateTime>,
+    inputs: Json<Vec<InputEntity>>,
+}
+
+impl From<TemplateInputsRow> for TemplateInputsEntity {
+    fn from(row: TemplateInputsRow) -> Self {
+        Self {
+            id: row.id,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            name: row.name,
+            description: row.description,
+            body: row.body,
+            engine: row.engine,
+            content_type: row.content_type,
+            namespace: row.namespace,
+            tenant: row.tenant,
+            cloned_from: row.cloned_from,
+            deleted_at: row.deleted_at,
+            inputs: row.inputs.0,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SqliteTemplateRepository {
+    pool: SqlitePool,
+    inputs_repository: DynInputRepositoryTrait,
+}
+
+impl SqliteTemplateRepository {
+    pub fn new(pool: SqlitePool, inputs_repository: DynInputRepositoryTrait) -> Self {
+        Self {
+            pool,
+            inputs_repository,
+        }
+    }
+}
+
+#[async_trait]
+impl TemplateRepositoryTrait for SqliteTemplateRepository {
+    async fn list_templates(
+        &self,
+        tenant: &str,
+        namespace: Option<&str>,
+        trashed: bool,
+        offset: i64,
+        limit: i64,
+    ) -> anyhow::Result<Vec<TemplateInputsEntity>> {
+        let templates = query_as::<_, TemplateInputsRow>(
+            r#"
+                select
+                    t.id as id,
+                    t.name as name,
+                    t.description as description,
+                    t.body as body,
+                    t.engine as engine,
+                    t.content_type as content_type,
+                    t.namespace as namespace,
+                    t.tenant as tenant,
+                    t.cloned_from as cloned_from,
+                    t.deleted_at as deleted_at,
+                    t.created_at as created_at,
+                    t.updated_at as updated_at,
+                    json_group_array(json_object(
+                        'id', i.id,
+                        'name', i.name,
+                        'default_value', i.default_value,
+                        'template_id', i.template_id
+                    )) filter (where i.id is not null) as inputs
+                from templates as t
+                left join inputs as i
+                    on t.id = i.template_id
+                where t.tenant = ?1
+                    and (
+                        ?2 is null
+                        or t.namespace = ?2
+                        or substr(t.namespace, 1, length(?2) + 1) = ?2 || '/'
+                    )
+                    and (t.deleted_at is not null) = ?3
+                group by t.id
+                order by t.namespace, t.name, t.id
+                limit ?4
+                offset ?5
+            "#,
+        )
+        .bind(tenant)
+        .bind(namespace)
+        .bind(trashed)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining template")?;
+
+        Ok(templates
+            .into_iter()
+            .map(|template| template.into())
+            .collect())
+    }
+
+    async fn get_templates_count(
+        &self,
+        tenant: &str,
+        namespace: Option<&str>,
+        trashed: bool,
+    ) -> anyhow::Result<i64> {
+        query_scalar::<_, i64>(
+            r#"
+                select
+                    count(*)
+                from templates
+                where tenant = ?1
+                    and (
+                        ?2 is null
+                        or namespace = ?2
+                        or substr(namespace, 1, length(?2) + 1) = ?2 || '/'
+                    )
+                    and (deleted_at is not null) = ?3
+            "#,
+        )
+        .bind(tenant)
+        .bind(namespace)
+        .bind(trashed)
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while counting templates")
+    }
+
+    async fn get_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let template = query_as::<_, TemplateInputsRow>(
+            r#"
+                select
+                    t.id as id,
+                    t.name as name,
+                    t.description as description,
+                    t.body as body,
+                    t.engine as engine,
+                    t.content_type as content_type,
+                    t.namespace as namespace,
+                    t.tenant as tenant,
+                    t.cloned_from as cloned_from,
+                    t.deleted_at as deleted_at,
+                    t.created_at as created_at,
+                    t.updated_at as updated_at,
+                    json_group_array(json_object(
+                        'id', i.id,
+                        'name', i.name,
+                        'default_value', i.default_value,
+                        'template_id', i.template_id
+                    )) filter (where i.id is not null) as inputs
+                from templates as t
+                left join inputs as i
+                    on t.id = i.template_id
+                where t.tenant = ?1
+                    and t.namespace = ?2
+                    and t.name = ?3
+                    and t.deleted_at is null
+                group by t.id
+            "#,
+        )
+        .bind(tenant)
+        .bind(namespace)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining template")?;
+
+        Ok(template.map(|template| template.into()))
+    }
+
+    async fn add_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        fields: TemplateFields<'_>,
+    ) -> anyhow::Result<TemplateInputsEntity> {
+        let add_template_response = query_as::<_, TemplateEntity>(
+            r#"
+                insert into templates (
+                        tenant,
+                        namespace,
+                        name,
+                        description,
+                        body,
+                        engine,
+                        content_type,
+                        created_at,
+                        updated_at
+                    )
+                values (
+                        ?1,
+                        ?2,
+                        ?3,
+                        ?4,
+                        ?5,
+                        ?6,
+                        ?7,
+                        ?8,
+                        ?8
+                    )
+                returning *
+            "#,
+        )
+        .bind(tenant)
+        .bind(namespace)
+        .bind(name)
+        .bind(fields.description)
+        .bind(fields.body)
+        .bind(fields.engine)
+        .bind(fields.content_type)
+        .bind(now())
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while creating the template")?;
+
+        self.inputs_repository
+            .add_inputs(fields.inputs, add_template_response.id)
+            .await?;
+
+        self.get_template(tenant, namespace, name)
+            .await?
+            .context("an unexpected error occured while obtaining the newly created template")
+    }
+
+    async fn update_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        fields: TemplateFields<'_>,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .context("an unexpected error occured while starting the template update")?;
+        let updated_template = query_as::<_, TemplateEntity>(
+            r#"
+                update templates
+                set
+                    description = ?4,
+                    body = ?5,
+                    engine = ?6,
+                    content_type = ?7,
+                    updated_at = ?8
+                where
+                    tenant = ?1
+                    and namespace = ?2
+                    and name = ?3
+                    and deleted_at is null
+                    and (?9 is null or updated_at = ?9)
+                returning *
+            "#,
+        )
+        .bind(tenant)
+        .bind(namespace)
+        .bind(name)
+        .bind(fields.description)
+        .bind(fields.body)
+        .bind(fields.engine)
+        .bind(fields.content_type)
+        .bind(now())
+        .bind(expected_updated_at.map(timestamp))
+        .fetch_optional(&mut *transaction)
+        .await
+        .context("an unexpected error occured while updating the template")?;
+
+        let template = match updated_template {
+            Some(template) => template,
+            None => return Ok(None),
+        };
+        // The row and its inputs are replaced together, so a failure part way leaves
+        // neither a bumped revision nor missing inputs behind.
+        delete_inputs(&mut *transaction, template.id).await?;
+        insert_inputs(&mut *transaction, fields.inputs, template.id).await?;
+        transaction
+            .commit()
+            .await
+            .context("an unexpected error occured while committing the template update")?;
+
+        self.get_template(tenant, namespace, name).await
+    }
+
+    async fn remove_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let template_to_remove = self.get_template(tenant, namespace, name).await?;
+
+        let removed_template = query_as::<_, TemplateEntity>(
+            r#"
+                update templates
+                set
+                    deleted_at = ?4
+                where
+                    tenant = ?1
+                    and namespace = ?2
+                    and name = ?3
+                    and deleted_at is null
+                    and (?5 is null or updated_at = ?5)
+                returning *
+            "#,
+        )
+        .bind(tenant)
+        .bind(namespace)
+        .bind(name)
+        .bind(now())
+        .bind(expected_updated_at.map(timestamp))
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the template")?;
+
+        Ok(removed_template.and(template_to_remove))
+    }
+
+    async fn restore_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let restored_template = query_as::<_, TemplateEntity>(
+            r#"
+                update templates
+                set
+                    deleted_at = null,
+                    updated_at = ?4
+                where id = (
+                    select id
+                    from templates
+                    where
+                        tenant = ?1
+                        and namespace = ?2
+                        and name = ?3
+                        and deleted_at is not null
+                    order by deleted_at desc
+                    limit 1
+                )
+                returning *
+            "#,
+        )
+        .bind(tenant)
+        .bind(namespace)
+        .bind(name)
+        .bind(now())
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while restoring the template")?;
+
+        match restored_template {
+            Some(_) => self.get_template(tenant, namespace, name).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn purge_templates(&self, deleted_before: OffsetDateTime) -> anyhow::Result<u64> {
+        let purge_result = query(
+            r#"
+                delete from templates
+                where deleted_at < ?1
+            "#,
+        )
+        .bind(timestamp(deleted_before))
+        .execute(&self.pool)
+        .await
+        .context("an unexpected error occured while purging trashed templates")?;
+
+        Ok(purge_result.rows_affected())
+    }
+
+    async fn rename_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        new_namespace: &str,
+        new_name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let renamed_template = query_as::<_, TemplateEntity>(
+            r#"
+                update templates
+                set
+                    namespace = ?4,
+                    name = ?5,
+                    updated_at = ?6
+                where
+                    tenant = ?1
+                    and namespace = ?2
+                    and name = ?3
+                    and deleted_at is null
+                returning *
+            "#,
+        )
+        .bind(tenant)
+        .bind(namespace)
+        .bind(name)
+        .bind(new_namespace)
+        .bind(new_name)
+        .bind(now())
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while renaming the template")?;
+
+        match renamed_template {
+            Some(template) => {
+                self.get_template(&template.tenant, &template.namespace, &template.name)
+                    .await
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn clone_template(
+        &self,
+        template_id: i64,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<TemplateInputsEntity> {
+        let cloned_template = query_as::<_, TemplateEntity>(
+            r#"
+                insert into templates (
+                        tenant,
+                        namespace,
+                        name,
+                        description,
+                        body,
+                        engine,
+                        content_type,
+                        cloned_from,
+                        created_at,
+                        updated_at
+                    )
+                select
+                    ?2,
+                    ?3,
+                    ?4,
+                    description,
+                    body,
+                    engine,
+                    content_type,
+                    id,
+                    ?5,
+                    ?5
+                from templates
+                where id = ?1
+                returning *
+            "#,
+        )
+        .bind(template_id)
+        .bind(tenant)
+        .bind(namespace)
+        .bind(name)
+        .bind(now())
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while cloning the template")?;
+
+        let template_inputs = self
+            .inputs_repository
+            .get_template_inputs(template_id)
+            .await?
+            .into_iter()
+            .map(|input| input.into())
+            .collect::<Vec<TemplateInput>>();
+        self.inputs_repository
+            .add_inputs(&template_inputs, cloned_template.id)
+            .await?;
+
+        self.get_template(tenant, namespace, name)
+            .await?
+            .context("an unexpected error occured while obtaining the cloned template")
+    }
+
+    async fn touch_template(&self, template_id: i64) -> anyhow::Result<()> {
+        query(
+            r#"
+                update templates
+                set updated_at = ?2
+                where id = ?1
+            "#,
+        )
+        .bind(template_id)
+        .bind(now())
+        .execute(&self.pool)
+        .await
+        .context("an unexpected error occured while updating the template")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::templating::TemplateInput;
+use sqlx::{query_as, SqlitePool};
+
+use crate::repository::template_override::{
+    OverrideInputEntity, TemplateOverrideEntity, TemplateOverrideInputsEntity,
+    TemplateOverrideRepositoryTrait,
+};
+
+use super::{json_inputs, now};
+
+#[derive(Clone)]
+pub struct SqliteTemplateOverrideRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteTemplateOverrideRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    async fn get_override_inputs(
+        &self,
+        override_id: i64,
+    ) -> anyhow::Result<Vec<OverrideInputEntity>> {
+        query_as::<_, OverrideInputEntity>(
+            r#"
+                select
+                    id,
+                    name,
+                    default_value,
+                    override_id
+                from override_inputs
+                where override_id = ?1
+            "#,
+        )
+        .bind(override_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining inputs for override")
+    }
+}
+
+#[async_trait]
+impl TemplateOverrideRepositoryTrait for SqliteTemplateOverrideRepository {
+    async fn get_overrides(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Vec<TemplateOverrideInputsEntity>> {
+        let template_overrides = query_as::<_, TemplateOverrideEntity>(
+            r#"
+                select
+                    id,
+                    scope,
+                    body,
+                    template_id,
+                    created_at,
+                    updated_at
+                from template_overrides
+                where template_id = ?1
+                order by scope
+            "#,
+        )
+        .bind(template_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining overrides")?;
+
+        let mut overrides = Vec::new();
+        for template_override in template_overrides {
+            let inputs = self.get_override_inputs(template_override.id).await?;
+            overrides.push(TemplateOverrideInputsEntity::new(template_override, inputs));
+        }
+
+        Ok(overrides)
+    }
+
+    async fn get_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+    ) -> anyhow::Result<Option<TemplateOverrideInputsEntity>> {
+        let template_override = query_as::<_, TemplateOverrideEntity>(
+            r#"
+                select
+                    id,
+                    scope,
+                    body,
+                    template_id,
+                    created_at,
+                    updated_at
+                from template_overrides
+                where template_id = ?1
+                    and scope = ?2
+            "#,
+        )
+        .bind(template_id)
+        .bind(scope)
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining override")?;
+
+        match template_override {
+            Some(template_override) => {
+                let inputs = self.get_override_inputs(template_override.id).await?;
+                Ok(Some(TemplateOverrideInputsEntity::new(
+                    template_override,
+                    inputs,
+                )))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn set_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+        body: Option<&str>,
+        override_inputs: &[TemplateInput],
+    ) -> anyhow::Result<TemplateOverrideInputsEntity> {
+        let template_override = query_as::<_, TemplateOverrideEntity>(
+            r#"
+                insert into template_overrides (
+                        template_id,
+                        scope,
+                        body,
+                        created_at,
+                        updated_at
+                    )
+                values (
+                        ?1,
+                        ?2,
+                        ?3,
+                        ?4,
+                        ?4
+                    )
+                on conflict (template_id, scope) do update
+                set
+                    body = excluded.body,
+                    updated_at = excluded.updated_at
+                returning *
+            "#,
+        )
+        .bind(template_id)
+        .bind(scope)
+        .bind(body)
+        .bind(now())
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the override")?;
+
+        query_as::<_, OverrideInputEntity>(
+            r#"
+                delete from override_inputs
+                where
+                    override_id = ?1
+                returning *
+            "#,
+        )
+        .bind(template_override.id)
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while replacing the override inputs")?;
+
+        let inputs = query_as::<_, OverrideInputEntity>(
+            r#"
+                insert into override_inputs (
+                        name,
+                        default_value,
+                        override_id
+                    )
+                select
+                    json_extract(value, '$.name'),
+                    json_extract(value, '$.default_value'),
+                    ?2
+                from json_each(?1)
+                returning *
+            "#,
+        )
+        .bind(json_inputs(override_inputs))
+        .bind(template_override.id)
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while creating the override inputs")?;
+
+        Ok(TemplateOverrideInputsEntity::new(template_override, inputs))
+    }
+
+    async fn remove_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+    ) -> anyhow::Result<Option<TemplateOverrideInputsEntity>> {
+        let template_override = self.get_override(template_id, scope).await?;
+
+        query_as::<_, TemplateOverrideEntity>(
+            r#"
+                delete from template_overrides
+                where
+                    template_id = ?1
+                    and scope = ?2
+                returning *
+            "#,
+        )
+        .bind(template_id)
+        .bind(scope)
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the override")?;
+
+        Ok(template_override)
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use sqlx::{query_as, SqlitePool};
+
+use crate::repository::template_schema::{TemplateSchemaEntity, TemplateSchemaRepositoryTrait};
+
+use super::now;
+
+#[derive(Clone)]
+pub struct SqliteTemplateSchemaRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteTemplateSchemaRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TemplateSchemaRepositoryTrait for SqliteTemplateSchemaRepository {
+    async fn get_schema(&self, template_id: i64) -> anyhow::Result<Option<TemplateSchemaEntity>> {
+        query_as::<_, TemplateSchemaEntity>(
+            r#"
+                select
+                    id,
+                    json_schema,
+                    template_id,
+                    created_at,
+                    updated_at
+                from template_schemas
+                where template_id = ?1
+            "#,
+        )
+        .bind(template_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining the schema of template")
+    }
+
+    async fn set_schema(
+        &self,
+        template_id: i64,
+        json_schema: &str,
+    ) -> anyhow::Result<TemplateSchemaEntity> {
+        query_as::<_, TemplateSchemaEntity>(
+            r#"
+                insert into template_schemas (
+                        template_id,
+                        json_schema,
+                        created_at,
+                        updated_at
+                    )
+                values (
+                        ?1,
+                        ?2,
+                        ?3,
+                        ?3
+                    )
+                on conflict (template_id) do update
+                set
+                    json_schema = excluded.json_schema,
+                    updated_at = excluded.updated_at
+                returning *
+            "#,
+        )
+        .bind(template_id)
+        .bind(json_schema)
+        .bind(now())
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the schema")
+    }
+
+    async fn remove_schema(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Option<TemplateSchemaEntity>> {
+        query_as::<_, TemplateSchemaEntity>(
+            r#"
+                delete from template_schemas
+                where template_id = ?1
+                returning *
+            "#,
+        )
+        .bind(template_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the schema")
+    }
+}
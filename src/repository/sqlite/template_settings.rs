@@ -0,0 +1,79 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use sqlx::{query_as, SqlitePool};
+
+use crate::repository::template_settings::{
+    TemplateSettingsEntity, TemplateSettingsRepositoryTrait,
+};
+
+use super::now;
+
+#[derive(Clone)]
+pub struct SqliteTemplateSettingsRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteTemplateSettingsRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TemplateSettingsRepositoryTrait for SqliteTemplateSettingsRepository {
+    async fn get_settings(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Option<TemplateSettingsEntity>> {
+        query_as::<_, TemplateSettingsEntity>(
+            r#"
+                select
+                    id,
+                    settings,
+                    template_id,
+                    created_at,
+                    updated_at
+                from template_settings
+                where template_id = ?1
+            "#,
+        )
+        .bind(template_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining the settings of template")
+    }
+
+    async fn set_settings(
+        &self,
+        template_id: i64,
+        settings: &str,
+    ) -> anyhow::Result<TemplateSettingsEntity> {
+        query_as::<_, TemplateSettingsEntity>(
+            r#"
+                insert into template_settings (
+                        template_id,
+                        settings,
+                        created_at,
+                        updated_at
+                    )
+                values (
+                        ?1,
+                        ?2,
+                        ?3,
+                        ?3
+                    )
+                on conflict (template_id) do update
+                set
+                    settings = excluded.settings,
+                    updated_at = excluded.updated_at
+                returning *
+            "#,
+        )
+        .bind(template_id)
+        .bind(settings)
+        .bind(now())
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the settings")
+    }
+}
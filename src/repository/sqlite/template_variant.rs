@@ -0,0 +1,122 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use sqlx::{query_as, SqlitePool};
+
+use crate::repository::template_variant::{
+    TemplateVariantEntity, TemplateVariantRepositoryTrait, VariantLocaleEntity,
+};
+
+use super::now;
+
+#[derive(Clone)]
+pub struct SqliteTemplateVariantRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteTemplateVariantRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TemplateVariantRepositoryTrait for SqliteTemplateVariantRepository {
+    async fn get_variants(&self, template_id: i64) -> anyhow::Result<Vec<TemplateVariantEntity>> {
+        query_as::<_, TemplateVariantEntity>(
+            r#"
+                select
+                    id,
+                    locale,
+                    body,
+                    template_id,
+                    created_at,
+                    updated_at
+                from template_variants
+                where template_id = ?1
+            "#,
+        )
+        .bind(template_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining variants for template")
+    }
+
+    async fn get_variant_locales(
+        &self,
+        template_ids: &[i64],
+    ) -> anyhow::Result<Vec<VariantLocaleEntity>> {
+        query_as::<_, VariantLocaleEntity>(
+            r#"
+                select
+                    template_id,
+                    locale
+                from template_variants
+                where template_id in (select value from json_each(?1))
+                order by locale
+            "#,
+        )
+        .bind(serde_json::to_string(template_ids)?)
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining locales for templates")
+    }
+
+    async fn set_variant(
+        &self,
+        template_id: i64,
+        locale: &str,
+        body: &str,
+    ) -> anyhow::Result<TemplateVariantEntity> {
+        query_as::<_, TemplateVariantEntity>(
+            r#"
+                insert into template_variants (
+                        template_id,
+                        locale,
+                        body,
+                        created_at,
+                        updated_at
+                    )
+                values (
+                        ?1,
+                        ?2,
+                        ?3,
+                        ?4,
+                        ?4
+                    )
+                on conflict (template_id, locale) do update
+                set
+                    body = excluded.body,
+                    updated_at = excluded.updated_at
+                returning *
+            "#,
+        )
+        .bind(template_id)
+        .bind(locale)
+        .bind(body)
+        .bind(now())
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the variant")
+    }
+
+    async fn remove_variant(
+        &self,
+        template_id: i64,
+        locale: &str,
+    ) -> anyhow::Result<Option<TemplateVariantEntity>> {
+        query_as::<_, TemplateVariantEntity>(
+            r#"
+                delete from template_variants
+                where
+                    template_id = ?1
+                    and locale = ?2
+                returning *
+            "#,
+        )
+        .bind(template_id)
+        .bind(locale)
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the variant")
+    }
+}
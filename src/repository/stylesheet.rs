@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::repository::connection_pool::ServiceConnectionPool;
+use serde::{Deserialize, Serialize};
+use sqlx::{query_as, types::time::OffsetDateTime, FromRow};
+
+#[derive(FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct StylesheetEntity {
+    pub id: i64,
+    pub tenant: String,
+    pub css: String,
+    #[serde(with = "super::timestamp")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "super::timestamp")]
+    pub updated_at: OffsetDateTime,
+}
+
+#[async_trait]
+pub trait StylesheetRepositoryTrait {
+    async fn get_stylesheet(&self, tenant: &str) -> anyhow::Result<Option<StylesheetEntity>>;
+    async fn set_stylesheet(&self, tenant: &str, css: &str) -> anyhow::Result<StylesheetEntity>;
+    async fn remove_stylesheet(&self, tenant: &str) -> anyhow::Result<Option<StylesheetEntity>>;
+}
+
+pub type DynStylesheetRepositoryTrait = Arc<dyn StylesheetRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct StylesheetRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl StylesheetRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl StylesheetRepositoryTrait for StylesheetRepository {
+    async fn get_stylesheet(&self, tenant: &str) -> anyhow::Result<Option<StylesheetEntity>> {
+        query_as!(
+            StylesheetEntity,
+            r#"
+                select
+                    id,
+                    tenant,
+                    css,
+                    created_at,
+                    updated_at
+                from stylesheets
+                where tenant = $1::varchar
+            "#,
+            tenant
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining the stylesheet of tenant")
+    }
+
+    async fn set_stylesheet(&self, tenant: &str, css: &str) -> anyhow::Result<StylesheetEntity> {
+        query_as!(
+            StylesheetEntity,
+            r#"
+                insert into stylesheets (
+                        tenant,
+                        css
+                    )
+                values (
+                        $1::varchar,
+                        $2::varchar
+                    )
+                on conflict (tenant) do update
+                set
+                    css = excluded.css,
+                    updated_at = current_timestamp
+                returning *
+            "#,
+            tenant,
+            css,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the stylesheet")
+    }
+
+    async fn remove_stylesheet(&self, tenant: &str) -> anyhow::Result<Option<StylesheetEntity>> {
+        query_as!(
+            StylesheetEntity,
+            r#"
+                delete from stylesheets
+                where tenant = $1::varchar
+                returning *
+            "#,
+            tenant,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the stylesheet")
+    }
+}
@@ -8,7 +8,12 @@ use madtofan_microservice_common::{
 };
 use sqlx::{query, query_as, types::time::OffsetDateTime, FromRow};
 
-use super::input::{DynInputRepositoryTrait, InputEntity};
+use crate::service::namespace::join_path;
+
+use super::input::{delete_inputs, insert_inputs, DynInputRepositoryTrait, InputEntity};
+
+/// Tenant owning the shared templates every other tenant falls back to.
+pub const GLOBAL_TENANT: &str = "global";
 
 #[derive(FromRow)]
 pub struct TemplateEntity {
@@ -18,6 +23,12 @@ pub struct TemplateEntity {
     pub name: String,
     pub description: String,
     pub body: String,
+    pub engine: String,
+    pub content_type: String,
+    pub namespace: String,
+    pub tenant: String,
+    pub cloned_from: Option<i64>,
+    pub deleted_at: Option<OffsetDateTime>,
 }
 
 #[derive(FromRow, Debug, Clone)]
@@ -28,13 +39,23 @@ pub struct TemplateInputsEntity {
     pub name: String,
     pub description: String,
     pub body: String,
+    pub engine: String,
+    pub content_type: String,
+    pub namespace: String,
+    pub tenant: String,
+    pub cloned_from: Option<i64>,
+    pub deleted_at: Option<OffsetDateTime>,
     pub inputs: Vec<InputEntity>,
 }
 
 impl TemplateInputsEntity {
+    pub fn path(&self) -> String {
+        join_path(&self.namespace, &self.name)
+    }
+
     pub fn into_template_response(self) -> TemplateResponse {
         TemplateResponse {
-            name: self.name,
+            name: self.path(),
             description: self.description,
             template_inputs: self
                 .inputs
@@ -48,7 +69,7 @@ impl TemplateInputsEntity {
 impl From<TemplateInputsEntity> for TemplateResponse {
     fn from(template_entity: TemplateInputsEntity) -> Self {
         Self {
-            name: template_entity.name,
+            name: template_entity.path(),
             description: template_entity.description,
             template_inputs: template_entity
                 .inputs
@@ -59,23 +80,91 @@ impl From<TemplateInputsEntity> for TemplateResponse {
     }
 }
 
+/// What `add_template` and `update_template` write into a template.
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateFields<'a> {
+    pub description: &'a str,
+    pub body: &'a str,
+    pub engine: &'a str,
+    pub content_type: &'a str,
+    pub inputs: &'a [TemplateInput],
+}
+
 #[async_trait]
 pub trait TemplateRepositoryTrait {
     async fn list_templates(
         &self,
+        tenant: &str,
+        namespace: Option<&str>,
+        trashed: bool,
         offset: i64,
         limit: i64,
     ) -> anyhow::Result<Vec<TemplateInputsEntity>>;
-    async fn get_templates_count(&self) -> anyhow::Result<i64>;
-    async fn get_template(&self, name: &str) -> anyhow::Result<Option<TemplateInputsEntity>>;
+    async fn get_templates_count(
+        &self,
+        tenant: &str,
+        namespace: Option<&str>,
+        trashed: bool,
+    ) -> anyhow::Result<i64>;
+    async fn get_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>>;
     async fn add_template(
         &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        fields: TemplateFields<'_>,
+    ) -> anyhow::Result<TemplateInputsEntity>;
+    /// Replaces the description, body, engine, content type and inputs of the template,
+    /// only when it was last updated at `expected_updated_at` if one is given.
+    async fn update_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        fields: TemplateFields<'_>,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>>;
+    /// Moves the template to the trash, it is kept with its inputs until restored or
+    /// purged. Only removed when it was last updated at `expected_updated_at` if one is
+    /// given.
+    async fn remove_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>>;
+    /// Restores the most recently trashed template of that name.
+    async fn restore_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>>;
+    async fn purge_templates(&self, deleted_before: OffsetDateTime) -> anyhow::Result<u64>;
+    async fn rename_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        new_namespace: &str,
+        new_name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>>;
+    async fn clone_template(
+        &self,
+        template_id: i64,
+        tenant: &str,
+        namespace: &str,
         name: &str,
-        description: &str,
-        body: &str,
-        template_inputs: &[TemplateInput],
     ) -> anyhow::Result<TemplateInputsEntity>;
-    async fn remove_template(&self, name: &str) -> anyhow::Result<Option<TemplateInputsEntity>>;
+    /// Bumps the `updated_at` of the template after a write to what is stored alongside
+    /// it, such as its overrides, variants, JSON schema or settings.
+    async fn touch_template(&self, template_id: i64) -> anyhow::Result<()>;
 }
 
 pub type DynTemplateRepositoryTrait = Arc<dyn TemplateRepositoryTrait + Send + Sync>;
@@ -99,6 +188,9 @@ impl TemplateRepository {
 impl TemplateRepositoryTrait for TemplateRepository {
     async fn list_templates(
         &self,
+        tenant: &str,
+        namespace: Option<&str>,
+        trashed: bool,
         offset: i64,
         limit: i64,
     ) -> anyhow::Result<Vec<TemplateInputsEntity>> {
@@ -110,6 +202,12 @@ impl TemplateRepositoryTrait for TemplateRepository {
                     t.name as name,
                     t.description as description,
                     t.body as body,
+                    t.engine as engine,
+                    t.content_type as content_type,
+                    t.namespace as namespace,
+                    t.tenant as tenant,
+                    t.cloned_from as cloned_from,
+                    t.deleted_at as deleted_at,
                     t.created_at as created_at,
                     t.updated_at as updated_at,
                     array_agg((
@@ -121,10 +219,21 @@ impl TemplateRepositoryTrait for TemplateRepository {
                 from templates as t
                 left join inputs as i
                     on t.id = i.template_id
+                where t.tenant = $1::varchar
+                    and (
+                        $2::varchar is null
+                        or t.namespace = $2::varchar
+                        or left(t.namespace, length($2::varchar) + 1) = $2::varchar || '/'
+                    )
+                    and (t.deleted_at is not null) = $3::bool
                 group by t.id
-                limit $1::int
-                offset $2::int
+                order by t.namespace, t.name, t.id
+                limit $4::int
+                offset $5::int
             "#,
+            tenant,
+            namespace,
+            trashed,
             limit as i32,
             offset as i32,
         )
@@ -133,13 +242,28 @@ impl TemplateRepositoryTrait for TemplateRepository {
         .context("an unexpected error occured while obtaining template")
     }
 
-    async fn get_templates_count(&self) -> anyhow::Result<i64> {
+    async fn get_templates_count(
+        &self,
+        tenant: &str,
+        namespace: Option<&str>,
+        trashed: bool,
+    ) -> anyhow::Result<i64> {
         let count_result = query!(
             r#"
                 select
                     count(*)
                 from templates
+                where tenant = $1::varchar
+                    and (
+                        $2::varchar is null
+                        or namespace = $2::varchar
+                        or left(namespace, length($2::varchar) + 1) = $2::varchar || '/'
+                    )
+                    and (deleted_at is not null) = $3::bool
             "#,
+            tenant,
+            namespace,
+            trashed,
         )
         .fetch_one(&self.pool)
         .await?;
@@ -147,7 +271,12 @@ impl TemplateRepositoryTrait for TemplateRepository {
         Ok(count_result.count.unwrap())
     }
 
-    async fn get_template(&self, name: &str) -> anyhow::Result<Option<TemplateInputsEntity>> {
+    async fn get_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
         query_as!(
             TemplateInputsEntity,
             r#"
@@ -156,6 +285,12 @@ impl TemplateRepositoryTrait for TemplateRepository {
                     t.name as name,
                     t.description as description,
                     t.body as body,
+                    t.engine as engine,
+                    t.content_type as content_type,
+                    t.namespace as namespace,
+                    t.tenant as tenant,
+                    t.cloned_from as cloned_from,
+                    t.deleted_at as deleted_at,
                     t.created_at as created_at,
                     t.updated_at as updated_at,
                     array_agg((
@@ -167,9 +302,14 @@ impl TemplateRepositoryTrait for TemplateRepository {
                 from templates as t
                 left join inputs as i
                     on t.id = i.template_id
-                where t.name = $1::varchar
+                where t.tenant = $1::varchar
+                    and t.namespace = $2::varchar
+                    and t.name = $3::varchar
+                    and t.deleted_at is null
                 group by t.id
             "#,
+            tenant,
+            namespace,
             name
         )
         .fetch_optional(&self.pool)
@@ -179,69 +319,318 @@ impl TemplateRepositoryTrait for TemplateRepository {
 
     async fn add_template(
         &self,
+        tenant: &str,
+        namespace: &str,
         name: &str,
-        description: &str,
-        body: &str,
-        template_inputs: &[TemplateInput],
+        fields: TemplateFields<'_>,
     ) -> anyhow::Result<TemplateInputsEntity> {
         let add_template_response = query_as!(
             TemplateEntity,
             r#"
                 insert into templates (
+                        tenant,
+                        namespace,
                         name,
                         description,
-                        body
+                        body,
+                        engine,
+                        content_type
                     )
                 values (
                         $1::varchar,
                         $2::varchar,
-                        $3::varchar
+                        $3::varchar,
+                        $4::varchar,
+                        $5::varchar,
+                        $6::varchar,
+                        $7::varchar
                     )
                 returning *
             "#,
+            tenant,
+            namespace,
             name,
-            description,
-            body,
+            fields.description,
+            fields.body,
+            fields.engine,
+            fields.content_type,
         )
         .fetch_one(&self.pool)
         .await
         .context("an unexpected error occured while creating the template")?;
 
         self.inputs_repository
-            .add_inputs(template_inputs, add_template_response.id)
+            .add_inputs(fields.inputs, add_template_response.id)
             .await?;
 
-        self.get_template(name)
+        self.get_template(tenant, namespace, name)
             .await?
             .context("an unexpected error occured while obtaining the newly created template")
     }
 
-    async fn remove_template(&self, name: &str) -> anyhow::Result<Option<TemplateInputsEntity>> {
-        let template_to_remove = self
-            .get_template(name)
-            .await?
-            .context("an unexpected error occured while looking for template to remove")
-            .unwrap();
+    async fn update_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        fields: TemplateFields<'_>,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .context("an unexpected error occured while starting the template update")?;
+        let updated_template = query_as!(
+            TemplateEntity,
+            r#"
+                update templates
+                set
+                    description = $4::varchar,
+                    body = $5::varchar,
+                    engine = $6::varchar,
+                    content_type = $7::varchar,
+                    updated_at = current_timestamp
+                where
+                    tenant = $1::varchar
+                    and namespace = $2::varchar
+                    and name = $3::varchar
+                    and deleted_at is null
+                    and ($8::timestamptz is null or updated_at = $8::timestamptz)
+                returning *
+            "#,
+            tenant,
+            namespace,
+            name,
+            fields.description,
+            fields.body,
+            fields.engine,
+            fields.content_type,
+            expected_updated_at,
+        )
+        .fetch_optional(&mut *transaction)
+        .await
+        .context("an unexpected error occured while updating the template")?;
 
-        self.inputs_repository
-            .remove_inputs(template_to_remove.id)
-            .await?;
+        let template = match updated_template {
+            Some(template) => template,
+            None => return Ok(None),
+        };
+        // The row and its inputs are replaced together, so a failure part way leaves
+        // neither a bumped revision nor missing inputs behind.
+        delete_inputs(&mut *transaction, template.id).await?;
+        insert_inputs(&mut *transaction, fields.inputs, template.id).await?;
+        transaction
+            .commit()
+            .await
+            .context("an unexpected error occured while committing the template update")?;
 
-        query_as!(
+        self.get_template(tenant, namespace, name).await
+    }
+
+    async fn remove_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let template_to_remove = self.get_template(tenant, namespace, name).await?;
+
+        let removed_template = query_as!(
+            TemplateEntity,
+            r#"
+                update templates
+                set
+                    deleted_at = current_timestamp
+                where
+                    tenant = $1::varchar
+                    and namespace = $2::varchar
+                    and name = $3::varchar
+                    and deleted_at is null
+                    and ($4::timestamptz is null or updated_at = $4::timestamptz)
+                returning *
+            "#,
+            tenant,
+            namespace,
+            name,
+            expected_updated_at,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the template")?;
+
+        Ok(removed_template.and(template_to_remove))
+    }
+
+    async fn restore_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let restored_template = query_as!(
+            TemplateEntity,
+            r#"
+                update templates
+                set
+                    deleted_at = null,
+                    updated_at = current_timestamp
+                where id = (
+                    select id
+                    from templates
+                    where
+                        tenant = $1::varchar
+                        and namespace = $2::varchar
+                        and name = $3::varchar
+                        and deleted_at is not null
+                    order by deleted_at desc
+                    limit 1
+                )
+                returning *
+            "#,
+            tenant,
+            namespace,
+            name,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while restoring the template")?;
+
+        match restored_template {
+            Some(_) => self.get_template(tenant, namespace, name).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn purge_templates(&self, deleted_before: OffsetDateTime) -> anyhow::Result<u64> {
+        let purge_result = query!(
+            r#"
+                delete from templates
+                where deleted_at < $1::timestamptz
+            "#,
+            deleted_before,
+        )
+        .execute(&self.pool)
+        .await
+        .context("an unexpected error occured while purging trashed templates")?;
+
+        Ok(purge_result.rows_affected())
+    }
+
+    async fn rename_template(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        new_namespace: &str,
+        new_name: &str,
+    ) -> anyhow::Result<Option<TemplateInputsEntity>> {
+        let renamed_template = query_as!(
             TemplateEntity,
             r#"
-                delete from templates 
-                where 
-                    name = $1::varchar 
+                update templates
+                set
+                    namespace = $4::varchar,
+                    name = $5::varchar,
+                    updated_at = current_timestamp
+                where
+                    tenant = $1::varchar
+                    and namespace = $2::varchar
+                    and name = $3::varchar
+                    and deleted_at is null
                 returning *
             "#,
+            tenant,
+            namespace,
             name,
+            new_namespace,
+            new_name,
         )
         .fetch_optional(&self.pool)
         .await
-        .context("an unexpected error occured while removing the template")
-        .unwrap();
+        .context("an unexpected error occured while renaming the template")?;
+
+        match renamed_template {
+            Some(template) => {
+                self.get_template(&template.tenant, &template.namespace, &template.name)
+                    .await
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn clone_template(
+        &self,
+        template_id: i64,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> anyhow::Result<TemplateInputsEntity> {
+        let cloned_template = query_as!(
+            TemplateEntity,
+            r#"
+                insert into templates (
+                        tenant,
+                        namespace,
+                        name,
+                        description,
+                        body,
+                        engine,
+                        content_type,
+                        cloned_from
+                    )
+                select
+                    $2::varchar,
+                    $3::varchar,
+                    $4::varchar,
+                    description,
+                    body,
+                    engine,
+                    content_type,
+                    id
+                from templates
+                where id = $1::bigint
+                returning *
+            "#,
+            template_id,
+            tenant,
+            namespace,
+            name,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while cloning the template")?;
+
+        let template_inputs = self
+            .inputs_repository
+            .get_template_inputs(template_id)
+            .await?
+            .into_iter()
+            .map(|input| input.into())
+            .collect::<Vec<TemplateInput>>();
+        self.inputs_repository
+            .add_inputs(&template_inputs, cloned_template.id)
+            .await?;
+
+        self.get_template(tenant, namespace, name)
+            .await?
+            .context("an unexpected error occured while obtaining the cloned template")
+    }
+
+    async fn touch_template(&self, template_id: i64) -> anyhow::Result<()> {
+        query!(
+            r#"
+                update templates
+                set updated_at = current_timestamp
+                where id = $1::bigint
+            "#,
+            template_id,
+        )
+        .execute(&self.pool)
+        .await
+        .context("an unexpected error occured while updating the template")?;
 
-        Ok(Some(template_to_remove))
+        Ok(())
     }
 }
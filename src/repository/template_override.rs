@@ -0,0 +1,302 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::{
+    repository::connection_pool::ServiceConnectionPool, templating::TemplateInput,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{query_as, types::time::OffsetDateTime, FromRow};
+
+#[derive(FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct TemplateOverrideEntity {
+    pub id: i64,
+    pub scope: String,
+    pub body: Option<String>,
+    pub template_id: i64,
+    #[serde(with = "super::timestamp")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "super::timestamp")]
+    pub updated_at: OffsetDateTime,
+}
+
+#[derive(FromRow, Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct OverrideInputEntity {
+    pub id: i64,
+    pub name: String,
+    pub default_value: String,
+    pub override_id: i64,
+}
+
+/// A named layer on top of a base template, replacing its body and/or some of its
+/// input default values.
+#[derive(Debug, Clone)]
+pub struct TemplateOverrideInputsEntity {
+    pub id: i64,
+    pub scope: String,
+    pub body: Option<String>,
+    pub template_id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub inputs: Vec<OverrideInputEntity>,
+}
+
+impl TemplateOverrideInputsEntity {
+    pub fn new(
+        template_override: TemplateOverrideEntity,
+        inputs: Vec<OverrideInputEntity>,
+    ) -> Self {
+        Self {
+            id: template_override.id,
+            scope: template_override.scope,
+            body: template_override.body,
+            template_id: template_override.template_id,
+            created_at: template_override.created_at,
+            updated_at: template_override.updated_at,
+            inputs,
+        }
+    }
+}
+
+#[async_trait]
+pub trait TemplateOverrideRepositoryTrait {
+    async fn get_overrides(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Vec<TemplateOverrideInputsEntity>>;
+    async fn get_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+    ) -> anyhow::Result<Option<TemplateOverrideInputsEntity>>;
+    async fn set_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+        body: Option<&str>,
+        override_inputs: &[TemplateInput],
+    ) -> anyhow::Result<TemplateOverrideInputsEntity>;
+    async fn remove_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+    ) -> anyhow::Result<Option<TemplateOverrideInputsEntity>>;
+}
+
+pub type DynTemplateOverrideRepositoryTrait =
+    Arc<dyn TemplateOverrideRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct TemplateOverrideRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl TemplateOverrideRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+
+    async fn get_override_inputs(
+        &self,
+        override_id: i64,
+    ) -> anyhow::Result<Vec<OverrideInputEntity>> {
+        query_as!(
+            OverrideInputEntity,
+            r#"
+                select
+                    id,
+                    name,
+                    default_value,
+                    override_id
+                from override_inputs
+                where override_id = $1::bigint
+            "#,
+            override_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining inputs for override")
+    }
+}
+
+#[async_trait]
+impl TemplateOverrideRepositoryTrait for TemplateOverrideRepository {
+    async fn get_overrides(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Vec<TemplateOverrideInputsEntity>> {
+        let template_overrides = query_as!(
+            TemplateOverrideEntity,
+            r#"
+                select
+                    id,
+                    scope,
+                    body,
+                    template_id,
+                    created_at,
+                    updated_at
+                from template_overrides
+                where template_id = $1::bigint
+                order by scope
+            "#,
+            template_id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining overrides")?;
+
+        let mut overrides = Vec::new();
+        for template_override in template_overrides {
+            let inputs = self.get_override_inputs(template_override.id).await?;
+            overrides.push(TemplateOverrideInputsEntity::new(template_override, inputs));
+        }
+
+        Ok(overrides)
+    }
+
+    async fn get_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+    ) -> anyhow::Result<Option<TemplateOverrideInputsEntity>> {
+        let template_override = query_as!(
+            TemplateOverrideEntity,
+            r#"
+                select
+                    id,
+                    scope,
+                    body,
+                    template_id,
+                    created_at,
+                    updated_at
+                from template_overrides
+                where template_id = $1::bigint
+                    and scope = $2::varchar
+            "#,
+            template_id,
+            scope
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining override")?;
+
+        match template_override {
+            Some(template_override) => {
+                let inputs = self.get_override_inputs(template_override.id).await?;
+                Ok(Some(TemplateOverrideInputsEntity::new(
+                    template_override,
+                    inputs,
+                )))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn set_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+        body: Option<&str>,
+        override_inputs: &[TemplateInput],
+    ) -> anyhow::Result<TemplateOverrideInputsEntity> {
+        let template_override = query_as!(
+            TemplateOverrideEntity,
+            r#"
+                insert into template_overrides (
+                        template_id,
+                        scope,
+                        body
+                    )
+                values (
+                        $1::bigint,
+                        $2::varchar,
+                        $3::varchar
+                    )
+                on conflict (template_id, scope) do update
+                set
+                    body = excluded.body,
+                    updated_at = current_timestamp
+                returning *
+            "#,
+            template_id,
+            scope,
+            body,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the override")?;
+
+        query_as!(
+            OverrideInputEntity,
+            r#"
+                delete from override_inputs
+                where
+                    override_id = $1::bigint
+                returning *
+            "#,
+            template_override.id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while replacing the override inputs")?;
+
+        let mut names: Vec<String> = Vec::new();
+        let mut default_values: Vec<String> = Vec::new();
+        let mut override_ids: Vec<i64> = Vec::new();
+        override_inputs.iter().cloned().for_each(|input| {
+            names.push(input.name);
+            default_values.push(input.default_value);
+            override_ids.push(template_override.id);
+        });
+        let inputs = query_as!(
+            OverrideInputEntity,
+            r#"
+                insert into override_inputs (
+                        name,
+                        default_value,
+                        override_id
+                    )
+                select * from unnest (
+                        $1::text[],
+                        $2::text[],
+                        $3::bigint[]
+                    )
+                returning *
+            "#,
+            &names,
+            &default_values,
+            &override_ids
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while creating the override inputs")?;
+
+        Ok(TemplateOverrideInputsEntity::new(template_override, inputs))
+    }
+
+    async fn remove_override(
+        &self,
+        template_id: i64,
+        scope: &str,
+    ) -> anyhow::Result<Option<TemplateOverrideInputsEntity>> {
+        let template_override = self.get_override(template_id, scope).await?;
+
+        query_as!(
+            TemplateOverrideEntity,
+            r#"
+                delete from template_overrides
+                where
+                    template_id = $1::bigint
+                    and scope = $2::varchar
+                returning *
+            "#,
+            template_id,
+            scope,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the override")?;
+
+        Ok(template_override)
+    }
+}
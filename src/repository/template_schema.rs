@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::repository::connection_pool::ServiceConnectionPool;
+use serde::{Deserialize, Serialize};
+use sqlx::{query_as, types::time::OffsetDateTime, FromRow};
+
+#[derive(FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct TemplateSchemaEntity {
+    pub id: i64,
+    pub json_schema: String,
+    pub template_id: i64,
+    #[serde(with = "super::timestamp")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "super::timestamp")]
+    pub updated_at: OffsetDateTime,
+}
+
+#[async_trait]
+pub trait TemplateSchemaRepositoryTrait {
+    async fn get_schema(&self, template_id: i64) -> anyhow::Result<Option<TemplateSchemaEntity>>;
+    async fn set_schema(
+        &self,
+        template_id: i64,
+        json_schema: &str,
+    ) -> anyhow::Result<TemplateSchemaEntity>;
+    async fn remove_schema(&self, template_id: i64)
+        -> anyhow::Result<Option<TemplateSchemaEntity>>;
+}
+
+pub type DynTemplateSchemaRepositoryTrait = Arc<dyn TemplateSchemaRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct TemplateSchemaRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl TemplateSchemaRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TemplateSchemaRepositoryTrait for TemplateSchemaRepository {
+    async fn get_schema(&self, template_id: i64) -> anyhow::Result<Option<TemplateSchemaEntity>> {
+        query_as!(
+            TemplateSchemaEntity,
+            r#"
+                select
+                    id,
+                    json_schema,
+                    template_id,
+                    created_at,
+                    updated_at
+                from template_schemas
+                where template_id = $1::bigint
+            "#,
+            template_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining the schema of template")
+    }
+
+    async fn set_schema(
+        &self,
+        template_id: i64,
+        json_schema: &str,
+    ) -> anyhow::Result<TemplateSchemaEntity> {
+        query_as!(
+            TemplateSchemaEntity,
+            r#"
+                insert into template_schemas (
+                        template_id,
+                        json_schema
+                    )
+                values (
+                        $1::bigint,
+                        $2::varchar
+                    )
+                on conflict (template_id) do update
+                set
+                    json_schema = excluded.json_schema,
+                    updated_at = current_timestamp
+                returning *
+            "#,
+            template_id,
+            json_schema,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the schema")
+    }
+
+    async fn remove_schema(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Option<TemplateSchemaEntity>> {
+        query_as!(
+            TemplateSchemaEntity,
+            r#"
+                delete from template_schemas
+                where template_id = $1::bigint
+                returning *
+            "#,
+            template_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the schema")
+    }
+}
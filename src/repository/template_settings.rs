@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::repository::connection_pool::ServiceConnectionPool;
+use serde::{Deserialize, Serialize};
+use sqlx::{query_as, types::time::OffsetDateTime, FromRow};
+
+#[derive(FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct TemplateSettingsEntity {
+    pub id: i64,
+    pub settings: String,
+    pub template_id: i64,
+    #[serde(with = "super::timestamp")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "super::timestamp")]
+    pub updated_at: OffsetDateTime,
+}
+
+#[async_trait]
+pub trait TemplateSettingsRepositoryTrait {
+    async fn get_settings(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Option<TemplateSettingsEntity>>;
+    async fn set_settings(
+        &self,
+        template_id: i64,
+        settings: &str,
+    ) -> anyhow::Result<TemplateSettingsEntity>;
+}
+
+pub type DynTemplateSettingsRepositoryTrait =
+    Arc<dyn TemplateSettingsRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct TemplateSettingsRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl TemplateSettingsRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TemplateSettingsRepositoryTrait for TemplateSettingsRepository {
+    async fn get_settings(
+        &self,
+        template_id: i64,
+    ) -> anyhow::Result<Option<TemplateSettingsEntity>> {
+        query_as!(
+            TemplateSettingsEntity,
+            r#"
+                select
+                    id,
+                    settings,
+                    template_id,
+                    created_at,
+                    updated_at
+                from template_settings
+                where template_id = $1::bigint
+            "#,
+            template_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining the settings of template")
+    }
+
+    async fn set_settings(
+        &self,
+        template_id: i64,
+        settings: &str,
+    ) -> anyhow::Result<TemplateSettingsEntity> {
+        query_as!(
+            TemplateSettingsEntity,
+            r#"
+                insert into template_settings (
+                        template_id,
+                        settings
+                    )
+                values (
+                        $1::bigint,
+                        $2::varchar
+                    )
+                on conflict (template_id) do update
+                set
+                    settings = excluded.settings,
+                    updated_at = current_timestamp
+                returning *
+            "#,
+            template_id,
+            settings,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the settings")
+    }
+}
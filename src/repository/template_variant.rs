@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use madtofan_microservice_common::repository::connection_pool::ServiceConnectionPool;
+use serde::{Deserialize, Serialize};
+use sqlx::{query_as, types::time::OffsetDateTime, FromRow};
+
+#[derive(FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct TemplateVariantEntity {
+    pub id: i64,
+    pub locale: String,
+    pub body: String,
+    pub template_id: i64,
+    #[serde(with = "super::timestamp")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "super::timestamp")]
+    pub updated_at: OffsetDateTime,
+}
+
+#[derive(FromRow, Debug, Eq, PartialEq, Clone)]
+pub struct VariantLocaleEntity {
+    pub template_id: i64,
+    pub locale: String,
+}
+
+#[async_trait]
+pub trait TemplateVariantRepositoryTrait {
+    async fn get_variants(&self, template_id: i64) -> anyhow::Result<Vec<TemplateVariantEntity>>;
+    async fn get_variant_locales(
+        &self,
+        template_ids: &[i64],
+    ) -> anyhow::Result<Vec<VariantLocaleEntity>>;
+    async fn set_variant(
+        &self,
+        template_id: i64,
+        locale: &str,
+        body: &str,
+    ) -> anyhow::Result<TemplateVariantEntity>;
+    async fn remove_variant(
+        &self,
+        template_id: i64,
+        locale: &str,
+    ) -> anyhow::Result<Option<TemplateVariantEntity>>;
+}
+
+pub type DynTemplateVariantRepositoryTrait = Arc<dyn TemplateVariantRepositoryTrait + Send + Sync>;
+
+#[derive(Clone)]
+pub struct TemplateVariantRepository {
+    pool: ServiceConnectionPool,
+}
+
+impl TemplateVariantRepository {
+    pub fn new(pool: ServiceConnectionPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TemplateVariantRepositoryTrait for TemplateVariantRepository {
+    async fn get_variants(&self, template_id: i64) -> anyhow::Result<Vec<TemplateVariantEntity>> {
+        query_as!(
+            TemplateVariantEntity,
+            r#"
+                select
+                    id,
+                    locale,
+                    body,
+                    template_id,
+                    created_at,
+                    updated_at
+                from template_variants
+                where template_id = $1::bigint
+            "#,
+            template_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining variants for template")
+    }
+
+    async fn get_variant_locales(
+        &self,
+        template_ids: &[i64],
+    ) -> anyhow::Result<Vec<VariantLocaleEntity>> {
+        query_as!(
+            VariantLocaleEntity,
+            r#"
+                select
+                    template_id,
+                    locale
+                from template_variants
+                where template_id = any($1::bigint[])
+                order by locale
+            "#,
+            template_ids
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("an unexpected error occured while obtaining locales for templates")
+    }
+
+    async fn set_variant(
+        &self,
+        template_id: i64,
+        locale: &str,
+        body: &str,
+    ) -> anyhow::Result<TemplateVariantEntity> {
+        query_as!(
+            TemplateVariantEntity,
+            r#"
+                insert into template_variants (
+                        template_id,
+                        locale,
+                        body
+                    )
+                values (
+                        $1::bigint,
+                        $2::varchar,
+                        $3::varchar
+                    )
+                on conflict (template_id, locale) do update
+                set
+                    body = excluded.body,
+                    updated_at = current_timestamp
+                returning *
+            "#,
+            template_id,
+            locale,
+            body,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("an unexpected error occured while saving the variant")
+    }
+
+    async fn remove_variant(
+        &self,
+        template_id: i64,
+        locale: &str,
+    ) -> anyhow::Result<Option<TemplateVariantEntity>> {
+        query_as!(
+            TemplateVariantEntity,
+            r#"
+                delete from template_variants
+                where
+                    template_id = $1::bigint
+                    and locale = $2::varchar
+                returning *
+            "#,
+            template_id,
+            locale,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("an unexpected error occured while removing the variant")
+    }
+}
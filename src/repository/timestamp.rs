@@ -0,0 +1,41 @@
+//! Serializes timestamps as microseconds since the Unix epoch, the precision they are
+//! stored with.
+
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+use sqlx::types::time::OffsetDateTime;
+
+pub fn serialize<S: Serializer>(time: &OffsetDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64((time.unix_timestamp_nanos() / 1_000) as i64)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OffsetDateTime, D::Error> {
+    let micros = i64::deserialize(deserializer)?;
+    OffsetDateTime::from_unix_timestamp_nanos(micros as i128 * 1_000).map_err(D::Error::custom)
+}
+
+/// The same for timestamps that may be missing, such as in hand-written files.
+pub mod option {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use sqlx::types::time::OffsetDateTime;
+
+    pub fn serialize<S: Serializer>(
+        time: &Option<OffsetDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => super::serialize(time, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<OffsetDateTime>, D::Error> {
+        Option::<i64>::deserialize(deserializer)?
+            .map(|micros| {
+                OffsetDateTime::from_unix_timestamp_nanos(micros as i128 * 1_000)
+                    .map_err(serde::de::Error::custom)
+            })
+            .transpose()
+    }
+}
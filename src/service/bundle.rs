@@ -0,0 +1,247 @@
+use clap::ValueEnum;
+use madtofan_microservice_common::{
+    errors::{ServiceError, ServiceResult},
+    templating::TemplateInput,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::repository::{
+    template::TemplateInputsEntity, template_override::TemplateOverrideInputsEntity,
+    template_schema::TemplateSchemaEntity, template_variant::TemplateVariantEntity,
+};
+
+use super::{
+    content::{parse_content_type, ContentType},
+    engine::{parse_engine, TemplateEngineKind},
+    settings::TemplateSettings,
+};
+
+/// Version of the bundle format written by this service, bundles of other versions are
+/// rejected on import.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// A portable, versioned set of templates with their inputs, overrides, locale
+/// variants, JSON Schemas and settings, serialised as JSON.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TemplateBundle {
+    pub version: u32,
+    pub templates: Vec<BundleTemplate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BundleTemplate {
+    pub name: String,
+    pub description: String,
+    pub body: String,
+    #[serde(default)]
+    pub engine: TemplateEngineKind,
+    #[serde(default)]
+    pub content_type: ContentType,
+    pub inputs: Vec<BundleInput>,
+    #[serde(default)]
+    pub overrides: Vec<BundleOverride>,
+    #[serde(default)]
+    pub variants: Vec<BundleVariant>,
+    #[serde(default)]
+    pub json_schema: Option<serde_json::Value>,
+    #[serde(default)]
+    pub settings: TemplateSettings,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BundleInput {
+    pub name: String,
+    #[serde(default)]
+    pub default_value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BundleOverride {
+    pub scope: String,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub inputs: Vec<BundleInput>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BundleVariant {
+    pub locale: String,
+    pub body: String,
+}
+
+impl TemplateBundle {
+    pub fn new(templates: Vec<BundleTemplate>) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            templates,
+        }
+    }
+
+    pub fn check_version(&self) -> ServiceResult<()> {
+        if self.version != BUNDLE_VERSION {
+            return Err(ServiceError::BadRequest(format!(
+                "bundle version {} is not supported, expected version {}",
+                self.version, BUNDLE_VERSION
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl BundleTemplate {
+    pub fn new(
+        template: TemplateInputsEntity,
+        overrides: Vec<TemplateOverrideInputsEntity>,
+        variants: Vec<TemplateVariantEntity>,
+        schema: Option<TemplateSchemaEntity>,
+        settings: TemplateSettings,
+    ) -> ServiceResult<Self> {
+        let json_schema = schema
+            .map(|schema| serde_json::from_str(&schema.json_schema))
+            .transpose()
+            .map_err(|_| {
+                ServiceError::InternalServerErrorWithContext(
+                    "Failed to export the JSON schema of template".to_string(),
+                )
+            })?;
+
+        Ok(Self {
+            engine: parse_engine(&template.engine)?,
+            content_type: parse_content_type(&template.content_type)?,
+            name: template.path(),
+            description: template.description,
+            body: template.body,
+            inputs: template
+                .inputs
+                .into_iter()
+                .map(|input| BundleInput {
+                    name: input.name,
+                    default_value: input.default_value,
+                })
+                .collect(),
+            overrides: overrides
+                .into_iter()
+                .map(|template_override| BundleOverride {
+                    scope: template_override.scope,
+                    body: template_override.body,
+                    inputs: template_override
+                        .inputs
+                        .into_iter()
+                        .map(|input| BundleInput {
+                            name: input.name,
+                            default_value: input.default_value,
+                        })
+                        .collect(),
+                })
+                .collect(),
+            variants: variants
+                .into_iter()
+                .map(|variant| BundleVariant {
+                    locale: variant.locale,
+                    body: variant.body,
+                })
+                .collect(),
+            json_schema,
+            settings,
+        }
+        .normalized())
+    }
+
+    /// Sorts inputs, overrides and variants so that bundles of the same templates
+    /// compare equal regardless of the order they were written in.
+    pub fn normalized(mut self) -> Self {
+        self.inputs.sort();
+        self.overrides
+            .iter_mut()
+            .for_each(|template_override| template_override.inputs.sort());
+        self.overrides.sort();
+        self.variants.sort();
+        self.settings.rich_html_inputs.sort();
+        self
+    }
+
+    /// The fields the imported template would change in this one, with the value each
+    /// of them has on both sides.
+    pub fn diff(&self, imported: &BundleTemplate) -> ServiceResult<Vec<FieldChange>> {
+        let to_fields = |template: &BundleTemplate| match serde_json::to_value(template) {
+            Ok(serde_json::Value::Object(fields)) => Ok(fields),
+            _ => Err(ServiceError::InternalServerErrorWithContext(
+                "Failed to compare the bundled template".to_string(),
+            )),
+        };
+        let existing_fields = to_fields(self)?;
+        let mut imported_fields = to_fields(imported)?;
+
+        Ok(existing_fields
+            .into_iter()
+            .filter_map(|(field, existing)| {
+                let imported = imported_fields.remove(&field).unwrap_or_default();
+                (existing != imported).then_some(FieldChange {
+                    field,
+                    existing,
+                    imported,
+                })
+            })
+            .collect())
+    }
+}
+
+impl From<BundleInput> for TemplateInput {
+    fn from(input: BundleInput) -> Self {
+        Self {
+            name: input.name,
+            default_value: input.default_value,
+        }
+    }
+}
+
+/// What to do with a bundled template whose name is already taken.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// Keeps the existing template.
+    #[default]
+    Skip,
+    /// Replaces the existing template with the bundled one.
+    Overwrite,
+    /// Imports the bundled template under the first free `<name>-<n>` name.
+    Rename,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImportOptions {
+    pub strategy: ConflictStrategy,
+    /// Reports the changes the import would make without writing anything.
+    pub dry_run: bool,
+}
+
+/// A field of a template an import overwrites.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: String,
+    pub existing: serde_json::Value,
+    pub imported: serde_json::Value,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ImportAction {
+    Create,
+    Unchanged,
+    Skip,
+    Overwrite { changes: Vec<FieldChange> },
+    Rename { name: String },
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ImportChange {
+    pub name: String,
+    #[serde(flatten)]
+    pub action: ImportAction,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub changes: Vec<ImportChange>,
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use fluent_bundle::{FluentArgs, FluentBundle, FluentResource, FluentValue};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, JsonValue, Output, RenderContext,
+    RenderError,
+};
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+use unic_langid::LanguageIdentifier;
+
+use crate::repository::message::MessageEntity;
+
+pub const TRANSLATE_HELPER_NAME: &str = "t";
+
+/// Fluent message id the stored value is wrapped into, catalog keys such as
+/// `welcome.title` are not valid Fluent identifiers themselves.
+const MESSAGE_ID: &str = "message";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogMessage {
+    pub key: String,
+    pub locale: String,
+    pub value: String,
+}
+
+impl From<MessageEntity> for CatalogMessage {
+    fn from(message_entity: MessageEntity) -> Self {
+        Self {
+            key: message_entity.key,
+            locale: message_entity.locale,
+            value: message_entity.value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogMessages {
+    pub messages: Vec<CatalogMessage>,
+    pub count: i64,
+}
+
+/// Picks the value of every key from the most specific locale of `locale_chain`, the
+/// tenant's own message winning over the global one for the same locale.
+pub fn resolve_messages(
+    messages: Vec<MessageEntity>,
+    tenant: &str,
+    locale_chain: &[String],
+) -> HashMap<String, String> {
+    let rank = |message: &MessageEntity| {
+        (
+            locale_chain
+                .iter()
+                .position(|locale| locale == &message.locale)
+                .unwrap_or(locale_chain.len()),
+            message.tenant != tenant,
+        )
+    };
+
+    let mut resolved: HashMap<String, MessageEntity> = HashMap::new();
+    messages
+        .into_iter()
+        .for_each(|message| match resolved.get(&message.key) {
+            Some(existing) if rank(existing) <= rank(&message) => {}
+            _ => {
+                resolved.insert(message.key.clone(), message);
+            }
+        });
+
+    resolved
+        .into_iter()
+        .map(|(key, message)| (key, message.value))
+        .collect()
+}
+
+/// Parses a catalog value written in Fluent syntax, e.g.
+/// `{ $count -> [one] one item *[other] { $count } items }`.
+pub fn parse_message(value: &str) -> ServiceResult<FluentResource> {
+    let source = format!(
+        "{} = {}",
+        MESSAGE_ID,
+        value.lines().collect::<Vec<&str>>().join("\n    ")
+    );
+
+    FluentResource::try_new(source).map_err(|(_, errors)| {
+        ServiceError::BadRequest(format!(
+            "message value is not valid Fluent syntax: {:?}",
+            errors
+        ))
+    })
+}
+
+/// The `{{t "key" count=n}}` helper, translating catalog keys into the locale the
+/// messages were resolved for.
+#[derive(Debug)]
+pub struct TranslateHelper {
+    locale: LanguageIdentifier,
+    messages: HashMap<String, String>,
+}
+
+impl TranslateHelper {
+    pub fn new(locale: &str, messages: HashMap<String, String>) -> Self {
+        Self {
+            locale: locale.parse().unwrap_or_default(),
+            messages,
+        }
+    }
+
+    pub fn translate(&self, key: &str, args: &FluentArgs) -> Result<String, RenderError> {
+        let value = self
+            .messages
+            .get(key)
+            .ok_or_else(|| RenderError::new(format!("missing translation for {:?}", key)))?;
+        let resource = parse_message(value)
+            .map_err(|_| RenderError::new(format!("invalid translation for {:?}", key)))?;
+
+        let mut bundle = FluentBundle::new(vec![self.locale.clone()]);
+        bundle.set_use_isolating(false);
+        bundle
+            .add_resource(resource)
+            .map_err(|_| RenderError::new(format!("invalid translation for {:?}", key)))?;
+
+        let pattern = bundle
+            .get_message(MESSAGE_ID)
+            .and_then(|message| message.value())
+            .ok_or_else(|| RenderError::new(format!("empty translation for {:?}", key)))?;
+
+        let mut errors = vec![];
+        let translation = bundle.format_pattern(pattern, Some(args), &mut errors);
+        if !errors.is_empty() {
+            return Err(RenderError::new(format!(
+                "failed to format translation for {:?}: {:?}",
+                key, errors
+            )));
+        }
+
+        Ok(translation.into_owned())
+    }
+}
+
+impl HelperDef for TranslateHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let key = h
+            .param(0)
+            .and_then(|param| param.value().as_str())
+            .ok_or_else(|| RenderError::new("t helper expects a translation key"))?;
+
+        let mut args = FluentArgs::new();
+        h.hash().iter().for_each(|(name, value)| {
+            args.set(name.to_string(), into_fluent_value(value.value()));
+        });
+
+        out.write(&self.translate(key, &args)?)?;
+        Ok(())
+    }
+}
+
+/// Input values are strings, so numeric strings are passed on as numbers to let
+/// plural selection work on them.
+pub fn into_fluent_value(value: &JsonValue) -> FluentValue<'static> {
+    match value {
+        JsonValue::Number(number) => FluentValue::from(number.as_f64().unwrap_or_default()),
+        JsonValue::String(string) => match string.parse::<f64>() {
+            Ok(number) => FluentValue::from(number),
+            Err(_) => FluentValue::from(string.clone()),
+        },
+        JsonValue::Bool(boolean) => FluentValue::from(boolean.to_string()),
+        _ => FluentValue::None,
+    }
+}
@@ -0,0 +1,369 @@
+use std::collections::{BTreeMap, HashMap};
+
+use madtofan_microservice_common::{
+    errors::{ServiceError, ServiceResult},
+    templating::compose_request::InputValue,
+};
+
+use crate::repository::{
+    input::InputEntity, template_override::TemplateOverrideInputsEntity,
+    template_variant::TemplateVariantEntity,
+};
+
+use super::{
+    catalog::TranslateHelper,
+    content::ContentType,
+    css::inline_css,
+    engine::TemplateEngineKind,
+    limits::{RenderDeadline, RenderLimits},
+    links::LinkRewriter,
+    markdown::{markdown_to_html, markdown_to_text},
+    marketing::{insert_footer, Unsubscribe, UNSUBSCRIBE_URL_INPUT},
+    mime::{write_mime, MimePart},
+};
+
+/// The input a layout places the HTML of a Markdown template with.
+pub const LAYOUT_CONTENT_INPUT: &str = "content";
+
+/// What the `content` input of a layout is rendered as, it is replaced by the HTML of
+/// the Markdown template afterwards so that every engine places it unescaped.
+const LAYOUT_CONTENT_PLACEHOLDER: &str = "templating-layout-content-placeholder";
+
+/// What the unsubscribe URL is rendered as to find out whether a template places it,
+/// it reads the same escaped for any content type.
+const UNSUBSCRIBE_URL_SENTINEL: &str = "templatingunsubscribeurlsentinel";
+
+/// Which rendering of the template `compose` returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ComposeOutput {
+    /// The body in the template's content type, HTML for Markdown templates.
+    #[default]
+    Body,
+    /// The plain-text alternative of Markdown and text templates.
+    Text,
+    /// A MIME message with the body and, for Markdown, its plain-text alternative.
+    Mime,
+}
+
+/// Whether composing the content type to `output` produces HTML, on its own or as a
+/// part of a MIME message.
+pub fn renders_html(content_type: ContentType, output: ComposeOutput) -> bool {
+    let html = matches!(
+        content_type,
+        ContentType::Html | ContentType::Mjml | ContentType::Markdown
+    );
+    html && output != ComposeOutput::Text
+}
+
+/// The outputs a MIME message of the content type is made of with their MIME types,
+/// in increasing order of preference.
+pub fn mime_parts(content_type: ContentType) -> ServiceResult<Vec<(ComposeOutput, &'static str)>> {
+    match content_type {
+        ContentType::Markdown => Ok(vec![
+            (ComposeOutput::Text, "text/plain"),
+            (ComposeOutput::Body, "text/html"),
+        ]),
+        ContentType::Html | ContentType::Mjml => Ok(vec![(ComposeOutput::Body, "text/html")]),
+        ContentType::Text => Ok(vec![(ComposeOutput::Body, "text/plain")]),
+        ContentType::Json => Err(ServiceError::BadRequest(
+            "JSON templates cannot be composed to MIME".to_string(),
+        )),
+        ContentType::Url => Err(ServiceError::BadRequest(
+            "URL templates cannot be composed to MIME".to_string(),
+        )),
+    }
+}
+
+pub fn parse_compose_output(output: &str) -> ServiceResult<ComposeOutput> {
+    match output.trim().to_ascii_lowercase().as_str() {
+        "body" => Ok(ComposeOutput::Body),
+        "text" | "text/plain" => Ok(ComposeOutput::Text),
+        "mime" | "message/rfc822" => Ok(ComposeOutput::Mime),
+        _ => Err(ServiceError::BadRequest(format!(
+            "compose output {:?} is not supported",
+            output
+        ))),
+    }
+}
+
+/// An HTML template the HTML of a Markdown template is wrapped in, rendered with its
+/// own defaults merged with the compose request inputs.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub engine: TemplateEngineKind,
+    pub body: String,
+    pub data: BTreeMap<String, String>,
+}
+
+/// The values a template is rendered with: the template defaults, replaced by the
+/// defaults of the scope override, replaced by the inputs of the compose request.
+pub fn merge_inputs(
+    default_inputs: Vec<InputEntity>,
+    template_override: Option<&TemplateOverrideInputsEntity>,
+    inputs: Vec<InputValue>,
+) -> BTreeMap<String, String> {
+    let mut data = BTreeMap::new();
+    default_inputs.into_iter().for_each(|input| {
+        data.insert(input.name, input.default_value);
+    });
+    if let Some(template_override) = template_override {
+        template_override.inputs.iter().for_each(|input| {
+            data.insert(input.name.clone(), input.default_value.clone());
+        });
+    }
+    inputs.into_iter().for_each(|input| {
+        data.insert(input.name, input.value);
+    });
+    data
+}
+
+/// The body a template is rendered from: the variant of the most specific locale of
+/// `locale_chain` that has one, otherwise the override body, otherwise the template
+/// body.
+pub fn select_body(
+    body: String,
+    template_override: Option<&TemplateOverrideInputsEntity>,
+    variants: &[TemplateVariantEntity],
+    locale_chain: &[String],
+) -> String {
+    if let Some(variant) = locale_chain
+        .iter()
+        .find_map(|locale| variants.iter().find(|variant| &variant.locale == locale))
+    {
+        return variant.body.clone();
+    }
+
+    template_override
+        .and_then(|template_override| template_override.body.clone())
+        .unwrap_or(body)
+}
+
+/// Renders a body written for `engine`, escaping values for `content_type`, its `t`
+/// helper translating catalog keys with `translator`.
+pub fn render(
+    engine: TemplateEngineKind,
+    content_type: ContentType,
+    body: &str,
+    data: &BTreeMap<String, String>,
+    translator: TranslateHelper,
+    limits: &RenderLimits,
+    deadline: &RenderDeadline,
+) -> ServiceResult<String> {
+    engine
+        .engine()
+        .render(body, data, translator, content_type, limits, deadline)
+}
+
+/// Turns the rendered body into the requested output: Markdown becomes sanitised HTML
+/// or its plain-text alternative, other content types are returned as rendered.
+pub fn convert_output(
+    content_type: ContentType,
+    rendered: String,
+    output: ComposeOutput,
+    limits: &RenderLimits,
+) -> ServiceResult<String> {
+    let converted = match (content_type, output) {
+        (ContentType::Markdown, ComposeOutput::Body) => markdown_to_html(&rendered),
+        (ContentType::Markdown, ComposeOutput::Text) => markdown_to_text(&rendered),
+        (_, ComposeOutput::Body) | (ContentType::Text, ComposeOutput::Text) => rendered,
+        (_, ComposeOutput::Text) => {
+            return Err(ServiceError::BadRequest(format!(
+                "{} templates have no plain-text alternative",
+                content_type
+            )))
+        }
+        (_, ComposeOutput::Mime) => {
+            return Err(ServiceError::InternalServerErrorWithContext(
+                "MIME output is converted part by part".to_string(),
+            ))
+        }
+    };
+    if converted.len() > limits.max_output_bytes {
+        return Err(limits.output_too_large());
+    }
+
+    Ok(converted)
+}
+
+/// Renders the layout with `content` as its `content` input.
+pub fn render_layout(
+    layout: &Layout,
+    content: &str,
+    locale: &str,
+    messages: HashMap<String, String>,
+    limits: &RenderLimits,
+    deadline: &RenderDeadline,
+) -> ServiceResult<String> {
+    let mut data = layout.data.clone();
+    data.insert(
+        LAYOUT_CONTENT_INPUT.to_string(),
+        LAYOUT_CONTENT_PLACEHOLDER.to_string(),
+    );
+    let rendered = render(
+        layout.engine,
+        ContentType::Html,
+        &layout.body,
+        &data,
+        TranslateHelper::new(locale, messages),
+        limits,
+        deadline,
+    )?;
+    if !rendered.contains(LAYOUT_CONTENT_PLACEHOLDER) {
+        return Err(ServiceError::BadRequest(format!(
+            "layout does not place the {:?} input",
+            LAYOUT_CONTENT_INPUT
+        )));
+    }
+
+    let wrapped = rendered.replace(LAYOUT_CONTENT_PLACEHOLDER, content);
+    if wrapped.len() > limits.max_output_bytes {
+        return Err(limits.output_too_large());
+    }
+
+    Ok(wrapped)
+}
+
+/// The steps a rendered body goes through on its way to the requested output, after
+/// it has been converted: the HTML is placed in the layout, its links are rewritten,
+/// the unsubscribe footer is added and its CSS inlined.
+pub struct ComposePipeline {
+    pub content_type: ContentType,
+    pub locale: String,
+    pub messages: HashMap<String, String>,
+    pub layout: Option<Layout>,
+    pub links: Option<LinkRewriter>,
+    pub unsubscribe: Option<Unsubscribe>,
+    pub inline_css: bool,
+    pub stylesheet: Option<String>,
+    pub limits: RenderLimits,
+    /// Shared by every render of the compose, so together they finish within the
+    /// timeout of `limits`.
+    pub deadline: RenderDeadline,
+}
+
+impl ComposePipeline {
+    pub fn compose(&self, rendered: String, output: ComposeOutput) -> ServiceResult<String> {
+        if output != ComposeOutput::Mime {
+            return self.compose_part(rendered, output);
+        }
+
+        let mut parts = vec![];
+        for (part_output, content_type) in mime_parts(self.content_type)? {
+            parts.push(MimePart {
+                content_type,
+                content: self.compose_part(rendered.clone(), part_output)?,
+            });
+        }
+        let headers = self
+            .unsubscribe
+            .as_ref()
+            .map(|unsubscribe| unsubscribe.headers())
+            .unwrap_or_default();
+        let message = write_mime(&headers, &parts);
+        if message.len() > self.limits.max_output_bytes {
+            return Err(self.limits.output_too_large());
+        }
+
+        Ok(message)
+    }
+
+    /// Leaves the unsubscribe footer out when the body or the layout places the
+    /// unsubscribe URL, found by rendering them with a sentinel in place of the URL.
+    pub fn place_unsubscribe_footer(
+        &mut self,
+        engine: TemplateEngineKind,
+        body: &str,
+        data: &BTreeMap<String, String>,
+    ) -> ServiceResult<()> {
+        match &self.unsubscribe {
+            Some(unsubscribe) if unsubscribe.footer.is_some() => {}
+            _ => return Ok(()),
+        }
+
+        let places_url = |engine, content_type, body: &str, data: &BTreeMap<String, String>| {
+            let mut data = data.clone();
+            data.insert(
+                UNSUBSCRIBE_URL_INPUT.to_string(),
+                UNSUBSCRIBE_URL_SENTINEL.to_string(),
+            );
+            data.entry(LAYOUT_CONTENT_INPUT.to_string()).or_default();
+            render(
+                engine,
+                content_type,
+                body,
+                &data,
+                TranslateHelper::new(&self.locale, self.messages.clone()),
+                &self.limits,
+                &self.deadline,
+            )
+            .map(|rendered| rendered.contains(UNSUBSCRIBE_URL_SENTINEL))
+        };
+        let mut placed = places_url(engine, self.content_type, body, data)?;
+        if let Some(layout) = self.layout.as_ref().filter(|_| !placed) {
+            placed = places_url(layout.engine, ContentType::Html, &layout.body, &layout.data)?;
+        }
+
+        if placed {
+            if let Some(unsubscribe) = &mut self.unsubscribe {
+                unsubscribe.footer = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compose_part(&self, rendered: String, output: ComposeOutput) -> ServiceResult<String> {
+        let converted = convert_output(self.content_type, rendered, output, &self.limits)?;
+        let composed = if renders_html(self.content_type, output) {
+            self.compose_html(converted)?
+        } else {
+            match self
+                .unsubscribe
+                .as_ref()
+                .and_then(|unsubscribe| unsubscribe.text_footer(&self.messages))
+            {
+                Some(footer) => format!("{}\n\n{}", converted.trim_end(), footer),
+                None => converted,
+            }
+        };
+        if composed.len() > self.limits.max_output_bytes {
+            return Err(self.limits.output_too_large());
+        }
+
+        Ok(composed)
+    }
+
+    fn compose_html(&self, converted: String) -> ServiceResult<String> {
+        let mut composed = match &self.layout {
+            Some(layout) => render_layout(
+                layout,
+                &converted,
+                &self.locale,
+                self.messages.clone(),
+                &self.limits,
+                &self.deadline,
+            )?,
+            None => converted,
+        };
+        if let Some(links) = &self.links {
+            composed = links.rewrite(&composed);
+        }
+        // The footer is added after the links are rewritten so that unsubscribing
+        // never goes through click tracking.
+        if let Some(unsubscribe) = &self.unsubscribe {
+            if let Some(footer) = unsubscribe.html_footer(
+                &self.locale,
+                self.messages.clone(),
+                &self.limits,
+                &self.deadline,
+            )? {
+                composed = insert_footer(&composed, &footer);
+            }
+        }
+        if self.inline_css {
+            composed = inline_css(&composed, self.stylesheet.as_deref())?;
+        }
+
+        Ok(composed)
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+
+use handlebars::html_escape;
+use jsonschema::JSONSchema;
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+use serde::{Deserialize, Serialize};
+
+/// The kind of document a template renders, it decides how input values are escaped
+/// when they are placed in the body, HTML unless the template declares otherwise.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentType {
+    #[default]
+    Html,
+    Text,
+    Json,
+    /// CommonMark with tables, composed into sanitised HTML or its plain-text
+    /// alternative once the values are in place.
+    Markdown,
+    /// MJML compiled to email-safe HTML, values are escaped as in HTML.
+    Mjml,
+    /// A URL, such as a link target or a webhook address, values are percent-encoded
+    /// so they stay within a single path segment or query value.
+    Url,
+}
+
+impl ContentType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Text => "text",
+            Self::Json => "json",
+            Self::Markdown => "markdown",
+            Self::Mjml => "mjml",
+            Self::Url => "url",
+        }
+    }
+
+    /// Escapes a value placed in a body of this content type: HTML entities for HTML
+    /// and MJML, nothing for text, JSON string escapes for JSON, where values are
+    /// expected to sit inside string literals, backslash escapes for Markdown so
+    /// values never turn into markup, and percent-encoding for URLs.
+    pub fn escape(&self, value: &str) -> String {
+        match self {
+            Self::Html | Self::Mjml => html_escape(value),
+            Self::Text => value.to_string(),
+            Self::Json => escape_json(value),
+            Self::Markdown => escape_markdown(value),
+            Self::Url => escape_url(value),
+        }
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses a content type by name or by its MIME type.
+pub fn parse_content_type(content_type: &str) -> ServiceResult<ContentType> {
+    match content_type.trim().to_ascii_lowercase().as_str() {
+        "html" | "text/html" => Ok(ContentType::Html),
+        "text" | "text/plain" => Ok(ContentType::Text),
+        "json" | "application/json" => Ok(ContentType::Json),
+        "markdown" | "text/markdown" => Ok(ContentType::Markdown),
+        "mjml" => Ok(ContentType::Mjml),
+        "url" | "text/uri-list" => Ok(ContentType::Url),
+        _ => Err(ServiceError::BadRequest(format!(
+            "content type {:?} is not supported",
+            content_type
+        ))),
+    }
+}
+
+/// Compiles the JSON Schema attached to a JSON template, rejecting documents that are
+/// not JSON or not a valid schema.
+pub fn compile_json_schema(json_schema: &str) -> ServiceResult<JSONSchema> {
+    let schema: serde_json::Value = serde_json::from_str(json_schema).map_err(|err| {
+        ServiceError::BadRequest(format!("JSON schema is not valid JSON: {}", err))
+    })?;
+
+    JSONSchema::compile(&schema)
+        .map_err(|err| ServiceError::BadRequest(format!("JSON schema is not valid: {}", err)))
+}
+
+/// Checks that the output of a JSON template parses as JSON and, when the template has
+/// a schema attached, that it satisfies the schema. Every violation is listed with the
+/// path of the offending value.
+pub fn check_json_output(rendered: &str, json_schema: Option<&str>) -> ServiceResult<()> {
+    let output: serde_json::Value = serde_json::from_str(rendered).map_err(|err| {
+        ServiceError::BadRequest(format!("rendered template is not valid JSON: {}", err))
+    })?;
+
+    let schema = match json_schema {
+        Some(json_schema) => compile_json_schema(json_schema)?,
+        None => return Ok(()),
+    };
+    if let Err(errors) = schema.validate(&output) {
+        let violations = errors
+            .map(|err| {
+                let path = err.instance_path.to_string();
+                if path.is_empty() {
+                    err.to_string()
+                } else {
+                    format!("{}: {}", path, err)
+                }
+            })
+            .collect::<Vec<String>>();
+        return Err(ServiceError::BadRequest(format!(
+            "rendered template does not match its JSON schema: {}",
+            violations.join("; ")
+        )));
+    }
+
+    Ok(())
+}
+
+/// Escapes a value for a JSON string literal, without the surrounding quotes.
+pub fn escape_json(value: &str) -> String {
+    let quoted = serde_json::Value::String(value.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Escapes every ASCII punctuation character with a backslash, which CommonMark reads as
+/// the literal character.
+pub fn escape_markdown(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        if character.is_ascii_punctuation() {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+/// Percent-encodes every byte of the value other than the unreserved characters of
+/// RFC 3986, so the value cannot end a path segment or a query parameter early.
+pub fn escape_url(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    escaped
+}
@@ -0,0 +1,58 @@
+use std::borrow::Cow;
+
+use css_inline::CSSInliner;
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+
+use crate::repository::stylesheet::StylesheetEntity;
+
+/// The stylesheet a tenant's HTML output can be inlined with, on top of the `<style>`
+/// blocks of the output itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stylesheet {
+    pub tenant: String,
+    pub css: String,
+}
+
+impl From<StylesheetEntity> for Stylesheet {
+    fn from(stylesheet_entity: StylesheetEntity) -> Self {
+        Self {
+            tenant: stylesheet_entity.tenant,
+            css: stylesheet_entity.css,
+        }
+    }
+}
+
+/// Moves the rules of the `<style>` blocks of `html` and of `stylesheet` into the
+/// `style` attributes of the elements they match. The `<style>` blocks are kept for
+/// the clients that honour them, rules such as media queries cannot be inlined.
+pub fn inline_css(html: &str, stylesheet: Option<&str>) -> ServiceResult<String> {
+    CSSInliner::options()
+        .keep_style_tags(true)
+        .load_remote_stylesheets(false)
+        .extra_css(stylesheet.map(Cow::Borrowed))
+        .build()
+        .inline(html)
+        .map_err(|err| ServiceError::BadRequest(format!("CSS could not be inlined: {}", err)))
+}
+
+/// Checks the stylesheet is not empty and can be inlined into a document.
+pub fn validate_stylesheet(css: &str) -> ServiceResult<()> {
+    if css.trim().is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Cannot save an empty stylesheet".to_string(),
+        ));
+    }
+
+    inline_css("<html><head></head><body></body></html>", Some(css)).map(|_| ())
+}
+
+pub fn parse_inline_css(inline_css: &str) -> ServiceResult<bool> {
+    match inline_css.trim().to_ascii_lowercase().as_str() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(ServiceError::BadRequest(format!(
+            "inline CSS option {:?} is not a boolean",
+            inline_css
+        ))),
+    }
+}
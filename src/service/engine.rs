@@ -0,0 +1,870 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt,
+    sync::Arc,
+};
+
+use fluent_bundle::FluentArgs;
+use handlebars::{
+    template::{Parameter, Template, TemplateElement},
+    Handlebars, JsonValue,
+};
+use liquid_core::{
+    model::{KString, KStringCow, KStringRef, ScalarCow, ValueCow},
+    parser::{
+        BlockReflection, FilterArguments, FilterReflection, Language, ParameterReflection,
+        ParseBlock, ParseFilter, TagBlock, TagTokenIter,
+    },
+    runtime::{Interrupt, InterruptRegister, PartialStore, Registers},
+    Expression, Filter, Renderable, Runtime, Value as LiquidValue, ValueView,
+};
+use liquid_lib::stdlib::ForBlock;
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+use minijinja::{escape_formatter, value::Kwargs, AutoEscape, Environment};
+use serde::{Deserialize, Serialize};
+use tera::{
+    ast::{Expr, ExprVal, FunctionCall, Node, WS},
+    Tera,
+};
+
+use super::{
+    catalog::{into_fluent_value, TranslateHelper, TRANSLATE_HELPER_NAME},
+    content::{escape_json, escape_markdown, escape_url, ContentType},
+    limits::{check_partial_depth, RenderDeadline, RenderLimits, RenderOutput},
+};
+
+const TEMPLATE_NAME: &str = "t1";
+/// Tera only escapes HTML in templates named with an HTML extension.
+const TERA_TEMPLATE_NAME: &str = "t1.html";
+/// Stands for a Handlebars partial whose name is only known once rendering.
+const DYNAMIC_PARTIAL: &str = "*";
+/// MiniJinja limits recursion rather than macro nesting, a macro call costs it several
+/// levels and every block inside the macro one more, so each allowed partial level is
+/// budgeted this many.
+const MINIJINJA_RECURSION_PER_PARTIAL: usize = 10;
+/// MiniJinja cannot check the deadline while it loops, so it is given about as many
+/// instructions as it runs in the timeout instead, running out counts as timing out.
+const MINIJINJA_FUEL_PER_MILLISECOND: u64 = 100_000;
+/// Called at the start of every Tera loop iteration, it fails once the deadline passes.
+const TERA_DEADLINE_FUNCTION: &str = "__check_render_deadline";
+/// Longest list the Tera `range` function builds, the same as MiniJinja allows.
+const MAX_RANGE_LENGTH: usize = 100_000;
+/// The namespace Tera calls macros defined in the same template through.
+const TERA_SELF_NAMESPACE: &str = "self";
+
+/// The template language a template body is written in, Handlebars unless the template
+/// declares otherwise.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateEngineKind {
+    #[default]
+    Handlebars,
+    MiniJinja,
+    Tera,
+    Liquid,
+}
+
+impl TemplateEngineKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Handlebars => "handlebars",
+            Self::MiniJinja => "minijinja",
+            Self::Tera => "tera",
+            Self::Liquid => "liquid",
+        }
+    }
+
+    pub fn engine(&self) -> &'static dyn TemplateEngine {
+        match self {
+            Self::Handlebars => &HandlebarsEngine,
+            Self::MiniJinja => &MiniJinjaEngine,
+            Self::Tera => &TeraEngine,
+            Self::Liquid => &LiquidEngine,
+        }
+    }
+}
+
+impl fmt::Display for TemplateEngineKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+pub fn parse_engine(engine: &str) -> ServiceResult<TemplateEngineKind> {
+    match engine.trim().to_ascii_lowercase().as_str() {
+        "handlebars" => Ok(TemplateEngineKind::Handlebars),
+        "minijinja" | "jinja" => Ok(TemplateEngineKind::MiniJinja),
+        "tera" => Ok(TemplateEngineKind::Tera),
+        "liquid" => Ok(TemplateEngineKind::Liquid),
+        _ => Err(ServiceError::BadRequest(format!(
+            "template engine {:?} is not supported",
+            engine
+        ))),
+    }
+}
+
+/// A template listed with the engine its bodies are rendered with and the content type
+/// they render.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateEngineListing {
+    pub name: String,
+    pub engine: TemplateEngineKind,
+    pub content_type: ContentType,
+}
+
+/// Compiles and renders template bodies of one template language. Every engine exposes
+/// the message catalog through a `t` helper, function or filter.
+pub trait TemplateEngine: Send + Sync {
+    /// Compiles the body without rendering it, syntax errors are reported as a bad
+    /// request.
+    fn validate(&self, body: &str) -> ServiceResult<()>;
+    /// Renders the body with values escaped for `content_type`, failing with the error
+    /// of the first of `limits` it exceeds. The time limit runs out at `deadline`.
+    fn render(
+        &self,
+        body: &str,
+        data: &BTreeMap<String, String>,
+        translator: TranslateHelper,
+        content_type: ContentType,
+        limits: &RenderLimits,
+        deadline: &RenderDeadline,
+    ) -> ServiceResult<String>;
+}
+
+/// Renders `{{name}}` placeholders escaped for the content type, `{{t "key" count=n}}`
+/// translates.
+pub struct HandlebarsEngine;
+
+impl TemplateEngine for HandlebarsEngine {
+    fn validate(&self, body: &str) -> ServiceResult<()> {
+        Handlebars::new()
+            .register_template_string(TEMPLATE_NAME, body)
+            .map_err(|err| invalid_syntax(TemplateEngineKind::Handlebars, &err))
+    }
+
+    fn render(
+        &self,
+        body: &str,
+        data: &BTreeMap<String, String>,
+        translator: TranslateHelper,
+        content_type: ContentType,
+        limits: &RenderLimits,
+        deadline: &RenderDeadline,
+    ) -> ServiceResult<String> {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(TRANSLATE_HELPER_NAME, Box::new(translator));
+        handlebars.register_escape_fn(move |value| content_type.escape(value));
+        handlebars
+            .register_template_string(TEMPLATE_NAME, body)
+            .map_err(|_| render_failed())?;
+        if let Some(template) = handlebars.get_template(TEMPLATE_NAME) {
+            let mut body_calls = vec![];
+            let mut partials = HashMap::new();
+            collect_handlebars_partials(template, &mut body_calls, &mut partials);
+            let (body_calls, partials) = resolve_dynamic_partials(body_calls, partials);
+            check_partial_depth(&body_calls, &partials, limits)?;
+        }
+
+        let mut output = RenderOutput::new(limits, deadline);
+        let rendered = handlebars.render_to_write(TEMPLATE_NAME, data, &mut output);
+        finish_render(output, rendered)
+    }
+}
+
+/// Gathers the partials called from `template` into `calls` and the inline partials it
+/// defines, with the partials each of them calls, into `partials`.
+fn collect_handlebars_partials(
+    template: &Template,
+    calls: &mut Vec<String>,
+    partials: &mut HashMap<String, Vec<String>>,
+) {
+    for element in &template.elements {
+        match element {
+            TemplateElement::DecoratorBlock(decorator)
+                if decorator.name.as_name() == Some("inline") =>
+            {
+                let name = match decorator.params.first() {
+                    Some(Parameter::Literal(JsonValue::String(name))) => name.clone(),
+                    _ => DYNAMIC_PARTIAL.to_string(),
+                };
+                let mut partial_calls = vec![];
+                if let Some(template) = &decorator.template {
+                    collect_handlebars_partials(template, &mut partial_calls, partials);
+                }
+                partials.entry(name).or_default().extend(partial_calls);
+            }
+            TemplateElement::PartialExpression(partial)
+            | TemplateElement::PartialBlock(partial) => {
+                calls.push(match &partial.name {
+                    Parameter::Literal(JsonValue::String(name)) => name.clone(),
+                    name => name.as_name().unwrap_or(DYNAMIC_PARTIAL).to_string(),
+                });
+                if let Some(template) = &partial.template {
+                    collect_handlebars_partials(template, calls, partials);
+                }
+            }
+            TemplateElement::DecoratorBlock(decorator) => {
+                if let Some(template) = &decorator.template {
+                    collect_handlebars_partials(template, calls, partials);
+                }
+            }
+            TemplateElement::HelperBlock(helper) => {
+                for template in helper.template.iter().chain(helper.inverse.iter()) {
+                    collect_handlebars_partials(template, calls, partials);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Handlebars partial names can be computed from the data, so a dynamic call may reach
+/// any inline partial and a call may reach a dynamically named one.
+fn resolve_dynamic_partials(
+    body_calls: Vec<String>,
+    partials: HashMap<String, Vec<String>>,
+) -> (Vec<String>, HashMap<String, Vec<String>>) {
+    let names = partials.keys().cloned().collect::<Vec<String>>();
+    let resolve = |calls: Vec<String>| -> Vec<String> {
+        calls
+            .into_iter()
+            .flat_map(|call| {
+                if call == DYNAMIC_PARTIAL {
+                    names.clone()
+                } else if !partials.contains_key(&call) && partials.contains_key(DYNAMIC_PARTIAL) {
+                    vec![DYNAMIC_PARTIAL.to_string()]
+                } else {
+                    vec![call]
+                }
+            })
+            .collect()
+    };
+
+    let resolved_partials = partials
+        .clone()
+        .into_iter()
+        .map(|(name, calls)| (name, resolve(calls)))
+        .collect();
+    (resolve(body_calls), resolved_partials)
+}
+
+/// Renders Jinja templates with values escaped for the content type, values marked
+/// `| safe` are left as they are, `{{ t("key", count=n) }}` translates.
+pub struct MiniJinjaEngine;
+
+impl TemplateEngine for MiniJinjaEngine {
+    fn validate(&self, body: &str) -> ServiceResult<()> {
+        Environment::new()
+            .template_from_str(body)
+            .map(|_| ())
+            .map_err(|err| invalid_syntax(TemplateEngineKind::MiniJinja, &err))
+    }
+
+    fn render(
+        &self,
+        body: &str,
+        data: &BTreeMap<String, String>,
+        translator: TranslateHelper,
+        content_type: ContentType,
+        limits: &RenderLimits,
+        deadline: &RenderDeadline,
+    ) -> ServiceResult<String> {
+        let translator = Arc::new(translator);
+        let mut environment = Environment::new();
+        environment.set_auto_escape_callback(move |_| match content_type {
+            ContentType::Html | ContentType::Mjml => AutoEscape::Html,
+            ContentType::Text | ContentType::Json | ContentType::Markdown | ContentType::Url => {
+                AutoEscape::None
+            }
+        });
+        if matches!(
+            content_type,
+            ContentType::Json | ContentType::Markdown | ContentType::Url
+        ) {
+            environment.set_formatter(move |out, state, value| {
+                if value.is_safe() || value.is_undefined() {
+                    return escape_formatter(out, state, value);
+                }
+                out.write_str(&content_type.escape(&value.to_string()))
+                    .map_err(minijinja::Error::from)
+            });
+        }
+        environment.set_recursion_limit(limits.max_partial_depth * MINIJINJA_RECURSION_PER_PARTIAL);
+        environment.set_fuel(Some(
+            deadline.remaining().as_millis() as u64 * MINIJINJA_FUEL_PER_MILLISECOND,
+        ));
+        environment.add_function(
+            TRANSLATE_HELPER_NAME,
+            move |key: &str, kwargs: Kwargs| -> Result<String, minijinja::Error> {
+                let mut args = FluentArgs::new();
+                for name in kwargs.args() {
+                    let value = kwargs.get::<minijinja::Value>(name)?;
+                    args.set(
+                        name.to_string(),
+                        into_fluent_value(&JsonValue::String(value.to_string())),
+                    );
+                }
+
+                translator.translate(key, &args).map_err(|err| {
+                    minijinja::Error::new(minijinja::ErrorKind::InvalidOperation, err.desc)
+                })
+            },
+        );
+
+        let template = environment
+            .template_from_str(body)
+            .map_err(|_| render_failed())?;
+
+        let mut output = RenderOutput::new(limits, deadline);
+        let rendered = template.render_captured_to(data, &mut output);
+        if let Err(err) = &rendered {
+            if err.kind() == minijinja::ErrorKind::OutOfFuel {
+                return Err(limits.timed_out());
+            }
+            if is_minijinja_recursion_error(err) {
+                return Err(limits.partials_too_deep());
+            }
+        }
+        finish_render(output, rendered)
+    }
+}
+
+fn is_minijinja_recursion_error(err: &minijinja::Error) -> bool {
+    let mut source: Option<&dyn Error> = Some(err);
+    while let Some(cause) = source {
+        if cause.to_string().contains("recursion limit exceeded") {
+            return true;
+        }
+        source = cause.source();
+    }
+
+    false
+}
+
+/// Renders Tera templates with values escaped for the content type,
+/// `{{ t(key="key", count=n) }}` translates.
+pub struct TeraEngine;
+
+impl TemplateEngine for TeraEngine {
+    fn validate(&self, body: &str) -> ServiceResult<()> {
+        Tera::default()
+            .add_raw_template(TERA_TEMPLATE_NAME, body)
+            .map_err(|err| invalid_syntax(TemplateEngineKind::Tera, &err))
+    }
+
+    fn render(
+        &self,
+        body: &str,
+        data: &BTreeMap<String, String>,
+        translator: TranslateHelper,
+        content_type: ContentType,
+        limits: &RenderLimits,
+        deadline: &RenderDeadline,
+    ) -> ServiceResult<String> {
+        let mut tera = Tera::default();
+        match content_type {
+            ContentType::Html | ContentType::Mjml => {}
+            ContentType::Text => tera.autoescape_on(vec![]),
+            ContentType::Json => tera.set_escape_fn(escape_json),
+            ContentType::Markdown => tera.set_escape_fn(escape_markdown),
+            ContentType::Url => tera.set_escape_fn(escape_url),
+        }
+        tera.add_raw_template(TERA_TEMPLATE_NAME, body)
+            .map_err(|_| render_failed())?;
+        let template = tera
+            .get_template(TERA_TEMPLATE_NAME)
+            .map_err(|_| render_failed())?;
+        let mut body_calls = vec![];
+        collect_tera_macro_calls(&template.ast, &mut body_calls);
+        let macros = template
+            .macros
+            .iter()
+            .map(|(name, definition)| {
+                let mut calls = vec![];
+                collect_tera_macro_calls(&definition.body, &mut calls);
+                (name.clone(), calls)
+            })
+            .collect();
+        check_partial_depth(&body_calls, &macros, limits)?;
+        if let Some(template) = tera.templates.get_mut(TERA_TEMPLATE_NAME) {
+            add_tera_deadline_checks(&mut template.ast);
+            template
+                .macros
+                .values_mut()
+                .for_each(|definition| add_tera_deadline_checks(&mut definition.body));
+            template
+                .blocks_definitions
+                .values_mut()
+                .flatten()
+                .for_each(|(_, block)| add_tera_deadline_checks(&mut block.body));
+        }
+
+        let mut output = RenderOutput::new(limits, deadline);
+        let deadline = deadline.clone();
+        tera.register_function(
+            TERA_DEADLINE_FUNCTION,
+            move |_: &HashMap<String, JsonValue>| -> tera::Result<JsonValue> {
+                match deadline.check() {
+                    true => Err(tera::Error::msg("render deadline passed")),
+                    false => Ok(JsonValue::String(String::new())),
+                }
+            },
+        );
+        tera.register_function("range", bounded_range);
+        tera.register_function(
+            TRANSLATE_HELPER_NAME,
+            move |kwargs: &HashMap<String, JsonValue>| -> tera::Result<JsonValue> {
+                let key = kwargs
+                    .get("key")
+                    .and_then(|key| key.as_str())
+                    .ok_or_else(|| tera::Error::msg("t function expects a translation key"))?;
+                let mut args = FluentArgs::new();
+                kwargs
+                    .iter()
+                    .filter(|(name, _)| name.as_str() != "key")
+                    .for_each(|(name, value)| args.set(name.clone(), into_fluent_value(value)));
+
+                translator
+                    .translate(key, &args)
+                    .map(JsonValue::String)
+                    .map_err(|err| tera::Error::msg(err.desc))
+            },
+        );
+        let context = tera::Context::from_serialize(data).map_err(|_| render_failed())?;
+
+        let rendered = tera.render_to(TERA_TEMPLATE_NAME, &context, &mut output);
+        finish_render(output, rendered)
+    }
+}
+
+/// Tera loops only check the deadline when they write, so every loop body is made to
+/// start with a call that fails once the deadline passes.
+fn add_tera_deadline_checks(nodes: &mut [Node]) {
+    for node in nodes {
+        match node {
+            Node::Forloop(_, forloop, _) => {
+                add_tera_deadline_checks(&mut forloop.body);
+                forloop.body.insert(
+                    0,
+                    Node::VariableBlock(
+                        WS::default(),
+                        Expr::new(ExprVal::FunctionCall(FunctionCall {
+                            name: TERA_DEADLINE_FUNCTION.to_string(),
+                            args: HashMap::new(),
+                        })),
+                    ),
+                );
+                if let Some(empty_body) = &mut forloop.empty_body {
+                    add_tera_deadline_checks(empty_body);
+                }
+            }
+            Node::Block(_, block, _) => add_tera_deadline_checks(&mut block.body),
+            Node::FilterSection(_, section, _) => add_tera_deadline_checks(&mut section.body),
+            Node::If(condition, _) => {
+                condition
+                    .conditions
+                    .iter_mut()
+                    .for_each(|(_, _, body)| add_tera_deadline_checks(body));
+                if let Some((_, body)) = &mut condition.otherwise {
+                    add_tera_deadline_checks(body);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Tera's `range`, refusing to build lists longer than [`MAX_RANGE_LENGTH`] since the
+/// whole list is built before a loop over it starts.
+fn bounded_range(args: &HashMap<String, JsonValue>) -> tera::Result<JsonValue> {
+    let argument = |name: &str, default: Option<u64>| -> tera::Result<u64> {
+        match args.get(name) {
+            Some(value) => value.as_u64().ok_or_else(|| {
+                tera::Error::msg(format!(
+                    "Function `range` received {}={} but `{}` can only be a number",
+                    name, value, name
+                ))
+            }),
+            None => default.ok_or_else(|| {
+                tera::Error::msg(format!(
+                    "Function `range` was called without a `{}` argument",
+                    name
+                ))
+            }),
+        }
+    };
+    let start = argument("start", Some(0))?;
+    let end = argument("end", None)?;
+    let step_by = argument("step_by", Some(1))?;
+
+    if start > end {
+        return Err(tera::Error::msg(
+            "Function `range` was called with a `start` argument greater than the `end` one",
+        ));
+    }
+    if step_by == 0 {
+        return Err(tera::Error::msg(
+            "Function `range` was called with a `step_by` argument of 0",
+        ));
+    }
+    if (end - start).div_ceil(step_by) > MAX_RANGE_LENGTH as u64 {
+        return Err(tera::Error::msg(format!(
+            "Function `range` cannot build more than {} numbers",
+            MAX_RANGE_LENGTH
+        )));
+    }
+
+    Ok(JsonValue::from(
+        (start..end).step_by(step_by as usize).collect::<Vec<u64>>(),
+    ))
+}
+
+/// Gathers the macros of the same template that `nodes` call, macro definitions are
+/// skipped since Tera keeps their bodies apart.
+fn collect_tera_macro_calls(nodes: &[Node], calls: &mut Vec<String>) {
+    for node in nodes {
+        match node {
+            Node::VariableBlock(_, expr) => collect_tera_expr_calls(expr, calls),
+            Node::Set(_, set) => collect_tera_expr_calls(&set.value, calls),
+            Node::FilterSection(_, section, _) => {
+                section
+                    .filter
+                    .args
+                    .values()
+                    .for_each(|arg| collect_tera_expr_calls(arg, calls));
+                collect_tera_macro_calls(&section.body, calls);
+            }
+            Node::Block(_, block, _) => collect_tera_macro_calls(&block.body, calls),
+            Node::Forloop(_, forloop, _) => {
+                collect_tera_expr_calls(&forloop.container, calls);
+                collect_tera_macro_calls(&forloop.body, calls);
+                if let Some(empty_body) = &forloop.empty_body {
+                    collect_tera_macro_calls(empty_body, calls);
+                }
+            }
+            Node::If(condition, _) => {
+                condition.conditions.iter().for_each(|(_, expr, body)| {
+                    collect_tera_expr_calls(expr, calls);
+                    collect_tera_macro_calls(body, calls);
+                });
+                if let Some((_, body)) = &condition.otherwise {
+                    collect_tera_macro_calls(body, calls);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_tera_expr_calls(expr: &Expr, calls: &mut Vec<String>) {
+    expr.filters
+        .iter()
+        .flat_map(|filter| filter.args.values())
+        .for_each(|arg| collect_tera_expr_calls(arg, calls));
+    collect_tera_value_calls(&expr.val, calls);
+}
+
+fn collect_tera_value_calls(value: &ExprVal, calls: &mut Vec<String>) {
+    match value {
+        ExprVal::MacroCall(macro_call) => {
+            if macro_call.namespace == TERA_SELF_NAMESPACE {
+                calls.push(macro_call.name.clone());
+            }
+            macro_call
+                .args
+                .values()
+                .for_each(|arg| collect_tera_expr_calls(arg, calls));
+        }
+        ExprVal::FunctionCall(function_call) => function_call
+            .args
+            .values()
+            .for_each(|arg| collect_tera_expr_calls(arg, calls)),
+        ExprVal::Math(math) => {
+            collect_tera_expr_calls(&math.lhs, calls);
+            collect_tera_expr_calls(&math.rhs, calls);
+        }
+        ExprVal::Logic(logic) => {
+            collect_tera_expr_calls(&logic.lhs, calls);
+            collect_tera_expr_calls(&logic.rhs, calls);
+        }
+        ExprVal::In(contains) => {
+            collect_tera_expr_calls(&contains.lhs, calls);
+            collect_tera_expr_calls(&contains.rhs, calls);
+        }
+        ExprVal::Test(test) => test
+            .args
+            .iter()
+            .for_each(|arg| collect_tera_expr_calls(arg, calls)),
+        ExprVal::Array(values) => values
+            .iter()
+            .for_each(|arg| collect_tera_expr_calls(arg, calls)),
+        ExprVal::StringConcat(concat) => concat
+            .values
+            .iter()
+            .for_each(|value| collect_tera_value_calls(value, calls)),
+        _ => {}
+    }
+}
+
+/// Renders Liquid templates, `{{ "key" | t: count: n }}` translates. Liquid has no output
+/// escaping of its own, so input values are escaped for the content type before they
+/// are handed to the template.
+pub struct LiquidEngine;
+
+impl LiquidEngine {
+    fn parser(
+        translator: TranslateHelper,
+        deadline: Option<RenderDeadline>,
+    ) -> liquid_core::Result<liquid::Parser> {
+        let builder = liquid::ParserBuilder::with_stdlib().filter(LiquidTranslateFilterParser {
+            translator: Arc::new(translator),
+        });
+        match deadline {
+            Some(deadline) => builder
+                .block(LiquidDeadlineBlockParser {
+                    block: Box::new(ForBlock),
+                    deadline,
+                })
+                .build(),
+            None => builder.build(),
+        }
+    }
+}
+
+impl TemplateEngine for LiquidEngine {
+    fn validate(&self, body: &str) -> ServiceResult<()> {
+        Self::parser(TranslateHelper::new("", HashMap::new()), None)
+            .and_then(|parser| parser.parse(body))
+            .map(|_| ())
+            .map_err(|err| invalid_syntax(TemplateEngineKind::Liquid, &err))
+    }
+
+    fn render(
+        &self,
+        body: &str,
+        data: &BTreeMap<String, String>,
+        translator: TranslateHelper,
+        content_type: ContentType,
+        limits: &RenderLimits,
+        deadline: &RenderDeadline,
+    ) -> ServiceResult<String> {
+        let mut output = RenderOutput::new(limits, deadline);
+        let template = Self::parser(translator, Some(deadline.clone()))
+            .and_then(|parser| parser.parse(body))
+            .map_err(|_| render_failed())?;
+        let escaped_data = data
+            .iter()
+            .map(|(name, value)| (name.clone(), content_type.escape(value)))
+            .collect::<BTreeMap<String, String>>();
+        let globals = liquid::to_object(&escaped_data).map_err(|_| render_failed())?;
+
+        let rendered = template.render_to(&mut output, &globals);
+        finish_render(output, rendered)
+    }
+}
+
+/// Parses a Liquid loop block into one that stops looping once the deadline passes.
+#[derive(Clone)]
+struct LiquidDeadlineBlockParser {
+    block: Box<dyn ParseBlock>,
+    deadline: RenderDeadline,
+}
+
+impl ParseBlock for LiquidDeadlineBlockParser {
+    fn parse(
+        &self,
+        arguments: TagTokenIter,
+        block: TagBlock,
+        options: &Language,
+    ) -> liquid_core::Result<Box<dyn Renderable>> {
+        Ok(Box::new(LiquidDeadlineBlock {
+            block: self.block.parse(arguments, block, options)?,
+            deadline: self.deadline.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn BlockReflection {
+        self.block.reflection()
+    }
+}
+
+#[derive(Debug)]
+struct LiquidDeadlineBlock {
+    block: Box<dyn Renderable>,
+    deadline: RenderDeadline,
+}
+
+impl Renderable for LiquidDeadlineBlock {
+    fn render_to(
+        &self,
+        writer: &mut dyn std::io::Write,
+        runtime: &dyn Runtime,
+    ) -> liquid_core::Result<()> {
+        self.block.render_to(
+            writer,
+            &LiquidDeadlineRuntime {
+                runtime,
+                deadline: &self.deadline,
+            },
+        )
+    }
+}
+
+/// Liquid loops read their interrupt register after every iteration, this runtime
+/// interrupts them with a break once the deadline passes.
+struct LiquidDeadlineRuntime<'r> {
+    runtime: &'r dyn Runtime,
+    deadline: &'r RenderDeadline,
+}
+
+impl<'r> Runtime for LiquidDeadlineRuntime<'r> {
+    fn partials(&self) -> &dyn PartialStore {
+        self.runtime.partials()
+    }
+
+    fn name(&self) -> Option<KStringRef<'_>> {
+        self.runtime.name()
+    }
+
+    fn roots(&self) -> std::collections::BTreeSet<KStringCow<'_>> {
+        self.runtime.roots()
+    }
+
+    fn try_get(&self, path: &[ScalarCow<'_>]) -> Option<ValueCow<'_>> {
+        self.runtime.try_get(path)
+    }
+
+    fn get(&self, path: &[ScalarCow<'_>]) -> liquid_core::Result<ValueCow<'_>> {
+        self.runtime.get(path)
+    }
+
+    fn set_global(&self, name: KString, val: LiquidValue) -> Option<LiquidValue> {
+        self.runtime.set_global(name, val)
+    }
+
+    fn set_index(&self, name: KString, val: LiquidValue) -> Option<LiquidValue> {
+        self.runtime.set_index(name, val)
+    }
+
+    fn get_index<'a>(&'a self, name: &str) -> Option<ValueCow<'a>> {
+        self.runtime.get_index(name)
+    }
+
+    fn registers(&self) -> &Registers {
+        let registers = self.runtime.registers();
+        if self.deadline.check() {
+            registers
+                .get_mut::<InterruptRegister>()
+                .set(Interrupt::Break);
+        }
+        registers
+    }
+}
+
+#[derive(Clone)]
+struct LiquidTranslateFilterParser {
+    translator: Arc<TranslateHelper>,
+}
+
+impl FilterReflection for LiquidTranslateFilterParser {
+    fn name(&self) -> &str {
+        TRANSLATE_HELPER_NAME
+    }
+
+    fn description(&self) -> &str {
+        "Translates a message catalog key."
+    }
+
+    fn positional_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+
+    fn keyword_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+}
+
+impl ParseFilter for LiquidTranslateFilterParser {
+    fn parse(&self, mut arguments: FilterArguments) -> liquid_core::Result<Box<dyn Filter>> {
+        if arguments.positional.next().is_some() {
+            return Err(liquid_core::Error::with_msg(
+                "t filter only takes keyword arguments",
+            ));
+        }
+
+        Ok(Box::new(LiquidTranslateFilter {
+            translator: self.translator.clone(),
+            arguments: arguments
+                .keyword
+                .map(|(name, expression)| (name.to_string(), expression))
+                .collect(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn FilterReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct LiquidTranslateFilter {
+    translator: Arc<TranslateHelper>,
+    arguments: Vec<(String, Expression)>,
+}
+
+impl fmt::Display for LiquidTranslateFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(TRANSLATE_HELPER_NAME)
+    }
+}
+
+impl Filter for LiquidTranslateFilter {
+    fn evaluate(
+        &self,
+        input: &dyn ValueView,
+        runtime: &dyn Runtime,
+    ) -> liquid_core::Result<LiquidValue> {
+        let mut args = FluentArgs::new();
+        for (name, expression) in &self.arguments {
+            let value = expression.evaluate(runtime)?;
+            args.set(
+                name.clone(),
+                into_fluent_value(&JsonValue::String(value.to_kstr().to_string())),
+            );
+        }
+
+        self.translator
+            .translate(&input.to_kstr(), &args)
+            .map(LiquidValue::scalar)
+            .map_err(|err| liquid_core::Error::with_msg(err.desc))
+    }
+}
+
+fn invalid_syntax(engine: TemplateEngineKind, err: &dyn Error) -> ServiceError {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+
+    ServiceError::BadRequest(format!(
+        "template body is not valid {} syntax: {}",
+        engine, message
+    ))
+}
+
+/// The rendered output, or the error of the limit that cut the render short.
+fn finish_render<T, E>(output: RenderOutput, rendered: Result<T, E>) -> ServiceResult<String> {
+    if let Some(err) = output.limit_error() {
+        return Err(err);
+    }
+    rendered.map_err(|_| render_failed())?;
+
+    output.into_string()
+}
+
+fn render_failed() -> ServiceError {
+    ServiceError::InternalServerErrorWithContext("Failed to render template".to_string())
+}
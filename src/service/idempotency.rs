@@ -0,0 +1,116 @@
+use madtofan_microservice_common::{
+    errors::{ServiceError, ServiceResult},
+    templating::TemplateResponse,
+};
+use prost::Message;
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+
+use crate::repository::idempotency_key::DynIdempotencyKeyRepositoryTrait;
+
+use super::revision::TemplateRevision;
+
+/// The response recorded for an idempotency key, with the revision it was sent with.
+#[derive(Clone, PartialEq, Message)]
+struct RecordedResponse {
+    #[prost(message, optional, tag = "1")]
+    template: Option<TemplateResponse>,
+    #[prost(string, tag = "2")]
+    revision: String,
+}
+
+/// Replays the recorded response of a create call retried with the same idempotency
+/// key, instead of applying it a second time.
+#[derive(Clone)]
+pub struct IdempotencyService {
+    idempotency_key_repository: DynIdempotencyKeyRepositoryTrait,
+}
+
+impl IdempotencyService {
+    pub fn new(idempotency_key_repository: DynIdempotencyKeyRepositoryTrait) -> Self {
+        Self {
+            idempotency_key_repository,
+        }
+    }
+
+    /// The response recorded for the key, a key reused for a request with a different
+    /// fingerprint is rejected.
+    pub async fn replay(
+        &self,
+        tenant: &str,
+        key: &str,
+        fingerprint: &[u8],
+    ) -> ServiceResult<Option<TemplateRevision>> {
+        let idempotency_key = self
+            .idempotency_key_repository
+            .get_idempotency_key(tenant, key)
+            .await?;
+
+        match idempotency_key {
+            Some(idempotency_key) if idempotency_key.request != fingerprint => {
+                error!("idempotency key {:?} was used for a different request", key);
+                Err(ServiceError::BadRequest(String::from(
+                    "idempotency key was used for a different request",
+                )))
+            }
+            Some(idempotency_key) => {
+                info!("replaying the response of idempotency key {:?}", key);
+                let recorded_response = RecordedResponse::decode(
+                    idempotency_key.response.as_slice(),
+                )
+                .map_err(|_| {
+                    ServiceError::InternalServerErrorWithContext(String::from(
+                        "Failed to decode the recorded response",
+                    ))
+                })?;
+
+                Ok(Some(TemplateRevision {
+                    template: recorded_response.template.unwrap_or_default(),
+                    revision: recorded_response.revision,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn record(
+        &self,
+        tenant: &str,
+        key: &str,
+        fingerprint: &[u8],
+        response: &TemplateRevision,
+    ) -> ServiceResult<()> {
+        let recorded_response = RecordedResponse {
+            template: Some(response.template.clone()),
+            revision: response.revision.clone(),
+        };
+        self.idempotency_key_repository
+            .add_idempotency_key(tenant, key, fingerprint, &recorded_response.encode_to_vec())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Digests everything that decides what a request does: the tenant, the request and
+/// the metadata values it was sent with. Retrying with the same key only replays the
+/// recorded response when the fingerprints match.
+pub fn fingerprint<Req: Message>(
+    tenant: &str,
+    request: &Req,
+    metadata: &[(&str, &str)],
+) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    let mut write_field = |field: &[u8]| {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field);
+    };
+    write_field(tenant.as_bytes());
+    write_field(&request.encode_to_vec());
+    for (name, value) in metadata {
+        write_field(name.as_bytes());
+        write_field(value.as_bytes());
+    }
+
+    hasher.finalize().to_vec()
+}
@@ -0,0 +1,220 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+use tokio::{task, time};
+
+/// Bounds every render is held to, a template exceeding one fails with an error naming
+/// the limit instead of tying up the service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderLimits {
+    pub timeout: Duration,
+    pub max_output_bytes: usize,
+    /// How deeply partials (Handlebars inline partials, Jinja and Tera macros) may call
+    /// each other, a partial that calls itself always exceeds it.
+    pub max_partial_depth: usize,
+}
+
+impl RenderLimits {
+    pub fn new(timeout: Duration, max_output_bytes: usize, max_partial_depth: usize) -> Self {
+        Self {
+            timeout,
+            max_output_bytes,
+            max_partial_depth,
+        }
+    }
+
+    pub fn timed_out(&self) -> ServiceError {
+        ServiceError::BadRequest(format!(
+            "template rendering did not finish within {}ms",
+            self.timeout.as_millis()
+        ))
+    }
+
+    pub fn output_too_large(&self) -> ServiceError {
+        ServiceError::BadRequest(format!(
+            "rendered template exceeds the maximum output size of {} bytes",
+            self.max_output_bytes
+        ))
+    }
+
+    pub fn partials_too_deep(&self) -> ServiceError {
+        ServiceError::BadRequest(format!(
+            "template partials are nested deeper than the maximum depth of {}",
+            self.max_partial_depth
+        ))
+    }
+}
+
+impl Default for RenderLimits {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(5), 1024 * 1024, 16)
+    }
+}
+
+/// Runs a render on the blocking pool so a slow template does not stall the executor,
+/// failing once `limits.timeout` elapses. The engines stop the render itself at the
+/// [`RenderDeadline`], so it does not keep running on the blocking pool.
+pub async fn render_blocking<F>(limits: RenderLimits, render: F) -> ServiceResult<String>
+where
+    F: FnOnce() -> ServiceResult<String> + Send + 'static,
+{
+    match time::timeout(limits.timeout, task::spawn_blocking(render)).await {
+        Ok(Ok(rendered)) => rendered,
+        Ok(Err(_)) => Err(ServiceError::InternalServerErrorWithContext(
+            "Failed to render template".to_string(),
+        )),
+        Err(_) => Err(limits.timed_out()),
+    }
+}
+
+/// When a compose has to be done by, `limits.timeout` after it starts. Every render of
+/// the compose shares it, the body as well as its layout and unsubscribe footer. Writes
+/// check it, and so do the engines on every loop iteration, so a loop that writes
+/// nothing stops once it passes too.
+#[derive(Debug, Clone)]
+pub struct RenderDeadline {
+    deadline: Instant,
+    passed: Arc<AtomicBool>,
+}
+
+impl RenderDeadline {
+    pub fn new(limits: &RenderLimits) -> Self {
+        Self {
+            deadline: Instant::now() + limits.timeout,
+            passed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Whether the deadline has passed, the render is timed out from then on.
+    pub fn check(&self) -> bool {
+        if !self.passed() && Instant::now() > self.deadline {
+            self.passed.store(true, Ordering::Relaxed);
+        }
+        self.passed()
+    }
+
+    pub fn passed(&self) -> bool {
+        self.passed.load(Ordering::Relaxed)
+    }
+
+    /// The time left until the deadline.
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+}
+
+/// The writer engines render into, it refuses further output once the output grows past
+/// `max_output_bytes` or the deadline passes, which stops a runaway render part way
+/// through instead of leaving it running on the blocking pool.
+pub struct RenderOutput {
+    limits: RenderLimits,
+    deadline: RenderDeadline,
+    output: Vec<u8>,
+    too_large: bool,
+}
+
+impl RenderOutput {
+    pub fn new(limits: &RenderLimits, deadline: &RenderDeadline) -> Self {
+        Self {
+            limits: *limits,
+            deadline: deadline.clone(),
+            output: Vec::new(),
+            too_large: false,
+        }
+    }
+
+    /// The error of the limit that stopped the render, if one did.
+    pub fn limit_error(&self) -> Option<ServiceError> {
+        if self.deadline.passed() {
+            Some(self.limits.timed_out())
+        } else if self.too_large {
+            Some(self.limits.output_too_large())
+        } else {
+            None
+        }
+    }
+
+    pub fn into_string(self) -> ServiceResult<String> {
+        String::from_utf8(self.output).map_err(|_| {
+            ServiceError::InternalServerErrorWithContext("Failed to render template".to_string())
+        })
+    }
+}
+
+impl io::Write for RenderOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.too_large && self.output.len() + buf.len() > self.limits.max_output_bytes {
+            self.too_large = true;
+        }
+        if self.deadline.check() || self.too_large {
+            return Err(io::Error::other("render limit exceeded"));
+        }
+
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Checks how deeply the partials called from the body nest, `partials` maps every
+/// partial a template defines to the partials it calls. Calls to partials the template
+/// does not define are left for the engine to reject.
+pub fn check_partial_depth(
+    body_calls: &[String],
+    partials: &HashMap<String, Vec<String>>,
+    limits: &RenderLimits,
+) -> ServiceResult<()> {
+    let mut depths = HashMap::new();
+    let mut deepest = 0;
+    for name in body_calls {
+        let depth = partial_depth(name, partials, &mut depths, &mut HashSet::new())
+            .ok_or_else(|| limits.partials_too_deep())?;
+        deepest = deepest.max(depth);
+    }
+
+    if deepest > limits.max_partial_depth {
+        return Err(limits.partials_too_deep());
+    }
+
+    Ok(())
+}
+
+/// The nesting depth of a call to `name`, `None` when the partial ends up calling
+/// itself.
+fn partial_depth<'a>(
+    name: &'a str,
+    partials: &'a HashMap<String, Vec<String>>,
+    depths: &mut HashMap<&'a str, usize>,
+    calling: &mut HashSet<&'a str>,
+) -> Option<usize> {
+    let calls = match partials.get(name) {
+        Some(calls) => calls,
+        None => return Some(0),
+    };
+    if let Some(depth) = depths.get(name) {
+        return Some(*depth);
+    }
+    if !calling.insert(name) {
+        return None;
+    }
+
+    let mut deepest = 0;
+    for call in calls {
+        deepest = deepest.max(partial_depth(call, partials, depths, calling)?);
+    }
+    calling.remove(name);
+    depths.insert(name, deepest + 1);
+
+    Some(deepest + 1)
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
+
+use anyhow::{bail, Context};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use html5ever::{
+    interface::Attribute,
+    local_name, namespace_url, ns, parse_document, parse_fragment,
+    serialize::{serialize, SerializeOpts, TraversalScope},
+    tendril::{StrTendril, TendrilSink},
+    ParseOpts, QualName,
+};
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+use markup5ever_rcdom::{NodeData, RcDom, SerializableHandle};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use url::Url;
+
+/// Leaves a link exactly as it was written, for links such as verification links that
+/// have to reach their destination unchanged. The attribute is dropped from the output.
+pub const NO_TRACK_ATTRIBUTE: &str = "data-no-track";
+/// Names the tag whose rules a link is rewritten with instead of the template's rules.
+/// The attribute is dropped from the output.
+pub const LINK_TAG_ATTRIBUTE: &str = "data-link-tag";
+
+/// How the `http` and `https` links of a template's HTML output are rewritten.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LinkRules {
+    /// Query parameters added to every link, such as `utm_source`. A parameter the link
+    /// already has keeps its value.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub utm_parameters: BTreeMap<String, String>,
+    /// Sends the link through the service's click-tracking redirect.
+    #[serde(skip_serializing_if = "is_false")]
+    pub track_clicks: bool,
+}
+
+pub fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl LinkRules {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn validate(&self) -> ServiceResult<()> {
+        for (name, value) in &self.utm_parameters {
+            if !name.starts_with("utm_") || name.len() == "utm_".len() {
+                return Err(ServiceError::BadRequest(format!(
+                    "link parameter {:?} is not a UTM parameter",
+                    name
+                )));
+            }
+            if value.trim().is_empty() {
+                return Err(ServiceError::BadRequest(format!(
+                    "UTM parameter {:?} cannot be empty",
+                    name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The redirect tracked links are sent through. It receives the destination as `url`
+/// and the template as `template`, with a `token` signing both so that the redirect
+/// only forwards to links the service wrote.
+#[derive(Clone)]
+pub struct ClickTracking {
+    redirect_url: Url,
+    signing_key: Vec<u8>,
+}
+
+impl ClickTracking {
+    pub fn new(redirect_url: &str, signing_key: &str) -> anyhow::Result<Self> {
+        let redirect_url = Url::parse(redirect_url).context("click-tracking URL is not valid")?;
+        if !matches!(redirect_url.scheme(), "http" | "https") {
+            bail!("click-tracking URL has to be an http or https URL");
+        }
+        if signing_key.is_empty() {
+            bail!("click-tracking signing key cannot be empty");
+        }
+
+        Ok(Self {
+            redirect_url,
+            signing_key: signing_key.as_bytes().to_vec(),
+        })
+    }
+
+    fn mac(&self, template: &str, url: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.signing_key)
+            .expect("HMAC accepts keys of any length");
+        mac.update(template.as_bytes());
+        mac.update(b"\n");
+        mac.update(url.as_bytes());
+        mac
+    }
+
+    pub fn sign(&self, template: &str, url: &str) -> String {
+        URL_SAFE_NO_PAD.encode(self.mac(template, url).finalize().into_bytes())
+    }
+
+    /// Checks the token of a tracked link, for the redirect to run before forwarding.
+    pub fn verify(&self, template: &str, url: &str, token: &str) -> bool {
+        match URL_SAFE_NO_PAD.decode(token) {
+            Ok(token) => self.mac(template, url).verify_slice(&token).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    pub fn wrap(&self, template: &str, url: &str) -> String {
+        let mut redirect_url = self.redirect_url.clone();
+        redirect_url
+            .query_pairs_mut()
+            .append_pair("url", url)
+            .append_pair("template", template)
+            .append_pair("token", &self.sign(template, url));
+        redirect_url.to_string()
+    }
+}
+
+/// The link rules of a template, with the redirect tracked links are sent through.
+pub struct LinkRewriter {
+    pub template: String,
+    pub rules: LinkRules,
+    pub tag_rules: BTreeMap<String, LinkRules>,
+    pub click_tracking: Option<Arc<ClickTracking>>,
+    /// The unsubscribe URL of a marketing template, unsubscribing never goes through
+    /// click tracking.
+    pub unsubscribe_url: Option<String>,
+}
+
+impl LinkRewriter {
+    /// Rewrites the `href` of every `<a>` of the HTML with the template's link rules,
+    /// or with the rules of the tag named by its `data-link-tag`. Links marked
+    /// `data-no-track`, links to the unsubscribe URL and links that are not absolute
+    /// `http` or `https` URLs are left as they are. The HTML is parsed the way a browser would, so markup inside
+    /// scripts, styles, text areas and comments is never taken for a link.
+    pub fn rewrite(&self, html: &str) -> String {
+        rewrite_links(
+            html,
+            &self.template,
+            &self.rules,
+            &self.tag_rules,
+            self.click_tracking.as_deref(),
+            self.unsubscribe_url.as_deref(),
+        )
+    }
+}
+
+fn rewrite_links(
+    html: &str,
+    template: &str,
+    rules: &LinkRules,
+    tag_rules: &BTreeMap<String, LinkRules>,
+    click_tracking: Option<&ClickTracking>,
+    unsubscribe_url: Option<&str>,
+) -> String {
+    let document = is_document(html);
+    let dom = if document {
+        parse_document(RcDom::default(), ParseOpts::default()).one(html)
+    } else {
+        parse_fragment(
+            RcDom::default(),
+            ParseOpts::default(),
+            QualName::new(None, ns!(html), local_name!("body")),
+            vec![],
+        )
+        .one(html)
+    };
+
+    let mut stack = vec![dom.document.clone()];
+    while let Some(node) = stack.pop() {
+        if let NodeData::Element {
+            name,
+            attrs,
+            template_contents,
+            ..
+        } = &node.data
+        {
+            if name.ns == ns!(html) && name.local == local_name!("a") {
+                rewrite_anchor(&mut attrs.borrow_mut(), |href, tag| {
+                    if unsubscribe_url == Some(href.trim()) {
+                        return None;
+                    }
+                    let rules = tag.and_then(|tag| tag_rules.get(tag)).unwrap_or(rules);
+                    rewrite_href(href, template, rules, click_tracking)
+                });
+            }
+            if let Some(contents) = template_contents.borrow().as_ref() {
+                stack.push(contents.clone());
+            }
+        }
+        stack.extend(node.children.borrow().iter().cloned());
+    }
+
+    // A fragment is parsed into an `<html>` element that is left out of the output.
+    let root: SerializableHandle = if document {
+        dom.document.clone().into()
+    } else {
+        dom.document.children.borrow()[0].clone().into()
+    };
+    let mut rewritten = vec![];
+    serialize(
+        &mut rewritten,
+        &root,
+        SerializeOpts {
+            traversal_scope: TraversalScope::ChildrenOnly(None),
+            ..Default::default()
+        },
+    )
+    .expect("serialising to memory does not fail");
+
+    String::from_utf8(rewritten).expect("html5ever serialises UTF-8")
+}
+
+/// Whether the HTML is a whole document rather than a fragment of a body.
+fn is_document(html: &str) -> bool {
+    let start = html.trim_start().as_bytes();
+    ["<!doctype", "<html"].iter().any(|prefix| {
+        start.len() >= prefix.len() && start[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+    })
+}
+
+/// Replaces the `href` of a link with the one `rewrite_href` returns for the rules of
+/// its tag, unless the link opted out, and drops the link tracking attributes.
+fn rewrite_anchor<F>(attributes: &mut Vec<Attribute>, rewrite_href: F)
+where
+    F: Fn(&str, Option<&str>) -> Option<String>,
+{
+    let value = |attributes: &Vec<Attribute>, name: &str| {
+        attributes
+            .iter()
+            .find(|attribute| attribute.name.local.as_ref() == name)
+            .map(|attribute| attribute.value.to_string())
+    };
+    let opted_out = value(attributes, NO_TRACK_ATTRIBUTE).is_some();
+    let tag = value(attributes, LINK_TAG_ATTRIBUTE);
+    attributes.retain(|attribute| {
+        attribute.name.local.as_ref() != NO_TRACK_ATTRIBUTE
+            && attribute.name.local.as_ref() != LINK_TAG_ATTRIBUTE
+    });
+    if opted_out {
+        return;
+    }
+
+    if let Some(href) = attributes
+        .iter_mut()
+        .find(|attribute| attribute.name.local == local_name!("href"))
+    {
+        if let Some(rewritten) = rewrite_href(&href.value, tag.as_deref()) {
+            href.value = StrTendril::from(rewritten);
+        }
+    }
+}
+
+fn rewrite_href(
+    href: &str,
+    template: &str,
+    rules: &LinkRules,
+    click_tracking: Option<&ClickTracking>,
+) -> Option<String> {
+    if rules.is_empty() {
+        return None;
+    }
+    let mut url = Url::parse(href.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+
+    let existing_parameters = url
+        .query_pairs()
+        .map(|(name, _)| name.into_owned())
+        .collect::<HashSet<String>>();
+    let missing_parameters = rules
+        .utm_parameters
+        .iter()
+        .filter(|(name, _)| !existing_parameters.contains(*name))
+        .collect::<Vec<(&String, &String)>>();
+    if !missing_parameters.is_empty() {
+        let mut query = url.query_pairs_mut();
+        for (name, value) in missing_parameters {
+            query.append_pair(name, value);
+        }
+    }
+
+    match click_tracking {
+        Some(click_tracking) if rules.track_clicks => {
+            Some(click_tracking.wrap(template, url.as_str()))
+        }
+        _ => Some(url.into()),
+    }
+}
@@ -0,0 +1,111 @@
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+
+pub const LOCALE_SEPARATOR: char = '-';
+
+#[derive(Debug, Clone)]
+pub struct LocaleSettings {
+    pub default_locale: String,
+    pub supported_locales: Vec<String>,
+}
+
+impl LocaleSettings {
+    pub fn new(default_locale: &str, supported_locales: &[String]) -> ServiceResult<Self> {
+        let default_locale = parse_locale(default_locale)?;
+        let mut supported_locales = supported_locales
+            .iter()
+            .map(|locale| parse_locale(locale))
+            .collect::<ServiceResult<Vec<String>>>()?;
+
+        if !supported_locales.contains(&default_locale) {
+            supported_locales.insert(0, default_locale.clone());
+        }
+
+        Ok(Self {
+            default_locale,
+            supported_locales,
+        })
+    }
+
+    /// Locales to try, most specific first, when looking for the variant of a locale,
+    /// e.g. `ms-MY` resolves through `ms-MY`, `ms` and then the default locale.
+    pub fn fallback_chain(&self, locale: &str) -> Vec<String> {
+        let mut chain = vec![locale.to_string()];
+        let mut current = locale;
+        while let Some((parent, _)) = current.rsplit_once(LOCALE_SEPARATOR) {
+            chain.push(parent.to_string());
+            current = parent;
+        }
+
+        if !chain.contains(&self.default_locale) {
+            chain.push(self.default_locale.clone());
+        }
+
+        chain
+    }
+}
+
+/// The locales a template has bodies for, the base body counts as the default locale,
+/// and the supported locales it is still missing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateLocales {
+    pub name: String,
+    pub locales: Vec<String>,
+    pub missing_locales: Vec<String>,
+}
+
+impl TemplateLocales {
+    pub fn new(name: String, locales: Vec<String>, settings: &LocaleSettings) -> Self {
+        let missing_locales = settings
+            .supported_locales
+            .iter()
+            .filter(|locale| !locales.contains(locale))
+            .cloned()
+            .collect();
+
+        Self {
+            name,
+            locales,
+            missing_locales,
+        }
+    }
+}
+
+impl Default for LocaleSettings {
+    fn default() -> Self {
+        Self {
+            default_locale: String::from("en"),
+            supported_locales: vec![String::from("en")],
+        }
+    }
+}
+
+/// Normalises a locale tag such as `ms_my` into `ms-MY`, the language subtag is lower
+/// cased and two letter region subtags are upper cased.
+pub fn parse_locale(locale: &str) -> ServiceResult<String> {
+    let subtags = locale
+        .trim()
+        .split(['-', '_'])
+        .map(str::to_string)
+        .collect::<Vec<String>>();
+
+    if subtags
+        .iter()
+        .any(|subtag| subtag.is_empty() || !subtag.chars().all(|c| c.is_ascii_alphanumeric()))
+    {
+        return Err(ServiceError::BadRequest(format!(
+            "locale {:?} is invalid",
+            locale
+        )));
+    }
+
+    Ok(subtags
+        .into_iter()
+        .enumerate()
+        .map(|(index, subtag)| match (index, subtag.len()) {
+            (0, _) => subtag.to_ascii_lowercase(),
+            (_, 2) => subtag.to_ascii_uppercase(),
+            _ => subtag,
+        })
+        .collect::<Vec<String>>()
+        .join(&LOCALE_SEPARATOR.to_string()))
+}
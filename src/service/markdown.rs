@@ -0,0 +1,106 @@
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+
+use super::links::{LINK_TAG_ATTRIBUTE, NO_TRACK_ATTRIBUTE};
+
+fn parser(source: &str) -> Parser<'_, '_> {
+    Parser::new_ext(source, Options::ENABLE_TABLES)
+}
+
+/// Converts CommonMark with tables to HTML, raw HTML in the source is cleaned down to
+/// a safe allowlist of tags and attributes. Links keep the attributes link rewriting
+/// reads, which are dropped once the links are rewritten.
+pub fn markdown_to_html(source: &str) -> String {
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser(source));
+
+    ammonia::Builder::default()
+        .add_tag_attributes("a", &[NO_TRACK_ATTRIBUTE, LINK_TAG_ATTRIBUTE])
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+/// Converts CommonMark with tables to the plain-text alternative of its HTML: markup is
+/// dropped, links keep their destination in parentheses, list items keep their marker
+/// and table cells are separated by `|`. Raw HTML is left out.
+pub fn markdown_to_text(source: &str) -> String {
+    let mut text = String::new();
+    let mut lists: Vec<Option<u64>> = vec![];
+    let mut first_cell = false;
+
+    for event in parser(source) {
+        match event {
+            Event::Text(value) | Event::Code(value) => text.push_str(&value),
+            Event::SoftBreak | Event::HardBreak => text.push('\n'),
+            Event::Rule => {
+                text.push_str("---");
+                end_block(&mut text);
+            }
+            Event::Start(Tag::List(first_number)) => {
+                if !lists.is_empty() {
+                    end_line(&mut text);
+                }
+                lists.push(first_number);
+            }
+            Event::End(Tag::List(_)) => {
+                lists.pop();
+                if lists.is_empty() {
+                    end_block(&mut text);
+                }
+            }
+            Event::Start(Tag::Item) => {
+                end_line(&mut text);
+                text.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        text.push_str(&format!("{}. ", number));
+                        *number += 1;
+                    }
+                    _ => text.push_str("- "),
+                }
+            }
+            Event::End(Tag::Item) | Event::End(Tag::TableHead) | Event::End(Tag::TableRow) => {
+                end_line(&mut text)
+            }
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => first_cell = true,
+            Event::Start(Tag::TableCell) => {
+                if !first_cell {
+                    text.push_str(" | ");
+                }
+                first_cell = false;
+            }
+            Event::End(Tag::Link(_, destination, _)) if !text.ends_with(destination.as_ref()) => {
+                text.push_str(&format!(" ({})", destination));
+            }
+            Event::End(
+                Tag::Paragraph
+                | Tag::Heading(..)
+                | Tag::BlockQuote
+                | Tag::CodeBlock(_)
+                | Tag::Table(_),
+            ) => {
+                if lists.is_empty() {
+                    end_block(&mut text);
+                } else {
+                    end_line(&mut text);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    text.trim_end().to_string()
+}
+
+fn end_line(text: &mut String) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+/// Ends the line and leaves a blank line after it, which separates blocks.
+fn end_block(text: &mut String) {
+    end_line(text);
+    if !text.is_empty() && !text.ends_with("\n\n") {
+        text.push('\n');
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::{BTreeMap, HashMap};
+
+use handlebars::html_escape;
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+use url::Url;
+
+use super::{
+    catalog::TranslateHelper,
+    compose::{render, Layout},
+    content::ContentType,
+    limits::{RenderDeadline, RenderLimits},
+};
+
+/// The input marketing templates are composed with the unsubscribe URL of the
+/// recipient in.
+pub const UNSUBSCRIBE_URL_INPUT: &str = "unsubscribe_url";
+/// Catalog key of the text of the built-in unsubscribe link, `Unsubscribe` when the
+/// catalog has no message for it.
+pub const UNSUBSCRIBE_MESSAGE_KEY: &str = "unsubscribe";
+
+/// How a marketing template lets its recipient unsubscribe.
+pub struct Unsubscribe {
+    pub url: String,
+    /// The footer added when the body does not place the unsubscribe URL itself,
+    /// `None` when it does.
+    pub footer: Option<UnsubscribeFooter>,
+}
+
+pub enum UnsubscribeFooter {
+    /// A paragraph linking to the unsubscribe URL.
+    BuiltIn,
+    /// An HTML template rendered with the values of the composed template.
+    Template(Layout),
+}
+
+impl Unsubscribe {
+    /// Reads the unsubscribe URL from the values of the template, it has to be an
+    /// `http`, `https` or `mailto` URL.
+    pub fn parse_url(data: &BTreeMap<String, String>) -> ServiceResult<String> {
+        let url = data
+            .get(UNSUBSCRIBE_URL_INPUT)
+            .map(|url| url.trim())
+            .filter(|url| !url.is_empty())
+            .ok_or_else(|| {
+                ServiceError::BadRequest(format!(
+                    "marketing templates have to be composed with an {:?} input",
+                    UNSUBSCRIBE_URL_INPUT
+                ))
+            })?;
+
+        match Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https" | "mailto") => {
+                Ok(parsed.into())
+            }
+            _ => Err(ServiceError::BadRequest(format!(
+                "unsubscribe URL {:?} is not an http, https or mailto URL",
+                url
+            ))),
+        }
+    }
+
+    /// The `List-Unsubscribe` header, with the one-click `List-Unsubscribe-Post` header
+    /// of RFC 8058 when the URL is `https`.
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![("List-Unsubscribe", format!("<{}>", self.url))];
+        if self.url.starts_with("https:") {
+            headers.push((
+                "List-Unsubscribe-Post",
+                "List-Unsubscribe=One-Click".to_string(),
+            ));
+        }
+        headers
+    }
+
+    pub fn html_footer(
+        &self,
+        locale: &str,
+        messages: HashMap<String, String>,
+        limits: &RenderLimits,
+        deadline: &RenderDeadline,
+    ) -> ServiceResult<Option<String>> {
+        match &self.footer {
+            Some(UnsubscribeFooter::BuiltIn) => Ok(Some(format!(
+                "<p style=\"font-size: 12px; text-align: center;\"><a href=\"{}\">{}</a></p>",
+                html_escape(&self.url),
+                html_escape(&unsubscribe_text(&messages))
+            ))),
+            Some(UnsubscribeFooter::Template(footer)) => render(
+                footer.engine,
+                ContentType::Html,
+                &footer.body,
+                &footer.data,
+                TranslateHelper::new(locale, messages),
+                limits,
+                deadline,
+            )
+            .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn text_footer(&self, messages: &HashMap<String, String>) -> Option<String> {
+        self.footer
+            .as_ref()
+            .map(|_| format!("{}: {}", unsubscribe_text(messages), self.url))
+    }
+}
+
+fn unsubscribe_text(messages: &HashMap<String, String>) -> String {
+    messages
+        .get(UNSUBSCRIBE_MESSAGE_KEY)
+        .cloned()
+        .unwrap_or_else(|| "Unsubscribe".to_string())
+}
+
+/// Places the footer at the end of the `<body>`, or at the end of the document when it
+/// has none.
+pub fn insert_footer(html: &str, footer: &str) -> String {
+    match html.to_ascii_lowercase().rfind("</body>") {
+        Some(end) => format!("{}{}{}", &html[..end], footer, &html[end..]),
+        None => format!("{}{}", html, footer),
+    }
+}
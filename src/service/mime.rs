@@ -0,0 +1,54 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Separates the parts of a multipart message. `=_` cannot occur in base64 encoded
+/// content, so the boundary never collides with a part.
+const MIME_BOUNDARY: &str = "=_templating-alternative";
+/// Longest line of base64 encoded content RFC 2045 allows.
+const MIME_LINE_LENGTH: usize = 76;
+
+/// One rendering of a composed template in a MIME message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimePart {
+    /// `text/plain` or `text/html`.
+    pub content_type: &'static str,
+    pub content: String,
+}
+
+/// Writes a MIME message with the headers and the parts in increasing order of
+/// preference, a single part is written without a multipart envelope.
+pub fn write_mime(headers: &[(&str, String)], parts: &[MimePart]) -> String {
+    let mut message = String::from("MIME-Version: 1.0\r\n");
+    for (name, value) in headers {
+        message.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    match parts {
+        [part] => write_part(&mut message, part),
+        _ => {
+            message.push_str(&format!(
+                "Content-Type: multipart/alternative; boundary=\"{}\"\r\n\r\n",
+                MIME_BOUNDARY
+            ));
+            for part in parts {
+                message.push_str(&format!("--{}\r\n", MIME_BOUNDARY));
+                write_part(&mut message, part);
+            }
+            message.push_str(&format!("--{}--\r\n", MIME_BOUNDARY));
+        }
+    }
+
+    message
+}
+
+fn write_part(message: &mut String, part: &MimePart) {
+    message.push_str(&format!(
+        "Content-Type: {}; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n",
+        part.content_type
+    ));
+
+    let encoded = STANDARD.encode(&part.content);
+    for line in encoded.as_bytes().chunks(MIME_LINE_LENGTH) {
+        message.push_str(&String::from_utf8_lossy(line));
+        message.push_str("\r\n");
+    }
+}
@@ -0,0 +1,47 @@
+use std::{collections::HashMap, fmt::Display, sync::Mutex};
+
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+use mrml::prelude::render::RenderOptions;
+
+/// How many compiled bodies an [`MjmlCache`] keeps before it starts over.
+const MJML_CACHE_CAPACITY: usize = 256;
+
+/// Compiles an MJML body to email-safe HTML. Placeholders of the template engine pass
+/// through untouched, so bodies are compiled before the values are placed in.
+pub fn compile_mjml(body: &str) -> ServiceResult<String> {
+    let mjml = mrml::parse(body).map_err(|err| invalid_mjml(&err))?;
+
+    mjml.render(&RenderOptions {
+        disable_comments: true,
+        ..Default::default()
+    })
+    .map_err(|err| invalid_mjml(&err))
+}
+
+fn invalid_mjml(err: &dyn Display) -> ServiceError {
+    ServiceError::BadRequest(format!("template body is not valid MJML: {}", err))
+}
+
+/// The HTML compose compiled MJML bodies to, keyed by their source so a body is only
+/// compiled again once it changes.
+#[derive(Default)]
+pub struct MjmlCache {
+    compiled: Mutex<HashMap<String, String>>,
+}
+
+impl MjmlCache {
+    pub fn compile(&self, body: &str) -> ServiceResult<String> {
+        if let Some(compiled) = self.compiled.lock().unwrap().get(body) {
+            return Ok(compiled.clone());
+        }
+
+        let compiled = compile_mjml(body)?;
+        let mut cache = self.compiled.lock().unwrap();
+        if cache.len() >= MJML_CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(body.to_string(), compiled.clone());
+
+        Ok(compiled)
+    }
+}
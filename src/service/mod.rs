@@ -1,20 +1,64 @@
+pub mod bundle;
+pub mod catalog;
+pub mod compose;
+pub mod content;
+pub mod css;
+pub mod engine;
+pub mod idempotency;
+pub mod limits;
+pub mod links;
+pub mod locale;
+pub mod markdown;
+pub mod marketing;
+pub mod mime;
+pub mod mjml;
+pub mod namespace;
+pub mod revision;
+pub mod sanitize;
+pub mod settings;
 pub mod templating;
 
 #[cfg(test)]
 pub mod test {
-    use std::sync::Arc;
+    use std::{
+        collections::{BTreeMap, HashMap},
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
 
-    use madtofan_microservice_common::templating::{
-        compose_request::InputValue, ListTemplateRequest, TemplateInput,
+    use madtofan_microservice_common::{
+        errors::{ServiceError, ServiceResult},
+        templating::{compose_request::InputValue, ListTemplateRequest, TemplateInput},
     };
     use sqlx::PgPool;
 
-    use crate::repository::{
-        input::{DynInputRepositoryTrait, InputRepository},
-        template::{DynTemplateRepositoryTrait, TemplateRepository},
+    use crate::{
+        repository::{
+            memory::InMemoryStore,
+            template::{DynTemplateRepositoryTrait, TemplateFields, GLOBAL_TENANT},
+        },
+        storage::Repositories,
     };
 
-    use super::templating::{DynTemplatingServiceTrait, TemplatingService};
+    use super::{
+        bundle::{ConflictStrategy, FieldChange, ImportAction, ImportOptions},
+        catalog::TranslateHelper,
+        compose::ComposeOutput,
+        content::ContentType,
+        engine::TemplateEngineKind,
+        limits::{RenderDeadline, RenderLimits},
+        links::{ClickTracking, LinkRewriter, LinkRules},
+        locale::{parse_locale, LocaleSettings},
+        markdown::markdown_to_html,
+        sanitize::SanitizedInput,
+        settings::TemplateSettings,
+        templating::{
+            ComposeOptions, DynTemplatingServiceTrait, EnsureOutcome, TemplateDefinition,
+            TemplatingService,
+        },
+    };
 
     struct AllTraits {
         templating_service: DynTemplatingServiceTrait,
@@ -22,19 +66,15 @@ pub mod test {
     }
 
     fn initialize_handler(pool: PgPool) -> AllTraits {
-        let inputs_repository =
-            Arc::new(InputRepository::new(pool.clone())) as DynInputRepositoryTrait;
-        let templates_repository = Arc::new(TemplateRepository::new(
-            pool.clone(),
-            inputs_repository.clone(),
-        )) as DynTemplateRepositoryTrait;
+        let repositories = Repositories::postgres(pool);
         let templating_service = Arc::new(TemplatingService::new(
-            templates_repository.clone(),
-            inputs_repository.clone(),
+            &repositories,
+            LocaleSettings::new("en", &["en".to_string(), "ms".to_string()])
+                .expect("test locales are valid"),
         )) as DynTemplatingServiceTrait;
 
         AllTraits {
-            templates_repository,
+            templates_repository: repositories.template_repository,
             templating_service,
         }
     }
@@ -53,16 +93,21 @@ pub mod test {
         all_traits
             .templating_service
             .add_template(
-                template_name.to_string(),
-                template_description.to_string(),
-                "<p>{input}</p>".to_string(),
-                vec![input],
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: template_name.to_string(),
+                    description: template_description.to_string(),
+                    body: "<p>{input}</p>".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: vec![input],
+                },
             )
             .await?;
 
         let created_template = all_traits
             .templates_repository
-            .get_template(template_name)
+            .get_template(GLOBAL_TENANT, "", template_name)
             .await?;
 
         assert_eq!(created_template.unwrap().description, template_description);
@@ -84,21 +129,27 @@ pub mod test {
         all_traits
             .templates_repository
             .add_template(
+                GLOBAL_TENANT,
+                "",
                 template_name,
-                template_description,
-                "template body {input_name}",
-                &input,
+                TemplateFields {
+                    description: template_description,
+                    body: "template body {input_name}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
             )
             .await?;
 
         all_traits
             .templating_service
-            .remove_template(template_name.to_string())
+            .remove_template(GLOBAL_TENANT.to_string(), template_name.to_string(), None)
             .await?;
 
         let get_template = all_traits
             .templates_repository
-            .get_template(template_name)
+            .get_template(GLOBAL_TENANT, "", template_name)
             .await?;
 
         assert!(get_template.is_none());
@@ -119,24 +170,115 @@ pub mod test {
         all_traits
             .templates_repository
             .add_template(
+                GLOBAL_TENANT,
+                "",
                 template_name,
-                "template_description",
-                "template body {input_name}",
-                &input,
+                TemplateFields {
+                    description: "template_description",
+                    body: "template body {input_name}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
             )
             .await?;
 
         let templates_list = all_traits
             .templating_service
-            .list_templates(ListTemplateRequest {
-                offset: 0,
-                limit: 10,
-            })
+            .list_templates(
+                GLOBAL_TENANT.to_string(),
+                ListTemplateRequest {
+                    offset: 0,
+                    limit: 10,
+                },
+                None,
+            )
             .await?
             .templates;
 
         assert_eq!(templates_list.len(), 1);
-        assert_eq!(templates_list.first().unwrap().name, template_name);
+        assert_eq!(templates_list.first().unwrap().template.name, template_name);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn move_template_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let input = vec![TemplateInput {
+            name: "input_name".to_string(),
+            default_value: "default_value".to_string(),
+        }];
+
+        all_traits
+            .templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "auth/welcome".to_string(),
+                    description: "template_description".to_string(),
+                    body: "template body {{input_name}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: input.clone(),
+                },
+            )
+            .await?;
+        all_traits
+            .templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "billing/welcome".to_string(),
+                    description: "template_description".to_string(),
+                    body: "template body {{input_name}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: input,
+                },
+            )
+            .await?;
+
+        let conflicting_move = all_traits
+            .templating_service
+            .move_template(
+                GLOBAL_TENANT.to_string(),
+                "auth/welcome".to_string(),
+                "billing".to_string(),
+            )
+            .await;
+
+        assert!(conflicting_move.is_err());
+
+        let moved_template = all_traits
+            .templating_service
+            .move_template(
+                GLOBAL_TENANT.to_string(),
+                "auth/welcome".to_string(),
+                "marketing/emails".to_string(),
+            )
+            .await?;
+
+        assert_eq!(moved_template.template.name, "marketing/emails/welcome");
+
+        let templates_list = all_traits
+            .templating_service
+            .list_templates(
+                GLOBAL_TENANT.to_string(),
+                ListTemplateRequest {
+                    offset: 0,
+                    limit: 10,
+                },
+                Some("marketing".to_string()),
+            )
+            .await?;
+
+        assert_eq!(templates_list.count, 1);
+        assert_eq!(
+            templates_list.templates.first().unwrap().template.name,
+            "marketing/emails/welcome"
+        );
 
         Ok(())
     }
@@ -155,16 +297,27 @@ pub mod test {
         all_traits
             .templates_repository
             .add_template(
+                GLOBAL_TENANT,
+                "",
                 template_name,
-                "template_description",
-                "composed text: {{input_name}}",
-                &input,
+                TemplateFields {
+                    description: "template_description",
+                    body: "composed text: {{input_name}}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
             )
             .await?;
 
         let composed_text = all_traits
             .templating_service
-            .compose(template_name.to_string(), vec![])
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                vec![],
+                ComposeOptions::default(),
+            )
             .await?;
 
         assert_eq!(&composed_text, "composed text: default_value");
@@ -176,11 +329,2412 @@ pub mod test {
 
         let composed_text = all_traits
             .templating_service
-            .compose(template_name.to_string(), vec![input])
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                vec![input],
+                ComposeOptions::default(),
+            )
             .await?;
 
         assert_eq!(&composed_text, "composed text: value");
 
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn compose_global_fallback_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let input = vec![TemplateInput {
+            name: "input_name".to_string(),
+            default_value: "default_value".to_string(),
+        }];
+
+        all_traits
+            .templates_repository
+            .add_template(
+                GLOBAL_TENANT,
+                "",
+                template_name,
+                TemplateFields {
+                    description: "template_description",
+                    body: "global text: {{input_name}}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
+            )
+            .await?;
+
+        let composed_text = all_traits
+            .templating_service
+            .compose(
+                "tenant".to_string(),
+                template_name.to_string(),
+                vec![],
+                ComposeOptions::default(),
+            )
+            .await?;
+
+        assert_eq!(&composed_text, "global text: default_value");
+
+        all_traits
+            .templates_repository
+            .add_template(
+                "tenant",
+                "",
+                template_name,
+                TemplateFields {
+                    description: "template_description",
+                    body: "tenant text: {{input_name}}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
+            )
+            .await?;
+
+        let composed_text = all_traits
+            .templating_service
+            .compose(
+                "tenant".to_string(),
+                template_name.to_string(),
+                vec![],
+                ComposeOptions::default(),
+            )
+            .await?;
+
+        assert_eq!(&composed_text, "tenant text: default_value");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn compose_override_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "registration";
+        let input = vec![
+            TemplateInput {
+                name: "logo".to_string(),
+                default_value: "base_logo".to_string(),
+            },
+            TemplateInput {
+                name: "colour".to_string(),
+                default_value: "base_colour".to_string(),
+            },
+        ];
+
+        all_traits
+            .templates_repository
+            .add_template(
+                GLOBAL_TENANT,
+                "",
+                template_name,
+                TemplateFields {
+                    description: "template_description",
+                    body: "{{logo}} {{colour}}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
+            )
+            .await?;
+
+        all_traits
+            .templating_service
+            .set_override(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                "product".to_string(),
+                None,
+                vec![TemplateInput {
+                    name: "logo".to_string(),
+                    default_value: "product_logo".to_string(),
+                }],
+            )
+            .await?;
+
+        let composed_text = all_traits
+            .templating_service
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                vec![InputValue {
+                    name: "colour".to_string(),
+                    value: "request_colour".to_string(),
+                }],
+                ComposeOptions {
+                    scope: Some("product".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert_eq!(&composed_text, "product_logo request_colour");
+
+        let composed_text = all_traits
+            .templating_service
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                vec![],
+                ComposeOptions::default(),
+            )
+            .await?;
+
+        assert_eq!(&composed_text, "base_logo base_colour");
+
+        let unknown_input = all_traits
+            .templating_service
+            .set_override(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                "product".to_string(),
+                Some("body".to_string()),
+                vec![TemplateInput {
+                    name: "unknown".to_string(),
+                    default_value: "value".to_string(),
+                }],
+            )
+            .await;
+
+        assert!(unknown_input.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn compose_locale_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let input = vec![TemplateInput {
+            name: "input_name".to_string(),
+            default_value: "default_value".to_string(),
+        }];
+
+        all_traits
+            .templates_repository
+            .add_template(
+                GLOBAL_TENANT,
+                "",
+                template_name,
+                TemplateFields {
+                    description: "template_description",
+                    body: "hello {{input_name}}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
+            )
+            .await?;
+        all_traits
+            .templating_service
+            .set_variant(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                "ms".to_string(),
+                "selamat datang {{input_name}}".to_string(),
+            )
+            .await?;
+
+        let composed_text = all_traits
+            .templating_service
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                vec![],
+                ComposeOptions {
+                    locale: Some("ms-MY".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert_eq!(&composed_text, "selamat datang default_value");
+
+        let composed_text = all_traits
+            .templating_service
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                vec![],
+                ComposeOptions {
+                    locale: Some("zh-CN".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert_eq!(&composed_text, "hello default_value");
+
+        let template_locales = all_traits
+            .templating_service
+            .list_template_locales(
+                GLOBAL_TENANT.to_string(),
+                ListTemplateRequest {
+                    offset: 0,
+                    limit: 10,
+                },
+                None,
+            )
+            .await?;
+
+        assert_eq!(template_locales.first().unwrap().locales, vec!["en", "ms"]);
+        assert!(template_locales.first().unwrap().missing_locales.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn locale_fallback_test() -> anyhow::Result<()> {
+        let settings = LocaleSettings::new("en", &[])?;
+
+        assert_eq!(parse_locale("ms_my")?, "ms-MY");
+        assert!(parse_locale("ms--MY").is_err());
+        assert_eq!(settings.fallback_chain("ms-MY"), vec!["ms-MY", "ms", "en"]);
+        assert_eq!(settings.fallback_chain("en-GB"), vec!["en-GB", "en"]);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn compose_translation_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let input = vec![TemplateInput {
+            name: "count".to_string(),
+            default_value: "1".to_string(),
+        }];
+
+        all_traits
+            .templates_repository
+            .add_template(
+                GLOBAL_TENANT,
+                "",
+                template_name,
+                TemplateFields {
+                    description: "template_description",
+                    body: "{{t \"cart.items\" count=count}}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
+            )
+            .await?;
+        all_traits
+            .templating_service
+            .set_message(
+                GLOBAL_TENANT.to_string(),
+                "cart.items".to_string(),
+                "en".to_string(),
+                "{ $count ->\n    [one] one item\n   *[other] { $count } items\n}".to_string(),
+            )
+            .await?;
+        all_traits
+            .templating_service
+            .set_message(
+                GLOBAL_TENANT.to_string(),
+                "cart.items".to_string(),
+                "ms".to_string(),
+                "{ $count } barang".to_string(),
+            )
+            .await?;
+
+        let composed_text = all_traits
+            .templating_service
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                vec![InputValue {
+                    name: "count".to_string(),
+                    value: "3".to_string(),
+                }],
+                ComposeOptions::default(),
+            )
+            .await?;
+
+        assert_eq!(&composed_text, "3 items");
+
+        let composed_text = all_traits
+            .templating_service
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                vec![],
+                ComposeOptions {
+                    locale: Some("ms-MY".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert_eq!(&composed_text, "1 barang");
+
+        let invalid_message = all_traits
+            .templating_service
+            .set_message(
+                GLOBAL_TENANT.to_string(),
+                "cart.items".to_string(),
+                "en".to_string(),
+                "{ $count".to_string(),
+            )
+            .await;
+
+        assert!(invalid_message.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn rename_template_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let input = vec![TemplateInput {
+            name: "input_name".to_string(),
+            default_value: "default_value".to_string(),
+        }];
+
+        let template = all_traits
+            .templates_repository
+            .add_template(
+                GLOBAL_TENANT,
+                "",
+                "welcome",
+                TemplateFields {
+                    description: "template_description",
+                    body: "template body {{input_name}}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
+            )
+            .await?;
+
+        let renamed_template = all_traits
+            .templating_service
+            .rename_template(
+                GLOBAL_TENANT.to_string(),
+                "welcome".to_string(),
+                "auth/greeting".to_string(),
+            )
+            .await?;
+
+        assert_eq!(renamed_template.template.name, "auth/greeting");
+        assert_eq!(renamed_template.template.template_inputs.len(), 1);
+
+        let get_template = all_traits
+            .templates_repository
+            .get_template(GLOBAL_TENANT, "auth", "greeting")
+            .await?;
+
+        assert_eq!(get_template.unwrap().id, template.id);
+
+        let cloned_template = all_traits
+            .templating_service
+            .clone_template(
+                "tenant".to_string(),
+                "auth/greeting".to_string(),
+                "auth/greeting".to_string(),
+            )
+            .await?;
+
+        assert_eq!(cloned_template.template.name, "auth/greeting");
+
+        let conflicting_clone = all_traits
+            .templating_service
+            .clone_template(
+                "tenant".to_string(),
+                "auth/greeting".to_string(),
+                "auth/greeting".to_string(),
+            )
+            .await;
+
+        assert!(conflicting_clone.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn restore_template_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let input = vec![TemplateInput {
+            name: "input_name".to_string(),
+            default_value: "default_value".to_string(),
+        }];
+
+        all_traits
+            .templates_repository
+            .add_template(
+                GLOBAL_TENANT,
+                "",
+                template_name,
+                TemplateFields {
+                    description: "template_description",
+                    body: "composed text: {{input_name}}",
+                    engine: "handlebars",
+                    content_type: "html",
+                    inputs: &input,
+                },
+            )
+            .await?;
+        all_traits
+            .templating_service
+            .remove_template(GLOBAL_TENANT.to_string(), template_name.to_string(), None)
+            .await?;
+
+        let trashed_compose = all_traits
+            .templating_service
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                vec![],
+                ComposeOptions::default(),
+            )
+            .await;
+        let trashed_templates = all_traits
+            .templating_service
+            .list_trashed_templates(
+                GLOBAL_TENANT.to_string(),
+                ListTemplateRequest {
+                    offset: 0,
+                    limit: 10,
+                },
+            )
+            .await?;
+
+        assert!(trashed_compose.is_err());
+        assert_eq!(trashed_templates.count, 1);
+
+        all_traits
+            .templating_service
+            .restore_template(GLOBAL_TENANT.to_string(), template_name.to_string())
+            .await?;
+
+        let composed_text = all_traits
+            .templating_service
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                vec![],
+                ComposeOptions::default(),
+            )
+            .await?;
+
+        assert_eq!(&composed_text, "composed text: default_value");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn update_template_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let input = vec![TemplateInput {
+            name: "input_name".to_string(),
+            default_value: "default_value".to_string(),
+        }];
+
+        let created_template = all_traits
+            .templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: template_name.to_string(),
+                    description: "template_description".to_string(),
+                    body: "first body {{input_name}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: input.clone(),
+                },
+            )
+            .await?;
+        let updated_template = all_traits
+            .templating_service
+            .update_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: template_name.to_string(),
+                    description: "updated_description".to_string(),
+                    body: "second body {{input_name}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: input.clone(),
+                },
+                Some(created_template.revision.clone()),
+            )
+            .await?;
+
+        assert_eq!(updated_template.template.description, "updated_description");
+        assert_ne!(updated_template.revision, created_template.revision);
+
+        let stale_update = all_traits
+            .templating_service
+            .update_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: template_name.to_string(),
+                    description: "stale_description".to_string(),
+                    body: "stale body {{input_name}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: input,
+                },
+                Some(created_template.revision),
+            )
+            .await;
+
+        assert!(stale_update.is_err());
+
+        let current_template = all_traits
+            .templating_service
+            .get_template(GLOBAL_TENANT.to_string(), template_name.to_string())
+            .await?;
+
+        assert_eq!(current_template, updated_template);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn variant_revision_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let created_template = all_traits
+            .templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: template_name.to_string(),
+                    description: "template_description".to_string(),
+                    body: "body {{input_name}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: vec![TemplateInput {
+                        name: "input_name".to_string(),
+                        default_value: "default_value".to_string(),
+                    }],
+                },
+            )
+            .await?;
+        all_traits
+            .templating_service
+            .set_variant(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                "ms".to_string(),
+                "badan {{input_name}}".to_string(),
+            )
+            .await?;
+        let varied_template = all_traits
+            .templating_service
+            .get_template(GLOBAL_TENANT.to_string(), template_name.to_string())
+            .await?;
+
+        assert_ne!(varied_template.revision, created_template.revision);
+
+        all_traits
+            .templating_service
+            .remove_variant(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                "ms".to_string(),
+            )
+            .await?;
+        let restored_template = all_traits
+            .templating_service
+            .get_template(GLOBAL_TENANT.to_string(), template_name.to_string())
+            .await?;
+
+        assert_ne!(restored_template.revision, varied_template.revision);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn ensure_template_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let input = vec![TemplateInput {
+            name: "input_name".to_string(),
+            default_value: "default_value".to_string(),
+        }];
+
+        let mut outcomes = vec![];
+        for body in [
+            "first body {{input_name}}",
+            "first body {{input_name}}",
+            "second body {{input_name}}",
+        ] {
+            let ensured_template = all_traits
+                .templating_service
+                .ensure_template(
+                    GLOBAL_TENANT.to_string(),
+                    TemplateDefinition {
+                        name: template_name.to_string(),
+                        description: "template_description".to_string(),
+                        body: body.to_string(),
+                        engine: TemplateEngineKind::Handlebars,
+                        content_type: ContentType::Html,
+                        inputs: input.clone(),
+                    },
+                )
+                .await?;
+            outcomes.push(ensured_template.outcome);
+        }
+
+        assert_eq!(
+            outcomes,
+            vec![
+                EnsureOutcome::Created,
+                EnsureOutcome::Unchanged,
+                EnsureOutcome::Updated
+            ]
+        );
+
+        let template = all_traits
+            .templates_repository
+            .get_template(GLOBAL_TENANT, "", template_name)
+            .await?;
+
+        assert_eq!(template.unwrap().body, "second body {{input_name}}");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn export_import_templates_test(pool: PgPool) -> anyhow::Result<()> {
+        let all_traits = initialize_handler(pool);
+
+        let template_name = "template_name";
+        let input = vec![TemplateInput {
+            name: "input_name".to_string(),
+            default_value: "default_value".to_string(),
+        }];
+
+        all_traits
+            .templating_service
+            .add_template(
+                "staging".to_string(),
+                TemplateDefinition {
+                    name: template_name.to_string(),
+                    description: "template_description".to_string(),
+                    body: "staging body {{input_name}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: input.clone(),
+                },
+            )
+            .await?;
+        all_traits
+            .templating_service
+            .set_variant(
+                "staging".to_string(),
+                template_name.to_string(),
+                "ms".to_string(),
+                "badan {{input_name}}".to_string(),
+            )
+            .await?;
+        all_traits
+            .templating_service
+            .add_template(
+                "production".to_string(),
+                TemplateDefinition {
+                    name: template_name.to_string(),
+                    description: "template_description".to_string(),
+                    body: "production body {{input_name}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: input,
+                },
+            )
+            .await?;
+
+        let bundle = all_traits
+            .templating_service
+            .export_templates("staging".to_string(), vec![])
+            .await?;
+
+        assert_eq!(bundle.templates.len(), 1);
+        assert_eq!(bundle.templates[0].variants.len(), 1);
+
+        let dry_run_report = all_traits
+            .templating_service
+            .import_templates(
+                "production".to_string(),
+                bundle.clone(),
+                ImportOptions {
+                    strategy: ConflictStrategy::Rename,
+                    dry_run: true,
+                },
+            )
+            .await?;
+
+        assert_eq!(
+            dry_run_report.changes[0].action,
+            ImportAction::Rename {
+                name: "template_name-1".to_string()
+            }
+        );
+        assert_eq!(
+            all_traits
+                .templating_service
+                .export_templates("production".to_string(), vec![])
+                .await?
+                .templates
+                .len(),
+            1
+        );
+
+        let overwrite_report = all_traits
+            .templating_service
+            .import_templates(
+                "production".to_string(),
+                bundle.clone(),
+                ImportOptions {
+                    strategy: ConflictStrategy::Overwrite,
+                    dry_run: true,
+                },
+            )
+            .await?;
+
+        assert_eq!(
+            overwrite_report.changes[0].action,
+            ImportAction::Overwrite {
+                changes: vec![
+                    FieldChange {
+                        field: "body".to_string(),
+                        existing: "production body {{input_name}}".into(),
+                        imported: "staging body {{input_name}}".into(),
+                    },
+                    FieldChange {
+                        field: "variants".to_string(),
+                        existing: serde_json::json!([]),
+                        imported: serde_json::json!([
+                            { "locale": "ms", "body": "badan {{input_name}}" }
+                        ]),
+                    },
+                ]
+            }
+        );
+
+        all_traits
+            .templating_service
+            .import_templates(
+                "production".to_string(),
+                bundle.clone(),
+                ImportOptions {
+                    strategy: ConflictStrategy::Overwrite,
+                    dry_run: false,
+                },
+            )
+            .await?;
+        let overwritten_bundle = all_traits
+            .templating_service
+            .export_templates("production".to_string(), vec![template_name.to_string()])
+            .await?;
+
+        assert_eq!(overwritten_bundle, bundle);
+
+        let unchanged_report = all_traits
+            .templating_service
+            .import_templates("production".to_string(), bundle, ImportOptions::default())
+            .await?;
+
+        assert_eq!(unchanged_report.changes[0].action, ImportAction::Unchanged);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compose_in_memory_test() -> anyhow::Result<()> {
+        let templating_service = Arc::new(TemplatingService::new(
+            &Repositories::memory(InMemoryStore::new()),
+            LocaleSettings::new("en", &["en".to_string(), "ms".to_string()])
+                .expect("test locales are valid"),
+        )) as DynTemplatingServiceTrait;
+        let template_name = "auth/registration";
+
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: template_name.to_string(),
+                    description: "template_description".to_string(),
+                    body: "{{t \"greeting\"}} {{logo}} {{name}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: vec![
+                        TemplateInput {
+                            name: "logo".to_string(),
+                            default_value: "base_logo".to_string(),
+                        },
+                        TemplateInput {
+                            name: "name".to_string(),
+                            default_value: "there".to_string(),
+                        },
+                    ],
+                },
+            )
+            .await?;
+        templating_service
+            .set_override(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                "product".to_string(),
+                None,
+                vec![TemplateInput {
+                    name: "logo".to_string(),
+                    default_value: "product_logo".to_string(),
+                }],
+            )
+            .await?;
+        templating_service
+            .set_variant(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                "ms".to_string(),
+                "{{t \"greeting\"}} {{name}}".to_string(),
+            )
+            .await?;
+        for (locale, value) in [("en", "Hello"), ("ms", "Selamat datang")] {
+            templating_service
+                .set_message(
+                    GLOBAL_TENANT.to_string(),
+                    "greeting".to_string(),
+                    locale.to_string(),
+                    value.to_string(),
+                )
+                .await?;
+        }
+
+        let composed_text = templating_service
+            .compose(
+                "tenant".to_string(),
+                template_name.to_string(),
+                vec![InputValue {
+                    name: "name".to_string(),
+                    value: "Ada".to_string(),
+                }],
+                ComposeOptions {
+                    scope: Some("product".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let localized_text = templating_service
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                template_name.to_string(),
+                vec![],
+                ComposeOptions {
+                    locale: Some("ms-MY".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert_eq!(&composed_text, "Hello product_logo Ada");
+        assert_eq!(&localized_text, "Selamat datang there");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compose_engines_test() -> anyhow::Result<()> {
+        let templating_service = Arc::new(TemplatingService::new(
+            &Repositories::memory(InMemoryStore::new()),
+            LocaleSettings::default(),
+        )) as DynTemplatingServiceTrait;
+        let inputs = vec![
+            TemplateInput {
+                name: "count".to_string(),
+                default_value: "2".to_string(),
+            },
+            TemplateInput {
+                name: "name".to_string(),
+                default_value: "<Ada>".to_string(),
+            },
+        ];
+        templating_service
+            .set_message(
+                GLOBAL_TENANT.to_string(),
+                "items".to_string(),
+                "en".to_string(),
+                "{ $count ->\n    [one] one item\n   *[other] { $count } items\n}".to_string(),
+            )
+            .await?;
+
+        let templates = [
+            (
+                "handlebars",
+                TemplateEngineKind::Handlebars,
+                "{{t \"items\" count=count}} for {{name}}",
+                "2 items for &lt;Ada&gt;",
+            ),
+            (
+                "minijinja",
+                TemplateEngineKind::MiniJinja,
+                "{{ t(\"items\", count=count) }} for {{ name }}",
+                "2 items for &lt;Ada&gt;",
+            ),
+            (
+                "tera",
+                TemplateEngineKind::Tera,
+                "{{ t(key=\"items\", count=count) }} for {{ name }}",
+                "2 items for &lt;Ada&gt;",
+            ),
+            (
+                "liquid",
+                TemplateEngineKind::Liquid,
+                "{{ \"items\" | t: count: count }} for {{ name }}",
+                "2 items for &lt;Ada&gt;",
+            ),
+        ];
+        for (name, engine, body, _) in &templates {
+            templating_service
+                .add_template(
+                    GLOBAL_TENANT.to_string(),
+                    TemplateDefinition {
+                        name: name.to_string(),
+                        description: "template_description".to_string(),
+                        body: body.to_string(),
+                        engine: *engine,
+                        content_type: ContentType::Html,
+                        inputs: inputs.clone(),
+                    },
+                )
+                .await?;
+        }
+
+        for (name, _, _, expected_text) in &templates {
+            let composed_text = templating_service
+                .compose(
+                    GLOBAL_TENANT.to_string(),
+                    name.to_string(),
+                    vec![],
+                    ComposeOptions::default(),
+                )
+                .await?;
+
+            assert_eq!(&composed_text, expected_text);
+        }
+
+        let listed_engines = templating_service
+            .list_template_engines(
+                GLOBAL_TENANT.to_string(),
+                ListTemplateRequest {
+                    offset: 0,
+                    limit: 10,
+                },
+                None,
+            )
+            .await?;
+
+        assert_eq!(listed_engines.len(), 4);
+        assert!(listed_engines
+            .iter()
+            .any(|listed| listed.name == "tera" && listed.engine == TemplateEngineKind::Tera));
+
+        let invalid_template = templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "invalid".to_string(),
+                    description: "template_description".to_string(),
+                    body: "{{ name ".to_string(),
+                    engine: TemplateEngineKind::Tera,
+                    content_type: ContentType::Html,
+                    inputs: inputs.clone(),
+                },
+            )
+            .await;
+        let invalid_variant = templating_service
+            .set_variant(
+                GLOBAL_TENANT.to_string(),
+                "liquid".to_string(),
+                "ms".to_string(),
+                "{% if name %}".to_string(),
+            )
+            .await;
+
+        assert!(invalid_template.is_err());
+        assert!(invalid_variant.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compose_content_types_test() -> anyhow::Result<()> {
+        let templating_service = Arc::new(TemplatingService::new(
+            &Repositories::memory(InMemoryStore::new()),
+            LocaleSettings::default(),
+        )) as DynTemplatingServiceTrait;
+        let inputs = vec![TemplateInput {
+            name: "name".to_string(),
+            default_value: "Tom & \"Jerry\"".to_string(),
+        }];
+
+        let templates = [
+            (
+                "sms",
+                TemplateEngineKind::Handlebars,
+                ContentType::Text,
+                "Hi {{name}}",
+                "Hi Tom & \"Jerry\"",
+            ),
+            (
+                "push",
+                TemplateEngineKind::Handlebars,
+                ContentType::Json,
+                "{\"title\": \"Hi {{name}}\"}",
+                "{\"title\": \"Hi Tom & \\\"Jerry\\\"\"}",
+            ),
+            (
+                "push_jinja",
+                TemplateEngineKind::MiniJinja,
+                ContentType::Json,
+                "{\"title\": \"Hi {{ name }}\"}",
+                "{\"title\": \"Hi Tom & \\\"Jerry\\\"\"}",
+            ),
+            (
+                "sms_tera",
+                TemplateEngineKind::Tera,
+                ContentType::Text,
+                "Hi {{ name }}",
+                "Hi Tom & \"Jerry\"",
+            ),
+            (
+                "push_liquid",
+                TemplateEngineKind::Liquid,
+                ContentType::Json,
+                "{\"title\": \"Hi {{ name }}\"}",
+                "{\"title\": \"Hi Tom & \\\"Jerry\\\"\"}",
+            ),
+            (
+                "email",
+                TemplateEngineKind::Handlebars,
+                ContentType::Html,
+                "<p>Hi {{name}}</p>",
+                "<p>Hi Tom &amp; &quot;Jerry&quot;</p>",
+            ),
+            (
+                "link",
+                TemplateEngineKind::Handlebars,
+                ContentType::Url,
+                "https://example.com/greet?name={{name}}",
+                "https://example.com/greet?name=Tom%20%26%20%22Jerry%22",
+            ),
+            (
+                "link_jinja",
+                TemplateEngineKind::MiniJinja,
+                ContentType::Url,
+                "https://example.com/greet?name={{ name }}",
+                "https://example.com/greet?name=Tom%20%26%20%22Jerry%22",
+            ),
+            (
+                "link_tera",
+                TemplateEngineKind::Tera,
+                ContentType::Url,
+                "https://example.com/greet?name={{ name }}",
+                "https://example.com/greet?name=Tom%20%26%20%22Jerry%22",
+            ),
+            (
+                "link_liquid",
+                TemplateEngineKind::Liquid,
+                ContentType::Url,
+                "https://example.com/greet?name={{ name }}",
+                "https://example.com/greet?name=Tom%20%26%20%22Jerry%22",
+            ),
+        ];
+        for (name, engine, content_type, body, _) in &templates {
+            templating_service
+                .add_template(
+                    GLOBAL_TENANT.to_string(),
+                    TemplateDefinition {
+                        name: name.to_string(),
+                        description: "template_description".to_string(),
+                        body: body.to_string(),
+                        engine: *engine,
+                        content_type: *content_type,
+                        inputs: inputs.clone(),
+                    },
+                )
+                .await?;
+        }
+
+        for (name, _, _, _, expected_text) in &templates {
+            let composed_text = templating_service
+                .compose(
+                    GLOBAL_TENANT.to_string(),
+                    name.to_string(),
+                    vec![],
+                    ComposeOptions::default(),
+                )
+                .await?;
+
+            assert_eq!(&composed_text, expected_text);
+        }
+
+        let listed_templates = templating_service
+            .list_template_engines(
+                GLOBAL_TENANT.to_string(),
+                ListTemplateRequest {
+                    offset: 0,
+                    limit: 10,
+                },
+                None,
+            )
+            .await?;
+
+        assert!(listed_templates
+            .iter()
+            .any(|listed| listed.name == "sms" && listed.content_type == ContentType::Text));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compose_render_limits_test() -> anyhow::Result<()> {
+        let repositories = Repositories::memory(InMemoryStore::new());
+        let templating_service = Arc::new(
+            TemplatingService::new(&repositories, LocaleSettings::default())
+                .with_render_limits(RenderLimits::new(Duration::from_secs(5), 64, 2)),
+        ) as DynTemplatingServiceTrait;
+        let slow_service = Arc::new(
+            TemplatingService::new(&repositories, LocaleSettings::default())
+                .with_render_limits(RenderLimits::new(Duration::from_millis(50), usize::MAX, 2)),
+        ) as DynTemplatingServiceTrait;
+
+        let templates = [
+            (
+                "nested",
+                TemplateEngineKind::Handlebars,
+                "{{#*inline \"a\"}}{{> b}}{{/inline}}{{#*inline \"b\"}}b{{/inline}}{{> a}}",
+            ),
+            (
+                "too_nested",
+                TemplateEngineKind::Handlebars,
+                "{{#*inline \"a\"}}{{> b}}{{/inline}}{{#*inline \"b\"}}{{> c}}{{/inline}}\
+                 {{#*inline \"c\"}}c{{/inline}}{{> a}}",
+            ),
+            (
+                "recursive",
+                TemplateEngineKind::Handlebars,
+                "{{#*inline \"a\"}}{{> a}}{{/inline}}{{> a}}",
+            ),
+            (
+                "nested_jinja",
+                TemplateEngineKind::MiniJinja,
+                "{% macro b() %}{% for i in [1] %}b{% endfor %}{% endmacro %}\
+                 {% macro a() %}{% if true %}{{ b() }}{% endif %}{% endmacro %}{{ a() }}",
+            ),
+            (
+                "recursive_jinja",
+                TemplateEngineKind::MiniJinja,
+                "{% macro a() %}{{ a() }}{% endmacro %}{{ a() }}",
+            ),
+            (
+                "recursive_tera",
+                TemplateEngineKind::Tera,
+                "{% macro a() %}{{ self::a() }}{% endmacro %}{{ self::a() }}",
+            ),
+            ("large", TemplateEngineKind::Handlebars, "{{name}}{{name}}"),
+            (
+                "slow",
+                TemplateEngineKind::Liquid,
+                "{% for i in (1..1000000) %}{{ i }}{% endfor %}",
+            ),
+        ];
+        for (name, engine, body) in &templates {
+            templating_service
+                .add_template(
+                    GLOBAL_TENANT.to_string(),
+                    TemplateDefinition {
+                        name: name.to_string(),
+                        description: "template_description".to_string(),
+                        body: body.to_string(),
+                        engine: *engine,
+                        content_type: ContentType::Html,
+                        inputs: vec![TemplateInput {
+                            name: "name".to_string(),
+                            default_value: "x".repeat(40),
+                        }],
+                    },
+                )
+                .await?;
+        }
+
+        async fn compose(service: &DynTemplatingServiceTrait, name: &str) -> ServiceResult<String> {
+            service
+                .compose(
+                    GLOBAL_TENANT.to_string(),
+                    name.to_string(),
+                    vec![],
+                    ComposeOptions::default(),
+                )
+                .await
+        }
+        let is_limit_error = |result: ServiceResult<String>, limit: &str| matches!(result, Err(ServiceError::BadRequest(message)) if message.contains(limit));
+
+        assert_eq!(compose(&templating_service, "nested").await?, "b");
+        assert_eq!(compose(&templating_service, "nested_jinja").await?, "b");
+        assert!(is_limit_error(
+            compose(&templating_service, "too_nested").await,
+            "maximum depth"
+        ));
+        for name in ["recursive", "recursive_jinja", "recursive_tera"] {
+            assert!(is_limit_error(
+                compose(&templating_service, name).await,
+                "maximum depth"
+            ));
+        }
+        assert!(is_limit_error(
+            compose(&templating_service, "large").await,
+            "maximum output size"
+        ));
+        assert!(is_limit_error(
+            compose(&slow_service, "slow").await,
+            "did not finish"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn silent_loop_render_limits_test() {
+        let limits = RenderLimits::new(Duration::from_millis(50), usize::MAX, 2);
+        let templates = [
+            (
+                TemplateEngineKind::Tera,
+                "{% for a in range(end=100000) %}{% for b in range(end=100000) %}\
+                 {% endfor %}{% endfor %}",
+            ),
+            (
+                TemplateEngineKind::Liquid,
+                "{% for a in (1..100000) %}{% for b in (1..100000) %}{% endfor %}{% endfor %}",
+            ),
+            (
+                TemplateEngineKind::MiniJinja,
+                "{% for a in range(100000) %}{% for b in range(100000) %}\
+                 {% endfor %}{% endfor %}",
+            ),
+        ];
+
+        for (engine, body) in templates {
+            let started_at = Instant::now();
+            let rendered = engine.engine().render(
+                body,
+                &BTreeMap::new(),
+                TranslateHelper::new("", HashMap::new()),
+                ContentType::Html,
+                &limits,
+                &RenderDeadline::new(&limits),
+            );
+
+            assert!(
+                matches!(rendered, Err(ServiceError::BadRequest(message)) if message.contains("did not finish")),
+                "{} loop was not stopped",
+                engine
+            );
+            assert!(started_at.elapsed() < Duration::from_secs(5));
+        }
+
+        let too_long_range = TemplateEngineKind::Tera.engine().render(
+            "{% for i in range(end=1000000000) %}{% endfor %}",
+            &BTreeMap::new(),
+            TranslateHelper::new("", HashMap::new()),
+            ContentType::Html,
+            &limits,
+            &RenderDeadline::new(&limits),
+        );
+
+        assert!(too_long_range.is_err());
+
+        let spent_deadline = RenderDeadline::new(&limits);
+        std::thread::sleep(Duration::from_millis(60));
+        let after_deadline = TemplateEngineKind::Handlebars.engine().render(
+            "body",
+            &BTreeMap::new(),
+            TranslateHelper::new("", HashMap::new()),
+            ContentType::Html,
+            &limits,
+            &spent_deadline,
+        );
+
+        assert!(
+            matches!(after_deadline, Err(ServiceError::BadRequest(message)) if message.contains("did not finish"))
+        );
+    }
+
+    #[tokio::test]
+    async fn compose_json_schema_test() -> anyhow::Result<()> {
+        let templating_service = Arc::new(TemplatingService::new(
+            &Repositories::memory(InMemoryStore::new()),
+            LocaleSettings::default(),
+        )) as DynTemplatingServiceTrait;
+        let inputs = vec![TemplateInput {
+            name: "count".to_string(),
+            default_value: "1".to_string(),
+        }];
+
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "push".to_string(),
+                    description: "template_description".to_string(),
+                    body: "{\"badge\": {{count}}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Json,
+                    inputs: inputs.clone(),
+                },
+            )
+            .await?;
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "email".to_string(),
+                    description: "template_description".to_string(),
+                    body: "<p>{{count}}</p>".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs,
+                },
+            )
+            .await?;
+
+        let json_schema = r#"{"type": "object", "required": ["badge"],
+            "properties": {"badge": {"type": "integer", "minimum": 0}}}"#;
+        let invalid_schema = templating_service
+            .set_json_schema(
+                GLOBAL_TENANT.to_string(),
+                "push".to_string(),
+                r#"{"type": "whole number"}"#.to_string(),
+            )
+            .await;
+        let html_schema = templating_service
+            .set_json_schema(
+                GLOBAL_TENANT.to_string(),
+                "email".to_string(),
+                json_schema.to_string(),
+            )
+            .await;
+
+        assert!(matches!(invalid_schema, Err(ServiceError::BadRequest(_))));
+        assert!(matches!(html_schema, Err(ServiceError::BadRequest(_))));
+
+        templating_service
+            .set_json_schema(
+                GLOBAL_TENANT.to_string(),
+                "push".to_string(),
+                json_schema.to_string(),
+            )
+            .await?;
+
+        async fn compose(
+            service: &DynTemplatingServiceTrait,
+            count: &str,
+        ) -> ServiceResult<String> {
+            service
+                .compose(
+                    GLOBAL_TENANT.to_string(),
+                    "push".to_string(),
+                    vec![InputValue {
+                        name: "count".to_string(),
+                        value: count.to_string(),
+                    }],
+                    ComposeOptions::default(),
+                )
+                .await
+        }
+
+        assert_eq!(compose(&templating_service, "3").await?, "{\"badge\": 3}");
+        assert!(matches!(
+            compose(&templating_service, "three").await,
+            Err(ServiceError::BadRequest(message)) if message.contains("not valid JSON")
+        ));
+        assert!(matches!(
+            compose(&templating_service, "-1").await,
+            Err(ServiceError::BadRequest(message)) if message.contains("/badge")
+        ));
+
+        templating_service
+            .remove_json_schema(GLOBAL_TENANT.to_string(), "push".to_string())
+            .await?;
+
+        assert_eq!(compose(&templating_service, "-1").await?, "{\"badge\": -1}");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compose_markdown_test() -> anyhow::Result<()> {
+        let templating_service = Arc::new(TemplatingService::new(
+            &Repositories::memory(InMemoryStore::new()),
+            LocaleSettings::default(),
+        )) as DynTemplatingServiceTrait;
+        let inputs = vec![
+            TemplateInput {
+                name: "name".to_string(),
+                default_value: "*Ada* <b>".to_string(),
+            },
+            TemplateInput {
+                name: "item".to_string(),
+                default_value: "Tea".to_string(),
+            },
+        ];
+
+        templating_service
+            .add_template(GLOBAL_TENANT.to_string(), TemplateDefinition { name: "newsletter".to_string(), description: "template_description".to_string(), body: "# Hi {{name}}\n\nYour [order](https://example.com/orders) is **ready**.\n\n\
+                 | Item | Qty |\n| --- | --- |\n| {{item}} | 2 |\n\n\
+                 - first\n- second\n\n<script>alert(1)</script>"
+                    .to_string(), engine: TemplateEngineKind::Handlebars, content_type: ContentType::Markdown, inputs: inputs.clone() })
+            .await?;
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "layout".to_string(),
+                    description: "template_description".to_string(),
+                    body: "<main>{{content}}</main><footer>{{footer}}</footer>".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: vec![TemplateInput {
+                        name: "footer".to_string(),
+                        default_value: "Tea & cake".to_string(),
+                    }],
+                },
+            )
+            .await?;
+
+        async fn compose(
+            service: &DynTemplatingServiceTrait,
+            options: ComposeOptions,
+        ) -> ServiceResult<String> {
+            service
+                .compose(
+                    GLOBAL_TENANT.to_string(),
+                    "newsletter".to_string(),
+                    vec![],
+                    options,
+                )
+                .await
+        }
+
+        let html = compose(&templating_service, ComposeOptions::default()).await?;
+        let text = compose(
+            &templating_service,
+            ComposeOptions {
+                output: ComposeOutput::Text,
+                ..Default::default()
+            },
+        )
+        .await?;
+        let wrapped = compose(
+            &templating_service,
+            ComposeOptions {
+                layout: Some("layout".to_string()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        assert!(html.starts_with(
+            "<h1>Hi *Ada* &lt;b&gt;</h1>\n<p>Your <a href=\"https://example.com/orders\""
+        ));
+        assert!(html.contains("<tr><td>Tea</td><td>2</td></tr>"));
+        assert!(!html.contains("script"));
+        assert_eq!(
+            text,
+            "Hi *Ada* <b>\n\nYour order (https://example.com/orders) is ready.\n\n\
+             Item | Qty\nTea | 2\n\n- first\n- second"
+        );
+        assert_eq!(
+            wrapped,
+            format!("<main>{}</main><footer>Tea &amp; cake</footer>", html)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compose_layout_rich_html_inputs_test() -> anyhow::Result<()> {
+        let templating_service = Arc::new(TemplatingService::new(
+            &Repositories::memory(InMemoryStore::new()),
+            LocaleSettings::default(),
+        )) as DynTemplatingServiceTrait;
+        let inputs = vec![TemplateInput {
+            name: "bio".to_string(),
+            default_value: "".to_string(),
+        }];
+
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "profile".to_string(),
+                    description: "template_description".to_string(),
+                    body: "# Profile".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Markdown,
+                    inputs: inputs.clone(),
+                },
+            )
+            .await?;
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "layout".to_string(),
+                    description: "template_description".to_string(),
+                    body: "<main>{{content}}</main><aside>{{{bio}}}</aside>".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs,
+                },
+            )
+            .await?;
+        templating_service
+            .set_template_settings(
+                GLOBAL_TENANT.to_string(),
+                "profile".to_string(),
+                TemplateSettings {
+                    rich_html_inputs: vec!["bio".to_string()],
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let wrapped = templating_service
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                "profile".to_string(),
+                vec![InputValue {
+                    name: "bio".to_string(),
+                    value: "<b>Hi</b><script>alert(1)</script>".to_string(),
+                }],
+                ComposeOptions {
+                    layout: Some("layout".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert_eq!(
+            wrapped,
+            "<main><h1>Profile</h1>\n</main><aside><b>Hi</b></aside>"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compose_mjml_test() -> anyhow::Result<()> {
+        let templating_service = Arc::new(TemplatingService::new(
+            &Repositories::memory(InMemoryStore::new()),
+            LocaleSettings::default(),
+        )) as DynTemplatingServiceTrait;
+        let inputs = vec![
+            TemplateInput {
+                name: "name".to_string(),
+                default_value: "Tom & Jerry".to_string(),
+            },
+            TemplateInput {
+                name: "url".to_string(),
+                default_value: "https://example.com/?a=1&b=2".to_string(),
+            },
+        ];
+
+        let templates = [
+            (
+                "handlebars",
+                TemplateEngineKind::Handlebars,
+                "{{name}}",
+                "{{url}}",
+            ),
+            (
+                "jinja",
+                TemplateEngineKind::MiniJinja,
+                "{{ name }}",
+                "{{ url }}",
+            ),
+            ("tera", TemplateEngineKind::Tera, "{{ name }}", "{{ url }}"),
+            (
+                "liquid",
+                TemplateEngineKind::Liquid,
+                "{{ name }}",
+                "{{ url }}",
+            ),
+        ];
+        for (name, engine, name_placeholder, url_placeholder) in &templates {
+            templating_service
+                .add_template(
+                    GLOBAL_TENANT.to_string(),
+                    TemplateDefinition {
+                        name: name.to_string(),
+                        description: "template_description".to_string(),
+                        body: format!(
+                            "<mjml><mj-body><mj-section><mj-column>\
+                         <mj-text>Hi {}</mj-text><mj-button href=\"{}\">Open</mj-button>\
+                         </mj-column></mj-section></mj-body></mjml>",
+                            name_placeholder, url_placeholder
+                        ),
+                        engine: *engine,
+                        content_type: ContentType::Mjml,
+                        inputs: inputs.clone(),
+                    },
+                )
+                .await?;
+        }
+        let invalid_template = templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "invalid".to_string(),
+                    description: "template_description".to_string(),
+                    body: "<mjml><mj-body><mj-section>".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Mjml,
+                    inputs,
+                },
+            )
+            .await;
+
+        assert!(matches!(
+            invalid_template,
+            Err(ServiceError::BadRequest(message)) if message.contains("not valid MJML")
+        ));
+
+        for (name, _, _, _) in &templates {
+            for _ in 0..2 {
+                let composed_text = templating_service
+                    .compose(
+                        GLOBAL_TENANT.to_string(),
+                        name.to_string(),
+                        vec![],
+                        ComposeOptions::default(),
+                    )
+                    .await?;
+
+                assert!(composed_text.starts_with("<!doctype html>"));
+                assert!(composed_text.contains("Hi Tom &amp; Jerry"));
+                assert!(composed_text.contains("href=\"https:"));
+                assert!(!composed_text.contains("&b"));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compose_inline_css_test() -> anyhow::Result<()> {
+        let templating_service = Arc::new(TemplatingService::new(
+            &Repositories::memory(InMemoryStore::new()),
+            LocaleSettings::default(),
+        )) as DynTemplatingServiceTrait;
+        let inputs = vec![TemplateInput {
+            name: "title".to_string(),
+            default_value: "Welcome".to_string(),
+        }];
+
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "email".to_string(),
+                    description: "template_description".to_string(),
+                    body: "<html><head><style>h1 { font-size: 20px; }</style></head>\
+                 <body><h1>{{title}}</h1><p>Hi</p></body></html>"
+                        .to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: inputs.clone(),
+                },
+            )
+            .await?;
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "sms".to_string(),
+                    description: "template_description".to_string(),
+                    body: "{{title}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Text,
+                    inputs,
+                },
+            )
+            .await?;
+        templating_service
+            .set_stylesheet(GLOBAL_TENANT.to_string(), "p { color: red; }".to_string())
+            .await?;
+
+        async fn compose(
+            service: &DynTemplatingServiceTrait,
+            tenant: &str,
+            name: &str,
+        ) -> ServiceResult<String> {
+            service
+                .compose(
+                    tenant.to_string(),
+                    name.to_string(),
+                    vec![],
+                    ComposeOptions {
+                        inline_css: true,
+                        ..Default::default()
+                    },
+                )
+                .await
+        }
+
+        let inlined = compose(&templating_service, GLOBAL_TENANT, "email").await?;
+
+        assert!(inlined.contains("<h1 style=\"font-size: 20px;\">Welcome</h1>"));
+        assert!(inlined.contains("<p style=\"color: red;\">Hi</p>"));
+        assert!(inlined.contains("<style>"));
+
+        templating_service
+            .set_stylesheet("tenant".to_string(), "p { color: blue; }".to_string())
+            .await?;
+        let tenant_inlined = compose(&templating_service, "tenant", "email").await?;
+        let text_inlined = compose(&templating_service, GLOBAL_TENANT, "sms").await;
+
+        assert!(tenant_inlined.contains("<p style=\"color: blue;\">Hi</p>"));
+        assert!(matches!(text_inlined, Err(ServiceError::BadRequest(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compose_rich_html_inputs_test() -> anyhow::Result<()> {
+        let templating_service = Arc::new(TemplatingService::new(
+            &Repositories::memory(InMemoryStore::new()),
+            LocaleSettings::default(),
+        )) as DynTemplatingServiceTrait;
+        let inputs = vec![
+            TemplateInput {
+                name: "bio".to_string(),
+                default_value: "".to_string(),
+            },
+            TemplateInput {
+                name: "name".to_string(),
+                default_value: "".to_string(),
+            },
+        ];
+
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "profile".to_string(),
+                    description: "template_description".to_string(),
+                    body: "<h1>{{name}}</h1><div>{{{bio}}}</div>".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: inputs.clone(),
+                },
+            )
+            .await?;
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "profile_liquid".to_string(),
+                    description: "template_description".to_string(),
+                    body: "<h1>{{ name }}</h1><div>{{ bio }}</div>".to_string(),
+                    engine: TemplateEngineKind::Liquid,
+                    content_type: ContentType::Html,
+                    inputs: inputs.clone(),
+                },
+            )
+            .await?;
+        let liquid_input = templating_service
+            .set_template_settings(
+                GLOBAL_TENANT.to_string(),
+                "profile_liquid".to_string(),
+                TemplateSettings {
+                    rich_html_inputs: vec!["bio".to_string()],
+                    ..Default::default()
+                },
+            )
+            .await;
+        let unknown_input = templating_service
+            .set_template_settings(
+                GLOBAL_TENANT.to_string(),
+                "profile".to_string(),
+                TemplateSettings {
+                    rich_html_inputs: vec!["signature".to_string()],
+                    ..Default::default()
+                },
+            )
+            .await;
+        templating_service
+            .set_template_settings(
+                GLOBAL_TENANT.to_string(),
+                "profile".to_string(),
+                TemplateSettings {
+                    rich_html_inputs: vec!["bio".to_string()],
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let composed = templating_service
+            .compose_with_report(
+                GLOBAL_TENANT.to_string(),
+                "profile".to_string(),
+                vec![
+                    InputValue {
+                        name: "bio".to_string(),
+                        value: "<p onclick=\"steal()\">Hello <b>there</b></p>\
+                                <script>alert(1)</script>"
+                            .to_string(),
+                    },
+                    InputValue {
+                        name: "name".to_string(),
+                        value: "<b>Ann</b>".to_string(),
+                    },
+                ],
+                ComposeOptions::default(),
+            )
+            .await?;
+
+        assert!(matches!(unknown_input, Err(ServiceError::BadRequest(_))));
+        assert!(matches!(liquid_input, Err(ServiceError::BadRequest(_))));
+        assert_eq!(
+            composed.output,
+            "<h1>&lt;b&gt;Ann&lt;/b&gt;</h1><div><p>Hello <b>there</b></p></div>"
+        );
+        assert_eq!(
+            composed.sanitized_inputs,
+            vec![SanitizedInput {
+                name: "bio".to_string(),
+                removed_tags: vec!["script".to_string()],
+                removed_attributes: vec!["p[onclick]".to_string()],
+            }]
+        );
+        assert_eq!(
+            templating_service
+                .get_template_settings(GLOBAL_TENANT.to_string(), "profile".to_string())
+                .await?
+                .rich_html_inputs,
+            vec!["bio".to_string()]
+        );
+
+        let without_rich_input = templating_service
+            .update_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "profile".to_string(),
+                    description: "template_description".to_string(),
+                    body: "<h1>{{name}}</h1>".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: vec![TemplateInput {
+                        name: "name".to_string(),
+                        default_value: "".to_string(),
+                    }],
+                },
+                None,
+            )
+            .await;
+        let as_text = templating_service
+            .update_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "profile".to_string(),
+                    description: "template_description".to_string(),
+                    body: "{{name}}: {{bio}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Text,
+                    inputs,
+                },
+                None,
+            )
+            .await;
+
+        assert!(matches!(
+            without_rich_input,
+            Err(ServiceError::BadRequest(_))
+        ));
+        assert!(matches!(as_text, Err(ServiceError::BadRequest(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compose_link_rewriting_test() -> anyhow::Result<()> {
+        let click_tracking = ClickTracking::new("https://click.example.com/r", "secret")?;
+        let templating_service = Arc::new(
+            TemplatingService::new(
+                &Repositories::memory(InMemoryStore::new()),
+                LocaleSettings::default(),
+            )
+            .with_click_tracking(click_tracking.clone()),
+        ) as DynTemplatingServiceTrait;
+        let inputs = vec![
+            TemplateInput {
+                name: "shop_url".to_string(),
+                default_value: "https://example.com/shop?ref=hero".to_string(),
+            },
+            TemplateInput {
+                name: "verification_token".to_string(),
+                default_value: "abc123".to_string(),
+            },
+        ];
+
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "welcome".to_string(),
+                    description: "template_description".to_string(),
+                    body: "<p><a href=\"{{shop_url}}\">Shop</a>\
+                 <a data-link-tag=\"footer\" href=\"https://example.com/help?utm_source=help\">\
+                 Help</a><a href=\"https://example.com/verify?token={{verification_token}}\" \
+                 data-no-track>Verify</a>\
+                 <a href=\"mailto:hi@example.com\">Mail</a></p>"
+                        .to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs,
+                },
+            )
+            .await?;
+        let not_utm = templating_service
+            .set_template_settings(
+                GLOBAL_TENANT.to_string(),
+                "welcome".to_string(),
+                TemplateSettings {
+                    links: LinkRules {
+                        utm_parameters: [("source".to_string(), "newsletter".to_string())].into(),
+                        track_clicks: false,
+                    },
+                    ..Default::default()
+                },
+            )
+            .await;
+        templating_service
+            .set_template_settings(
+                GLOBAL_TENANT.to_string(),
+                "welcome".to_string(),
+                TemplateSettings {
+                    links: LinkRules {
+                        utm_parameters: [
+                            ("utm_source".to_string(), "newsletter".to_string()),
+                            ("utm_medium".to_string(), "email".to_string()),
+                        ]
+                        .into(),
+                        track_clicks: true,
+                    },
+                    link_tags: [(
+                        "footer".to_string(),
+                        LinkRules {
+                            utm_parameters: [("utm_medium".to_string(), "footer".to_string())]
+                                .into(),
+                            track_clicks: false,
+                        },
+                    )]
+                    .into(),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let composed = templating_service
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                "welcome".to_string(),
+                vec![],
+                ComposeOptions::default(),
+            )
+            .await?;
+        let tracked_url = click_tracking.wrap(
+            "welcome",
+            "https://example.com/shop?ref=hero&utm_medium=email&utm_source=newsletter",
+        );
+
+        assert!(matches!(not_utm, Err(ServiceError::BadRequest(_))));
+        assert_eq!(
+            composed,
+            format!(
+                "<p><a href=\"{}\">Shop</a>\
+                 <a href=\"https://example.com/help?utm_source=help&amp;utm_medium=footer\">\
+                 Help</a><a href=\"https://example.com/verify?token=abc123\">Verify</a>\
+                 <a href=\"mailto:hi@example.com\">Mail</a></p>",
+                tracked_url.replace('&', "&amp;")
+            )
+        );
+        assert!(
+            tracked_url.starts_with("https://click.example.com/r?url=https%3A%2F%2Fexample.com")
+        );
+        assert!(click_tracking.verify(
+            "welcome",
+            "https://example.com/shop?ref=hero&utm_medium=email&utm_source=newsletter",
+            &click_tracking.sign(
+                "welcome",
+                "https://example.com/shop?ref=hero&utm_medium=email&utm_source=newsletter"
+            )
+        ));
+        assert!(!click_tracking.verify(
+            "welcome",
+            "https://attacker.example.com",
+            &click_tracking.sign(
+                "welcome",
+                "https://example.com/shop?ref=hero&utm_medium=email&utm_source=newsletter"
+            )
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn link_rewriting_markup_test() {
+        let rewriter = LinkRewriter {
+            template: "welcome".to_string(),
+            rules: LinkRules {
+                utm_parameters: [("utm_source".to_string(), "newsletter".to_string())].into(),
+                track_clicks: false,
+            },
+            tag_rules: Default::default(),
+            click_tracking: None,
+            unsubscribe_url: None,
+        };
+
+        let fragment = rewriter.rewrite(
+            "<script>var link = '<a href=\"https://example.com/a\">';</script>\
+             <style>a[href^=\"<a href='https://example.com/b'>\"] { color: red; }</style>\
+             <textarea><a href=\"https://example.com/c\"></textarea>\
+             <!-- <a href=\"https://example.com/d\"> -->\
+             <svg><![CDATA[<a href=\"https://example.com/e\">]]></svg>\
+             <a href=\"https://example.com/f\">F</a>",
+        );
+        let document = rewriter.rewrite(
+            "<!DOCTYPE html><html><head><title><a href=\"https://example.com/g\"></title>\
+             </head><body><a href=\"https://example.com/h\">H</a></body></html>",
+        );
+
+        assert_eq!(fragment.matches("utm_source").count(), 1);
+        assert!(fragment.contains("<a href=\"https://example.com/f?utm_source=newsletter\">F</a>"));
+        assert!(fragment.contains("var link = '<a href=\"https://example.com/a\">';"));
+        assert!(fragment.contains("&lt;a href=\"https://example.com/c\"&gt;"));
+        assert!(fragment.contains("<!-- <a href=\"https://example.com/d\"> -->"));
+        assert!(document.starts_with("<!DOCTYPE html><html><head><title>"));
+        assert_eq!(document.matches("utm_source").count(), 1);
+        assert!(document.contains("https://example.com/h?utm_source=newsletter"));
+
+        let markdown = markdown_to_html(
+            "[Shop](https://example.com/shop) \
+             <a href=\"https://example.com/verify\" data-no-track>Verify</a> \
+             <a href=\"https://example.com/help\" data-link-tag=\"footer\">Help</a>",
+        );
+        let rewritten_markdown = LinkRewriter {
+            tag_rules: [(
+                "footer".to_string(),
+                LinkRules {
+                    utm_parameters: [("utm_source".to_string(), "footer".to_string())].into(),
+                    track_clicks: false,
+                },
+            )]
+            .into(),
+            ..rewriter
+        }
+        .rewrite(&markdown);
+
+        assert!(rewritten_markdown.contains("https://example.com/shop?utm_source=newsletter"));
+        assert!(rewritten_markdown.contains("<a href=\"https://example.com/verify\" rel="));
+        assert!(rewritten_markdown.contains("https://example.com/help?utm_source=footer"));
+        assert!(!rewritten_markdown.contains("data-"));
+    }
+
+    #[tokio::test]
+    async fn compose_marketing_test() -> anyhow::Result<()> {
+        let templating_service = Arc::new(TemplatingService::new(
+            &Repositories::memory(InMemoryStore::new()),
+            LocaleSettings::default(),
+        )) as DynTemplatingServiceTrait;
+        let inputs = vec![
+            TemplateInput {
+                name: "name".to_string(),
+                default_value: "reader".to_string(),
+            },
+            TemplateInput {
+                name: "unsubscribe_url".to_string(),
+                default_value: "".to_string(),
+            },
+        ];
+        let marketing = TemplateSettings {
+            marketing: true,
+            ..Default::default()
+        };
+
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "newsletter".to_string(),
+                    description: "template_description".to_string(),
+                    body: "# Hi {{name}}\n\nThe news is [here](https://example.com/news)."
+                        .to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Markdown,
+                    inputs: inputs.clone(),
+                },
+            )
+            .await?;
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "promotion".to_string(),
+                    description: "template_description".to_string(),
+                    body: "<p>Sale!</p><a href=\"{{unsubscribe_url}}\">Leave</a>".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: inputs.clone(),
+                },
+            )
+            .await?;
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "mention".to_string(),
+                    description: "template_description".to_string(),
+                    body: "<p>Sale!</p>{{!-- no unsubscribe_url here --}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs,
+                },
+            )
+            .await?;
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "receipt".to_string(),
+                    description: "template_description".to_string(),
+                    body: "Thanks {{name}}".to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Text,
+                    inputs: vec![TemplateInput {
+                        name: "name".to_string(),
+                        default_value: "".to_string(),
+                    }],
+                },
+            )
+            .await?;
+        for name in ["newsletter", "promotion", "mention"] {
+            templating_service
+                .set_template_settings(
+                    GLOBAL_TENANT.to_string(),
+                    name.to_string(),
+                    marketing.clone(),
+                )
+                .await?;
+        }
+        let without_input = templating_service
+            .set_template_settings(GLOBAL_TENANT.to_string(), "receipt".to_string(), marketing)
+            .await;
+
+        async fn compose(
+            service: &DynTemplatingServiceTrait,
+            name: &str,
+            unsubscribe_url: &str,
+            output: ComposeOutput,
+        ) -> ServiceResult<String> {
+            service
+                .compose(
+                    GLOBAL_TENANT.to_string(),
+                    name.to_string(),
+                    vec![InputValue {
+                        name: "unsubscribe_url".to_string(),
+                        value: unsubscribe_url.to_string(),
+                    }],
+                    ComposeOptions {
+                        output,
+                        ..Default::default()
+                    },
+                )
+                .await
+        }
+
+        let unsubscribe_url = "https://example.com/unsubscribe?u=1";
+        let missing_url = compose(&templating_service, "newsletter", "", ComposeOutput::Body).await;
+        let html = compose(
+            &templating_service,
+            "newsletter",
+            unsubscribe_url,
+            ComposeOutput::Body,
+        )
+        .await?;
+        let text = compose(
+            &templating_service,
+            "newsletter",
+            unsubscribe_url,
+            ComposeOutput::Text,
+        )
+        .await?;
+        let mime = compose(
+            &templating_service,
+            "newsletter",
+            unsubscribe_url,
+            ComposeOutput::Mime,
+        )
+        .await?;
+        let placed = compose(
+            &templating_service,
+            "promotion",
+            unsubscribe_url,
+            ComposeOutput::Body,
+        )
+        .await?;
+
+        assert!(matches!(without_input, Err(ServiceError::BadRequest(_))));
+        assert!(matches!(missing_url, Err(ServiceError::BadRequest(_))));
+        assert!(html
+            .ends_with("<a href=\"https://example.com/unsubscribe?u&#x3D;1\">Unsubscribe</a></p>"));
+        assert!(text.ends_with("\n\nUnsubscribe: https://example.com/unsubscribe?u=1"));
+        assert!(mime.starts_with(
+            "MIME-Version: 1.0\r\n\
+             List-Unsubscribe: <https://example.com/unsubscribe?u=1>\r\n\
+             List-Unsubscribe-Post: List-Unsubscribe=One-Click\r\n\
+             Content-Type: multipart/alternative;"
+        ));
+        let parts = mime
+            .split("Content-Transfer-Encoding: base64\r\n\r\n")
+            .skip(1)
+            .map(|part| {
+                let encoded = part
+                    .split("--")
+                    .next()
+                    .unwrap_or_default()
+                    .replace("\r\n", "");
+                String::from_utf8(STANDARD.decode(encoded)?).map_err(anyhow::Error::from)
+            })
+            .collect::<anyhow::Result<Vec<String>>>()?;
+        assert_eq!(parts, vec![text, html]);
+        assert_eq!(
+            placed,
+            "<p>Sale!</p><a href=\"https://example.com/unsubscribe?u&#x3D;1\">Leave</a>"
+        );
+
+        let mentioned = compose(
+            &templating_service,
+            "mention",
+            unsubscribe_url,
+            ComposeOutput::Body,
+        )
+        .await?;
+        let reported = templating_service
+            .compose_with_report(
+                GLOBAL_TENANT.to_string(),
+                "promotion".to_string(),
+                vec![InputValue {
+                    name: "unsubscribe_url".to_string(),
+                    value: unsubscribe_url.to_string(),
+                }],
+                ComposeOptions::default(),
+            )
+            .await?;
+
+        assert!(mentioned.ends_with(">Unsubscribe</a></p>"));
+        assert_eq!(
+            reported.unsubscribe_headers,
+            vec![
+                (
+                    "List-Unsubscribe",
+                    "<https://example.com/unsubscribe?u=1>".to_string()
+                ),
+                (
+                    "List-Unsubscribe-Post",
+                    "List-Unsubscribe=One-Click".to_string()
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compose_tracked_unsubscribe_test() -> anyhow::Result<()> {
+        let click_tracking = ClickTracking::new("https://click.example.com/r", "secret")?;
+        let templating_service = Arc::new(
+            TemplatingService::new(
+                &Repositories::memory(InMemoryStore::new()),
+                LocaleSettings::default(),
+            )
+            .with_click_tracking(click_tracking.clone()),
+        ) as DynTemplatingServiceTrait;
+
+        templating_service
+            .add_template(
+                GLOBAL_TENANT.to_string(),
+                TemplateDefinition {
+                    name: "promotion".to_string(),
+                    description: "template_description".to_string(),
+                    body: "<a href=\"https://example.com/shop\">Shop</a>\
+                           <a href=\"{{unsubscribe_url}}\">Leave</a>"
+                        .to_string(),
+                    engine: TemplateEngineKind::Handlebars,
+                    content_type: ContentType::Html,
+                    inputs: vec![TemplateInput {
+                        name: "unsubscribe_url".to_string(),
+                        default_value: "".to_string(),
+                    }],
+                },
+            )
+            .await?;
+        templating_service
+            .set_template_settings(
+                GLOBAL_TENANT.to_string(),
+                "promotion".to_string(),
+                TemplateSettings {
+                    marketing: true,
+                    links: LinkRules {
+                        utm_parameters: Default::default(),
+                        track_clicks: true,
+                    },
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let unsubscribe_url = "https://example.com/unsubscribe?u=1";
+        let composed = templating_service
+            .compose(
+                GLOBAL_TENANT.to_string(),
+                "promotion".to_string(),
+                vec![InputValue {
+                    name: "unsubscribe_url".to_string(),
+                    value: unsubscribe_url.to_string(),
+                }],
+                ComposeOptions::default(),
+            )
+            .await?;
+
+        assert_eq!(
+            composed,
+            format!(
+                "<a href=\"{}\">Shop</a><a href=\"{}\">Leave</a>",
+                click_tracking
+                    .wrap("promotion", "https://example.com/shop")
+                    .replace('&', "&amp;"),
+                unsubscribe_url
+            )
+        );
+
+        Ok(())
+    }
 }
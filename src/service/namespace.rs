@@ -0,0 +1,83 @@
+use std::fmt;
+
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+
+pub const NAMESPACE_SEPARATOR: char = '/';
+
+/// A template name split into its namespace and the name within that namespace,
+/// e.g. `auth/registration` is the template `registration` in namespace `auth`.
+/// Templates without a separator live in the root namespace (`""`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplatePath {
+    pub namespace: String,
+    pub name: String,
+}
+
+impl TemplatePath {
+    pub fn parse(path: &str) -> ServiceResult<Self> {
+        let (namespace, name) = match path.rsplit_once(NAMESPACE_SEPARATOR) {
+            Some((namespace, name)) => (parse_namespace(namespace)?, name),
+            None => (String::new(), path),
+        };
+
+        if name.trim().is_empty() {
+            return Err(ServiceError::BadRequest(format!(
+                "template name {:?} is invalid",
+                path
+            )));
+        }
+
+        Ok(Self {
+            namespace,
+            name: name.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for TemplatePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&join_path(&self.namespace, &self.name))
+    }
+}
+
+/// Validates a namespace such as `auth` or `billing/invoices`, every segment must be
+/// non-empty. The empty string is the root namespace.
+pub fn parse_namespace(namespace: &str) -> ServiceResult<String> {
+    if namespace.is_empty() {
+        return Ok(String::new());
+    }
+
+    if namespace
+        .split(NAMESPACE_SEPARATOR)
+        .any(|segment| segment.trim().is_empty())
+    {
+        return Err(ServiceError::BadRequest(format!(
+            "namespace {:?} is invalid",
+            namespace
+        )));
+    }
+
+    Ok(namespace.to_string())
+}
+
+/// Whether a template in `template_namespace` is listed under `namespace`, which covers
+/// the namespace itself and every namespace nested below it. No namespace lists all.
+pub fn is_within_namespace(template_namespace: &str, namespace: Option<&str>) -> bool {
+    match namespace {
+        Some(namespace) => {
+            template_namespace == namespace
+                || template_namespace
+                    .strip_prefix(namespace)
+                    .is_some_and(|rest| rest.starts_with(NAMESPACE_SEPARATOR))
+        }
+        None => true,
+    }
+}
+
+pub fn join_path(namespace: &str, name: &str) -> String {
+    if namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, name)
+    }
+}
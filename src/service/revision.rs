@@ -0,0 +1,37 @@
+use madtofan_microservice_common::{
+    errors::{ServiceError, ServiceResult},
+    templating::TemplateResponse,
+};
+use sqlx::types::time::OffsetDateTime;
+
+use crate::repository::template::TemplateInputsEntity;
+
+/// A template together with the revision it was read at. Every write to a template
+/// bumps its `updated_at`, the revision is that timestamp in unix microseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateRevision {
+    pub template: TemplateResponse,
+    pub revision: String,
+}
+
+impl From<TemplateInputsEntity> for TemplateRevision {
+    fn from(template_entity: TemplateInputsEntity) -> Self {
+        Self {
+            revision: format_revision(template_entity.updated_at),
+            template: template_entity.into_template_response(),
+        }
+    }
+}
+
+pub fn format_revision(updated_at: OffsetDateTime) -> String {
+    (updated_at.unix_timestamp_nanos() / 1_000).to_string()
+}
+
+pub fn parse_revision(revision: &str) -> ServiceResult<OffsetDateTime> {
+    revision
+        .trim()
+        .parse::<i128>()
+        .ok()
+        .and_then(|micros| OffsetDateTime::from_unix_timestamp_nanos(micros * 1_000).ok())
+        .ok_or_else(|| ServiceError::BadRequest(format!("revision {:?} is invalid", revision)))
+}
@@ -0,0 +1,111 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use html5ever::{
+    tendril::StrTendril,
+    tokenizer::{
+        states::RawKind, BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer,
+        TokenizerOpts,
+    },
+};
+use serde::Serialize;
+
+/// What cleaning the value of a rich HTML input took out of it.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SanitizedInput {
+    pub name: String,
+    /// Tags that are not on the allowlist, their content is kept unless it is a script
+    /// or a style.
+    pub removed_tags: Vec<String>,
+    /// Attributes that are not on the allowlist of their tag or hold an unsafe URL,
+    /// written as `tag[attribute]`.
+    pub removed_attributes: Vec<String>,
+}
+
+/// Cleans the values of the named inputs down to a safe allowlist of tags and
+/// attributes, reporting the inputs anything was removed from.
+pub fn sanitize_rich_inputs(
+    data: &mut BTreeMap<String, String>,
+    rich_html_inputs: &[String],
+) -> Vec<SanitizedInput> {
+    let mut sanitized_inputs = vec![];
+    for name in rich_html_inputs {
+        let value = match data.get_mut(name) {
+            Some(value) => value,
+            None => continue,
+        };
+
+        let cleaned = ammonia::clean(value);
+        let before = collect_markup(value);
+        let after = collect_markup(&cleaned);
+        *value = cleaned;
+
+        let removed_tags = removed(&before.tags, &after.tags);
+        let removed_attributes = removed(&before.attributes, &after.attributes);
+        if !removed_tags.is_empty() || !removed_attributes.is_empty() {
+            sanitized_inputs.push(SanitizedInput {
+                name: name.clone(),
+                removed_tags,
+                removed_attributes,
+            });
+        }
+    }
+
+    sanitized_inputs
+}
+
+/// The entries of `before` that occur fewer times in `after`.
+fn removed(before: &BTreeMap<String, usize>, after: &BTreeMap<String, usize>) -> Vec<String> {
+    before
+        .iter()
+        .filter(|(entry, count)| after.get(*entry).copied().unwrap_or_default() < **count)
+        .map(|(entry, _)| entry.clone())
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect()
+}
+
+/// How many times each start tag and each `tag[attribute]` occur in a fragment.
+#[derive(Default)]
+struct Markup {
+    tags: BTreeMap<String, usize>,
+    attributes: BTreeMap<String, usize>,
+}
+
+impl TokenSink for Markup {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        let tag = match token {
+            Token::TagToken(tag) if tag.kind == TagKind::StartTag => tag,
+            _ => return TokenSinkResult::Continue,
+        };
+
+        let name = tag.name.to_string();
+        *self.tags.entry(name.clone()).or_default() += 1;
+        for attribute in &tag.attrs {
+            *self
+                .attributes
+                .entry(format!("{}[{}]", name, attribute.name.local))
+                .or_default() += 1;
+        }
+
+        match name.as_str() {
+            "script" => TokenSinkResult::RawData(RawKind::ScriptData),
+            "style" | "xmp" | "iframe" | "noembed" | "noframes" => {
+                TokenSinkResult::RawData(RawKind::Rawtext)
+            }
+            "textarea" | "title" => TokenSinkResult::RawData(RawKind::Rcdata),
+            _ => TokenSinkResult::Continue,
+        }
+    }
+}
+
+fn collect_markup(fragment: &str) -> Markup {
+    let mut tokenizer = Tokenizer::new(Markup::default(), TokenizerOpts::default());
+    let mut queue = BufferQueue::new();
+    queue.push_back(StrTendril::from_slice(fragment));
+    let _ = tokenizer.feed(&mut queue);
+    tokenizer.end();
+
+    tokenizer.sink
+}
@@ -0,0 +1,134 @@
+use std::{collections::BTreeMap, iter};
+
+use madtofan_microservice_common::errors::{ServiceError, ServiceResult};
+use serde::{Deserialize, Serialize};
+
+use crate::repository::template::TemplateInputsEntity;
+
+use super::{
+    content::{parse_content_type, ContentType},
+    engine::{parse_engine, TemplateEngineKind},
+    links::{is_false, LinkRules},
+    marketing::UNSUBSCRIBE_URL_INPUT,
+};
+
+/// How composing treats a template beyond its definition, stored as a JSON document
+/// next to the template. Templates without settings compose with the defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplateSettings {
+    /// Inputs whose values are HTML markup, cleaned down to an allowlist of tags and
+    /// attributes before they are rendered. Templates place them unescaped, with
+    /// `{{{name}}}` in Handlebars or the `safe` filter in MiniJinja and Tera. Liquid
+    /// cannot place a value unescaped, so Liquid templates have no rich inputs.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rich_html_inputs: Vec<String>,
+    /// How the links of the HTML output are rewritten.
+    #[serde(skip_serializing_if = "LinkRules::is_empty")]
+    pub links: LinkRules,
+    /// Rules for the links whose `data-link-tag` names the tag, in place of `links`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub link_tags: BTreeMap<String, LinkRules>,
+    /// Marketing templates are composed with the recipient's unsubscribe URL in the
+    /// `unsubscribe_url` input, their body gets an unsubscribe footer unless it places
+    /// the URL itself and their MIME output carries `List-Unsubscribe` headers, which
+    /// are reported to the caller with any other output.
+    #[serde(skip_serializing_if = "is_false")]
+    pub marketing: bool,
+    /// An HTML template used as the unsubscribe footer of a marketing template in place
+    /// of the built-in one, rendered with the values of the template.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unsubscribe_footer: Option<String>,
+}
+
+impl TemplateSettings {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn rewrites_links(&self) -> bool {
+        !self.links.is_empty() || !self.link_tags.is_empty()
+    }
+
+    pub fn tracks_clicks(&self) -> bool {
+        iter::once(&self.links)
+            .chain(self.link_tags.values())
+            .any(|rules| rules.track_clicks)
+    }
+
+    /// Checks the settings apply to the template: rich HTML inputs have to be inputs
+    /// of an HTML template not written in Liquid, only the links of HTML templates are
+    /// rewritten and marketing templates have to take an unsubscribe URL.
+    pub fn validate(&self, template: &TemplateInputsEntity) -> ServiceResult<()> {
+        for rules in iter::once(&self.links).chain(self.link_tags.values()) {
+            rules.validate()?;
+        }
+        if self.link_tags.keys().any(|tag| tag.trim().is_empty()) {
+            return Err(ServiceError::BadRequest(
+                "link tags cannot be empty".to_string(),
+            ));
+        }
+
+        let renders_html = matches!(
+            parse_content_type(&template.content_type)?,
+            ContentType::Html | ContentType::Mjml | ContentType::Markdown
+        );
+        if !renders_html && !self.rich_html_inputs.is_empty() {
+            return Err(ServiceError::BadRequest(
+                "rich HTML inputs only apply to HTML templates".to_string(),
+            ));
+        }
+        if parse_engine(&template.engine)? == TemplateEngineKind::Liquid
+            && !self.rich_html_inputs.is_empty()
+        {
+            return Err(ServiceError::BadRequest(
+                "Liquid templates cannot have rich HTML inputs".to_string(),
+            ));
+        }
+        if !renders_html && self.rewrites_links() {
+            return Err(ServiceError::BadRequest(
+                "links can only be rewritten in HTML templates".to_string(),
+            ));
+        }
+
+        for name in &self.rich_html_inputs {
+            if !template.inputs.iter().any(|input| &input.name == name) {
+                return Err(ServiceError::BadRequest(format!(
+                    "rich HTML input {:?} is not an input of the template",
+                    name
+                )));
+            }
+        }
+
+        if self.unsubscribe_footer.is_some() && !self.marketing {
+            return Err(ServiceError::BadRequest(
+                "unsubscribe footers only apply to marketing templates".to_string(),
+            ));
+        }
+        if self.marketing {
+            if parse_content_type(&template.content_type)? == ContentType::Json {
+                return Err(ServiceError::BadRequest(
+                    "JSON templates cannot be marketing templates".to_string(),
+                ));
+            }
+            if !template
+                .inputs
+                .iter()
+                .any(|input| input.name == UNSUBSCRIBE_URL_INPUT)
+            {
+                return Err(ServiceError::BadRequest(format!(
+                    "marketing templates have to take an {:?} input",
+                    UNSUBSCRIBE_URL_INPUT
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn parse_settings(settings: &str) -> ServiceResult<TemplateSettings> {
+    serde_json::from_str(settings).map_err(|err| {
+        ServiceError::BadRequest(format!("template settings are not valid: {}", err))
+    })
+}
@@ -1,7 +1,9 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
-use handlebars::Handlebars;
 use madtofan_microservice_common::{
     errors::{ServiceError, ServiceResult},
     templating::{
@@ -11,23 +13,384 @@ use madtofan_microservice_common::{
 };
 use tracing::{error, info};
 
-use crate::repository::{input::DynInputRepositoryTrait, template::DynTemplateRepositoryTrait};
+use crate::{
+    repository::{
+        input::{DynInputRepositoryTrait, InputEntity},
+        message::DynMessageRepositoryTrait,
+        stylesheet::DynStylesheetRepositoryTrait,
+        template::{
+            DynTemplateRepositoryTrait, TemplateFields, TemplateInputsEntity, GLOBAL_TENANT,
+        },
+        template_override::{DynTemplateOverrideRepositoryTrait, TemplateOverrideInputsEntity},
+        template_schema::DynTemplateSchemaRepositoryTrait,
+        template_settings::DynTemplateSettingsRepositoryTrait,
+        template_variant::DynTemplateVariantRepositoryTrait,
+    },
+    storage::Repositories,
+};
+
+use super::{
+    bundle::{
+        BundleTemplate, ConflictStrategy, ImportAction, ImportChange, ImportOptions, ImportReport,
+        TemplateBundle,
+    },
+    catalog::{parse_message, resolve_messages, CatalogMessage, CatalogMessages, TranslateHelper},
+    compose::{
+        merge_inputs, render, renders_html, select_body, ComposeOutput, ComposePipeline, Layout,
+    },
+    content::{check_json_output, compile_json_schema, parse_content_type, ContentType},
+    css::{validate_stylesheet, Stylesheet},
+    engine::{parse_engine, TemplateEngineKind, TemplateEngineListing},
+    limits::{render_blocking, RenderDeadline, RenderLimits},
+    links::{ClickTracking, LinkRewriter},
+    locale::{parse_locale, LocaleSettings, TemplateLocales},
+    marketing::{Unsubscribe, UnsubscribeFooter},
+    mjml::{compile_mjml, MjmlCache},
+    namespace::{parse_namespace, TemplatePath},
+    revision::{format_revision, parse_revision, TemplateRevision},
+    sanitize::{sanitize_rich_inputs, SanitizedInput},
+    settings::{parse_settings, TemplateSettings},
+};
 
 #[async_trait]
 pub trait TemplatingServiceTrait {
     async fn add_template(
         &self,
+        tenant: String,
+        definition: TemplateDefinition,
+    ) -> ServiceResult<TemplateRevision>;
+    async fn get_template(&self, tenant: String, name: String) -> ServiceResult<TemplateRevision>;
+    /// Replaces the description, body, engine, content type and inputs of the template.
+    /// When an `expected_revision` is given the update is rejected with a conflict if
+    /// the template has been written since that revision.
+    async fn update_template(
+        &self,
+        tenant: String,
+        definition: TemplateDefinition,
+        expected_revision: Option<String>,
+    ) -> ServiceResult<TemplateRevision>;
+    /// Creates the template or updates it to the given definition, reporting whether
+    /// anything had to change so provisioning can safely be run again.
+    async fn ensure_template(
+        &self,
+        tenant: String,
+        definition: TemplateDefinition,
+    ) -> ServiceResult<EnsuredTemplate>;
+    async fn remove_template(
+        &self,
+        tenant: String,
         name: String,
-        description: String,
-        body: String,
+        expected_revision: Option<String>,
+    ) -> ServiceResult<TemplateRevision>;
+    async fn list_templates(
+        &self,
+        tenant: String,
+        request: ListTemplateRequest,
+        namespace: Option<String>,
+    ) -> ServiceResult<ListedTemplates>;
+    async fn list_trashed_templates(
+        &self,
+        tenant: String,
+        request: ListTemplateRequest,
+    ) -> ServiceResult<ListedTemplates>;
+    async fn restore_template(
+        &self,
+        tenant: String,
+        name: String,
+    ) -> ServiceResult<TemplateRevision>;
+    async fn move_template(
+        &self,
+        tenant: String,
+        name: String,
+        namespace: String,
+    ) -> ServiceResult<TemplateRevision>;
+    /// Renames the template in place, keeping its id, inputs, overrides and variants.
+    async fn rename_template(
+        &self,
+        tenant: String,
+        name: String,
+        new_name: String,
+    ) -> ServiceResult<TemplateRevision>;
+    /// Copies the template and its inputs under a new name in the tenant, recording
+    /// the template it was cloned from. Global templates can be cloned into a tenant.
+    async fn clone_template(
+        &self,
+        tenant: String,
+        name: String,
+        new_name: String,
+    ) -> ServiceResult<TemplateRevision>;
+    async fn set_override(
+        &self,
+        tenant: String,
+        name: String,
+        scope: String,
+        body: Option<String>,
         inputs: Vec<TemplateInput>,
     ) -> ServiceResult<TemplateResponse>;
-    async fn remove_template(&self, name: String) -> ServiceResult<TemplateResponse>;
-    async fn list_templates(
+    async fn remove_override(
+        &self,
+        tenant: String,
+        name: String,
+        scope: String,
+    ) -> ServiceResult<TemplateResponse>;
+    async fn set_variant(
+        &self,
+        tenant: String,
+        name: String,
+        locale: String,
+        body: String,
+    ) -> ServiceResult<TemplateResponse>;
+    async fn remove_variant(
+        &self,
+        tenant: String,
+        name: String,
+        locale: String,
+    ) -> ServiceResult<TemplateResponse>;
+    /// Attaches the JSON Schema the output of a JSON template is validated against when
+    /// it is composed, replacing the schema the template had.
+    async fn set_json_schema(
+        &self,
+        tenant: String,
+        name: String,
+        json_schema: String,
+    ) -> ServiceResult<TemplateResponse>;
+    async fn remove_json_schema(
+        &self,
+        tenant: String,
+        name: String,
+    ) -> ServiceResult<TemplateResponse>;
+    async fn get_template_settings(
+        &self,
+        tenant: String,
+        name: String,
+    ) -> ServiceResult<TemplateSettings>;
+    /// Replaces the settings of the template, saving the default settings resets them.
+    async fn set_template_settings(
+        &self,
+        tenant: String,
+        name: String,
+        settings: TemplateSettings,
+    ) -> ServiceResult<TemplateResponse>;
+    /// Bundles the named templates of the tenant, or all of its templates when no names
+    /// are given.
+    async fn export_templates(
         &self,
+        tenant: String,
+        names: Vec<String>,
+    ) -> ServiceResult<TemplateBundle>;
+    /// Imports the bundled templates into the tenant, resolving taken names with the
+    /// conflict strategy. Templates identical to the existing one are left unchanged.
+    async fn import_templates(
+        &self,
+        tenant: String,
+        bundle: TemplateBundle,
+        options: ImportOptions,
+    ) -> ServiceResult<ImportReport>;
+    async fn list_template_locales(
+        &self,
+        tenant: String,
+        request: ListTemplateRequest,
+        namespace: Option<String>,
+    ) -> ServiceResult<Vec<TemplateLocales>>;
+    async fn list_template_engines(
+        &self,
+        tenant: String,
         request: ListTemplateRequest,
-    ) -> ServiceResult<ListTemplateResponse>;
-    async fn compose(&self, name: String, inputs: Vec<InputValue>) -> ServiceResult<String>;
+        namespace: Option<String>,
+    ) -> ServiceResult<Vec<TemplateEngineListing>>;
+    async fn list_messages(
+        &self,
+        tenant: String,
+        offset: i64,
+        limit: i64,
+    ) -> ServiceResult<CatalogMessages>;
+    async fn set_message(
+        &self,
+        tenant: String,
+        key: String,
+        locale: String,
+        value: String,
+    ) -> ServiceResult<CatalogMessage>;
+    async fn remove_message(
+        &self,
+        tenant: String,
+        key: String,
+        locale: String,
+    ) -> ServiceResult<CatalogMessage>;
+    async fn get_stylesheet(&self, tenant: String) -> ServiceResult<Stylesheet>;
+    /// Saves the stylesheet composing with `inline_css` inlines into the tenant's HTML
+    /// output, the global tenant's stylesheet applies to tenants without their own.
+    async fn set_stylesheet(&self, tenant: String, css: String) -> ServiceResult<Stylesheet>;
+    async fn remove_stylesheet(&self, tenant: String) -> ServiceResult<Stylesheet>;
+    /// Input values are merged in increasing precedence: the base template defaults,
+    /// then the defaults of the `scope` override (if the template has one), then the
+    /// values given in the request.
+    ///
+    /// The body is the variant of the requested locale, falling back through its parent
+    /// locales, then the body of the `scope` override, then the base template body.
+    ///
+    /// Rendering runs on the blocking pool and fails once it exceeds the service's
+    /// render limits. The output of a JSON template has to parse as JSON and satisfy
+    /// the template's JSON Schema if it has one.
+    ///
+    /// Markdown templates are rendered first and then converted to sanitised HTML,
+    /// placed in the `layout` template if one is given, or to their plain-text
+    /// alternative. MJML templates are compiled to HTML before they are rendered, each
+    /// distinct body once. With `inline_css` the CSS of the HTML output and of the
+    /// tenant's stylesheet is inlined into the elements it applies to.
+    ///
+    /// The values of the template's rich HTML inputs are cleaned down to an allowlist
+    /// of tags and attributes before rendering, the composed template reports what was
+    /// removed from each of them. The links of the HTML output are rewritten with the
+    /// template's link rules.
+    ///
+    /// Marketing templates have to be composed with an unsubscribe URL, an unsubscribe
+    /// footer is added unless the body places it, and their MIME output carries the
+    /// `List-Unsubscribe` headers.
+    async fn compose_with_report(
+        &self,
+        tenant: String,
+        name: String,
+        inputs: Vec<InputValue>,
+        options: ComposeOptions,
+    ) -> ServiceResult<ComposedTemplate>;
+    async fn compose(
+        &self,
+        tenant: String,
+        name: String,
+        inputs: Vec<InputValue>,
+        options: ComposeOptions,
+    ) -> ServiceResult<String> {
+        Ok(self
+            .compose_with_report(tenant, name, inputs, options)
+            .await?
+            .output)
+    }
+}
+
+/// A template as `add_template`, `update_template` and `ensure_template` write it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateDefinition {
+    /// The path of the template, e.g. `auth/registration`.
+    pub name: String,
+    pub description: String,
+    pub body: String,
+    pub engine: TemplateEngineKind,
+    pub content_type: ContentType,
+    pub inputs: Vec<TemplateInput>,
+}
+
+impl TemplateDefinition {
+    fn fields(&self) -> TemplateFields<'_> {
+        TemplateFields {
+            description: &self.description,
+            body: &self.body,
+            engine: self.engine.as_str(),
+            content_type: self.content_type.as_str(),
+            inputs: &self.inputs,
+        }
+    }
+
+    /// The template as it is once written with this definition, for checks that have to
+    /// pass before it is.
+    fn applied_to(&self, template: &TemplateInputsEntity) -> TemplateInputsEntity {
+        TemplateInputsEntity {
+            description: self.description.clone(),
+            body: self.body.clone(),
+            engine: self.engine.as_str().to_string(),
+            content_type: self.content_type.as_str().to_string(),
+            inputs: self
+                .inputs
+                .iter()
+                .map(|input| InputEntity {
+                    id: 0,
+                    name: input.name.clone(),
+                    default_value: input.default_value.clone(),
+                    template_id: template.id,
+                })
+                .collect(),
+            ..template.clone()
+        }
+    }
+
+    /// Whether the template already has this definition, inputs compare regardless of
+    /// their order.
+    fn is_written_in(&self, template: &TemplateInputsEntity) -> bool {
+        let mut existing_inputs = template
+            .inputs
+            .iter()
+            .map(|input| (input.name.as_str(), input.default_value.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+        let mut inputs = self
+            .inputs
+            .iter()
+            .map(|input| (input.name.as_str(), input.default_value.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+        existing_inputs.sort_unstable();
+        inputs.sort_unstable();
+
+        template.description == self.description
+            && template.body == self.body
+            && template.engine == self.engine.as_str()
+            && template.content_type == self.content_type.as_str()
+            && existing_inputs == inputs
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ComposeOptions {
+    pub scope: Option<String>,
+    pub locale: Option<String>,
+    pub output: ComposeOutput,
+    /// An HTML template the HTML of a Markdown template is placed in, as its `content`
+    /// input.
+    pub layout: Option<String>,
+    pub inline_css: bool,
+}
+
+/// A page of templates, each with the revision it was read at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListedTemplates {
+    pub templates: Vec<TemplateRevision>,
+    pub count: i64,
+}
+
+impl ListedTemplates {
+    pub fn into_list_template_response(self) -> ListTemplateResponse {
+        ListTemplateResponse {
+            templates: self
+                .templates
+                .into_iter()
+                .map(|template| template.template)
+                .collect(),
+            count: self.count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComposedTemplate {
+    pub output: String,
+    /// The revision of the template that was composed.
+    pub revision: String,
+    /// The rich HTML inputs the sanitiser removed anything from.
+    pub sanitized_inputs: Vec<SanitizedInput>,
+    /// The `List-Unsubscribe` headers of a marketing template for the caller to send
+    /// with the output, left out of MIME output that carries them itself.
+    pub unsubscribe_headers: Vec<(&'static str, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnsureOutcome {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnsuredTemplate {
+    pub template: TemplateRevision,
+    pub outcome: EnsureOutcome,
 }
 
 pub type DynTemplatingServiceTrait = Arc<dyn TemplatingServiceTrait + Send + Sync>;
@@ -35,32 +398,382 @@ pub type DynTemplatingServiceTrait = Arc<dyn TemplatingServiceTrait + Send + Syn
 pub struct TemplatingService {
     template_repository: DynTemplateRepositoryTrait,
     inputs_repository: DynInputRepositoryTrait,
+    override_repository: DynTemplateOverrideRepositoryTrait,
+    variant_repository: DynTemplateVariantRepositoryTrait,
+    schema_repository: DynTemplateSchemaRepositoryTrait,
+    settings_repository: DynTemplateSettingsRepositoryTrait,
+    message_repository: DynMessageRepositoryTrait,
+    stylesheet_repository: DynStylesheetRepositoryTrait,
+    locale_settings: LocaleSettings,
+    render_limits: RenderLimits,
+    mjml_cache: Arc<MjmlCache>,
+    click_tracking: Option<Arc<ClickTracking>>,
 }
 
 impl TemplatingService {
-    pub fn new(
-        template_repository: DynTemplateRepositoryTrait,
-        inputs_repository: DynInputRepositoryTrait,
-    ) -> Self {
+    pub fn new(repositories: &Repositories, locale_settings: LocaleSettings) -> Self {
         Self {
-            template_repository,
-            inputs_repository,
+            template_repository: repositories.template_repository.clone(),
+            inputs_repository: repositories.inputs_repository.clone(),
+            override_repository: repositories.override_repository.clone(),
+            variant_repository: repositories.variant_repository.clone(),
+            schema_repository: repositories.schema_repository.clone(),
+            settings_repository: repositories.settings_repository.clone(),
+            message_repository: repositories.message_repository.clone(),
+            stylesheet_repository: repositories.stylesheet_repository.clone(),
+            locale_settings,
+            render_limits: RenderLimits::default(),
+            mjml_cache: Arc::new(MjmlCache::default()),
+            click_tracking: None,
+        }
+    }
+
+    pub fn with_render_limits(mut self, render_limits: RenderLimits) -> Self {
+        self.render_limits = render_limits;
+        self
+    }
+
+    /// The redirect links are sent through when their rules track clicks, without one
+    /// such settings are rejected.
+    pub fn with_click_tracking(mut self, click_tracking: ClickTracking) -> Self {
+        self.click_tracking = Some(Arc::new(click_tracking));
+        self
+    }
+
+    /// Looks the template up in the tenant first, falling back to the global tenant's
+    /// template of the same name when the tenant has no override.
+    async fn resolve_template(
+        &self,
+        tenant: &str,
+        path: &TemplatePath,
+    ) -> ServiceResult<Option<TemplateInputsEntity>> {
+        let tenant_template = self
+            .template_repository
+            .get_template(tenant, &path.namespace, &path.name)
+            .await?;
+
+        if tenant_template.is_some() || tenant == GLOBAL_TENANT {
+            return Ok(tenant_template);
+        }
+
+        Ok(self
+            .template_repository
+            .get_template(GLOBAL_TENANT, &path.namespace, &path.name)
+            .await?)
+    }
+
+    /// Looks up a template another one is composed with, such as the layout of a
+    /// Markdown template or the unsubscribe footer of a marketing template. It resolves
+    /// like the template itself and has to render HTML.
+    async fn resolve_html_template(
+        &self,
+        tenant: &str,
+        name: &str,
+        inputs: Vec<InputValue>,
+        role: &str,
+    ) -> ServiceResult<Layout> {
+        let path = TemplatePath::parse(name)?;
+        let layout = self.resolve_template(tenant, &path).await?.ok_or_else(|| {
+            error!("{} {:?} does not exists", role, name);
+            ServiceError::NotFound(format!("{} template does not exist", role))
+        })?;
+        if parse_content_type(&layout.content_type)? != ContentType::Html {
+            error!("{} {:?} does not render HTML", role, name);
+            return Err(ServiceError::BadRequest(format!(
+                "{}s have to be HTML templates",
+                role
+            )));
+        }
+
+        let default_inputs = self
+            .inputs_repository
+            .get_template_inputs(layout.id)
+            .await?;
+
+        Ok(Layout {
+            engine: parse_engine(&layout.engine)?,
+            body: layout.body,
+            data: merge_inputs(default_inputs, None, inputs),
+        })
+    }
+
+    /// How the recipient of a marketing template unsubscribes. The footer is left out
+    /// when rendering finds that the body or the layout places the unsubscribe URL.
+    async fn resolve_unsubscribe(
+        &self,
+        tenant: &str,
+        settings: &TemplateSettings,
+        data: &BTreeMap<String, String>,
+    ) -> ServiceResult<Unsubscribe> {
+        let url = Unsubscribe::parse_url(data)?;
+
+        let footer = match &settings.unsubscribe_footer {
+            Some(footer) => {
+                let inputs = data
+                    .iter()
+                    .map(|(name, value)| InputValue {
+                        name: name.clone(),
+                        value: value.clone(),
+                    })
+                    .collect();
+                Some(UnsubscribeFooter::Template(
+                    self.resolve_html_template(tenant, footer, inputs, "unsubscribe footer")
+                        .await?,
+                ))
+            }
+            None => Some(UnsubscribeFooter::BuiltIn),
+        };
+
+        Ok(Unsubscribe { url, footer })
+    }
+
+    /// The stylesheet inlined into the composed HTML, the tenant's own or else the
+    /// global tenant's. Only HTML output can be inlined.
+    async fn resolve_stylesheet(
+        &self,
+        tenant: &str,
+        content_type: ContentType,
+        output: ComposeOutput,
+    ) -> ServiceResult<Option<String>> {
+        if !renders_html(content_type, output) {
+            error!("cannot inline CSS into {} output", content_type);
+            return Err(ServiceError::BadRequest(
+                "CSS can only be inlined into HTML output".to_string(),
+            ));
+        }
+
+        let stylesheet = match self.stylesheet_repository.get_stylesheet(tenant).await? {
+            Some(stylesheet) => Some(stylesheet),
+            None if tenant != GLOBAL_TENANT => {
+                self.stylesheet_repository
+                    .get_stylesheet(GLOBAL_TENANT)
+                    .await?
+            }
+            None => None,
+        };
+
+        Ok(stylesheet.map(|stylesheet| stylesheet.css))
+    }
+
+    async fn load_settings(&self, template_id: i64) -> ServiceResult<TemplateSettings> {
+        match self.settings_repository.get_settings(template_id).await? {
+            Some(settings) => parse_settings(&settings.settings),
+            None => Ok(TemplateSettings::default()),
+        }
+    }
+
+    async fn get_existing_template(
+        &self,
+        tenant: &str,
+        name: &str,
+    ) -> ServiceResult<TemplateInputsEntity> {
+        let path = TemplatePath::parse(name)?;
+
+        self.template_repository
+            .get_template(tenant, &path.namespace, &path.name)
+            .await?
+            .ok_or_else(|| {
+                error!("template {:?} does not exists", name);
+                ServiceError::NotFound(String::from("template name does not exist"))
+            })
+    }
+
+    async fn export_template(
+        &self,
+        template: TemplateInputsEntity,
+    ) -> ServiceResult<BundleTemplate> {
+        let overrides = self.override_repository.get_overrides(template.id).await?;
+        let variants = self.variant_repository.get_variants(template.id).await?;
+        let schema = self.schema_repository.get_schema(template.id).await?;
+        let settings = self.load_settings(template.id).await?;
+
+        BundleTemplate::new(template, overrides, variants, schema, settings)
+    }
+
+    /// Writes the bundled template under `name`, replacing the definition, overrides,
+    /// variants, JSON Schema and settings of the existing template if there is one.
+    async fn write_bundle_template(
+        &self,
+        tenant: &str,
+        name: &str,
+        template: &BundleTemplate,
+        existing_template: Option<&TemplateInputsEntity>,
+    ) -> ServiceResult<()> {
+        let definition = TemplateDefinition {
+            name: name.to_string(),
+            description: template.description.clone(),
+            body: template.body.clone(),
+            engine: template.engine,
+            content_type: template.content_type,
+            inputs: template
+                .inputs
+                .iter()
+                .cloned()
+                .map(TemplateInput::from)
+                .collect(),
+        };
+
+        match existing_template {
+            Some(existing_template) => {
+                // The stored settings may not apply to the bundled definition, the
+                // bundled settings are saved once the template is written.
+                if !self.load_settings(existing_template.id).await?.is_default() {
+                    self.set_template_settings(
+                        tenant.to_string(),
+                        name.to_string(),
+                        TemplateSettings::default(),
+                    )
+                    .await?;
+                }
+                self.update_template(tenant.to_string(), definition, None)
+                    .await?;
+
+                for template_override in self
+                    .override_repository
+                    .get_overrides(existing_template.id)
+                    .await?
+                {
+                    if !template
+                        .overrides
+                        .iter()
+                        .any(|bundled| bundled.scope == template_override.scope)
+                    {
+                        self.override_repository
+                            .remove_override(existing_template.id, &template_override.scope)
+                            .await?;
+                    }
+                }
+                for variant in self
+                    .variant_repository
+                    .get_variants(existing_template.id)
+                    .await?
+                {
+                    if !template
+                        .variants
+                        .iter()
+                        .any(|bundled| bundled.locale == variant.locale)
+                    {
+                        self.variant_repository
+                            .remove_variant(existing_template.id, &variant.locale)
+                            .await?;
+                    }
+                }
+                if template.json_schema.is_none() {
+                    self.schema_repository
+                        .remove_schema(existing_template.id)
+                        .await?;
+                }
+            }
+            None => {
+                self.add_template(tenant.to_string(), definition).await?;
+            }
+        }
+
+        for template_override in &template.overrides {
+            self.set_override(
+                tenant.to_string(),
+                name.to_string(),
+                template_override.scope.clone(),
+                template_override.body.clone(),
+                template_override
+                    .inputs
+                    .iter()
+                    .cloned()
+                    .map(TemplateInput::from)
+                    .collect(),
+            )
+            .await?;
+        }
+        for variant in &template.variants {
+            self.set_variant(
+                tenant.to_string(),
+                name.to_string(),
+                variant.locale.clone(),
+                variant.body.clone(),
+            )
+            .await?;
+        }
+        if let Some(json_schema) = &template.json_schema {
+            self.set_json_schema(
+                tenant.to_string(),
+                name.to_string(),
+                json_schema.to_string(),
+            )
+            .await?;
+        }
+        if !template.settings.is_default() {
+            self.set_template_settings(
+                tenant.to_string(),
+                name.to_string(),
+                template.settings.clone(),
+            )
+            .await?;
         }
+
+        Ok(())
     }
 }
 
+/// Checks the body parses for its engine and, for MJML templates, that it compiles.
+fn validate_body(
+    name: &str,
+    engine: TemplateEngineKind,
+    content_type: ContentType,
+    body: &str,
+) -> ServiceResult<()> {
+    let validation = engine
+        .engine()
+        .validate(body)
+        .and_then(|_| match content_type {
+            ContentType::Mjml => compile_mjml(body).map(|_| ()),
+            _ => Ok(()),
+        });
+    if let Err(validation_error) = validation {
+        error!(
+            "template {:?} body is not valid: {:?}",
+            name, &validation_error
+        );
+        return Err(validation_error);
+    }
+
+    Ok(())
+}
+
+/// Applies an override layer on top of its base template, the override body replaces
+/// the base body and the override inputs replace the base default values.
+fn apply_override(
+    mut template: TemplateInputsEntity,
+    template_override: TemplateOverrideInputsEntity,
+) -> TemplateInputsEntity {
+    if let Some(body) = template_override.body {
+        template.body = body;
+    }
+    template.inputs.iter_mut().for_each(|input| {
+        if let Some(override_input) = template_override
+            .inputs
+            .iter()
+            .find(|override_input| override_input.name == input.name)
+        {
+            input.default_value = override_input.default_value.clone();
+        }
+    });
+    template
+}
+
 #[async_trait]
 impl TemplatingServiceTrait for TemplatingService {
     async fn add_template(
         &self,
-        name: String,
-        description: String,
-        body: String,
-        inputs: Vec<TemplateInput>,
-    ) -> ServiceResult<TemplateResponse> {
-        let existing_template = self.template_repository.get_template(&name).await?;
+        tenant: String,
+        definition: TemplateDefinition,
+    ) -> ServiceResult<TemplateRevision> {
+        let path = TemplatePath::parse(&definition.name)?;
+        let existing_template = self
+            .template_repository
+            .get_template(&tenant, &path.namespace, &path.name)
+            .await?;
 
-        if inputs.is_empty() {
+        if definition.inputs.is_empty() {
             error!("Cannot create template with no inputs");
             return Err(ServiceError::BadRequest(
                 "Cannot create template with no inputs".to_string(),
@@ -68,93 +781,1209 @@ impl TemplatingServiceTrait for TemplatingService {
         }
 
         if existing_template.is_some() {
-            error!("template {:?} already exists", &name);
+            error!("template {:?} already exists", &definition.name);
             return Err(ServiceError::ObjectConflict(String::from(
                 "template name is taken",
             )));
         }
 
-        info!("creating template {:?}", &name);
+        validate_body(
+            &definition.name,
+            definition.engine,
+            definition.content_type,
+            &definition.body,
+        )?;
+
+        info!("creating template {:?}", &definition.name);
         let created_template = self
             .template_repository
-            .add_template(&name, &description, &body, &inputs)
+            .add_template(&tenant, &path.namespace, &path.name, definition.fields())
             .await?;
 
-        info!("group successfully created");
+        info!("template successfully created");
 
-        Ok(created_template.into_template_response())
+        Ok(created_template.into())
     }
 
-    async fn remove_template(&self, name: String) -> ServiceResult<TemplateResponse> {
-        let existing_template = self.template_repository.get_template(&name).await?;
+    async fn get_template(&self, tenant: String, name: String) -> ServiceResult<TemplateRevision> {
+        Ok(self.get_existing_template(&tenant, &name).await?.into())
+    }
 
-        match existing_template {
-            Some(template) => {
-                info!("removed template {:?}", &name);
-                self.template_repository.remove_template(&name).await?;
+    async fn update_template(
+        &self,
+        tenant: String,
+        definition: TemplateDefinition,
+        expected_revision: Option<String>,
+    ) -> ServiceResult<TemplateRevision> {
+        let path = TemplatePath::parse(&definition.name)?;
+        let expected_updated_at = expected_revision
+            .as_deref()
+            .map(parse_revision)
+            .transpose()?;
 
-                info!("successfully removed subscriber from group");
-                Ok(template.into_template_response())
+        if definition.inputs.is_empty() {
+            error!("Cannot update template with no inputs");
+            return Err(ServiceError::BadRequest(
+                "Cannot update template with no inputs".to_string(),
+            ));
+        }
+
+        let existing_template = self
+            .get_existing_template(&tenant, &definition.name)
+            .await?;
+        validate_body(
+            &definition.name,
+            definition.engine,
+            definition.content_type,
+            &definition.body,
+        )?;
+        let settings = self.load_settings(existing_template.id).await?;
+        if let Err(validation_error) = settings.validate(&definition.applied_to(&existing_template))
+        {
+            error!(
+                "settings of template {:?} do not apply to the update: {:?}",
+                &definition.name, &validation_error
+            );
+            return Err(validation_error);
+        }
+
+        info!("updating template {:?}", &definition.name);
+        let updated_template = self
+            .template_repository
+            .update_template(
+                &tenant,
+                &path.namespace,
+                &path.name,
+                definition.fields(),
+                expected_updated_at,
+            )
+            .await?;
+
+        match updated_template {
+            Some(template) => {
+                info!("template successfully updated");
+                Ok(template.into())
             }
             None => {
-                error!("template {:?} does not exists", &name);
+                error!(
+                    "template {:?} has been modified since {:?}",
+                    &definition.name, &expected_revision
+                );
                 Err(ServiceError::ObjectConflict(String::from(
-                    "template name does not exist",
+                    "template has been modified since the expected revision",
                 )))
             }
         }
     }
 
-    async fn list_templates(
+    async fn ensure_template(
         &self,
-        request: ListTemplateRequest,
-    ) -> ServiceResult<ListTemplateResponse> {
-        let templates = self
+        tenant: String,
+        definition: TemplateDefinition,
+    ) -> ServiceResult<EnsuredTemplate> {
+        let path = TemplatePath::parse(&definition.name)?;
+        let existing_template = self
             .template_repository
-            .list_templates(request.offset, request.limit)
+            .get_template(&tenant, &path.namespace, &path.name)
             .await?;
-        let count = self.template_repository.get_templates_count().await?;
 
-        Ok(ListTemplateResponse {
-            templates: templates
-                .into_iter()
-                .map(|input| input.into_template_response())
-                .collect(),
-            count,
-        })
+        let existing_template = match existing_template {
+            Some(template) => template,
+            None => {
+                let created_template = self.add_template(tenant, definition).await?;
+
+                return Ok(EnsuredTemplate {
+                    template: created_template,
+                    outcome: EnsureOutcome::Created,
+                });
+            }
+        };
+
+        if definition.is_written_in(&existing_template) {
+            info!("template {:?} is up to date", &definition.name);
+            return Ok(EnsuredTemplate {
+                template: existing_template.into(),
+                outcome: EnsureOutcome::Unchanged,
+            });
+        }
+
+        if definition.inputs.is_empty() {
+            error!("Cannot update template with no inputs");
+            return Err(ServiceError::BadRequest(
+                "Cannot update template with no inputs".to_string(),
+            ));
+        }
+
+        validate_body(
+            &definition.name,
+            definition.engine,
+            definition.content_type,
+            &definition.body,
+        )?;
+
+        info!(
+            "updating template {:?} to the ensured definition",
+            &definition.name
+        );
+        let updated_template = self
+            .template_repository
+            .update_template(
+                &tenant,
+                &path.namespace,
+                &path.name,
+                definition.fields(),
+                Some(existing_template.updated_at),
+            )
+            .await?;
+
+        match updated_template {
+            Some(template) => Ok(EnsuredTemplate {
+                template: template.into(),
+                outcome: EnsureOutcome::Updated,
+            }),
+            None => {
+                error!(
+                    "template {:?} was modified while being ensured",
+                    &definition.name
+                );
+                Err(ServiceError::ObjectConflict(String::from(
+                    "template was modified while being ensured",
+                )))
+            }
+        }
     }
 
-    async fn compose(&self, name: String, inputs: Vec<InputValue>) -> ServiceResult<String> {
-        let existing_template = self.template_repository.get_template(&name).await?;
+    async fn remove_template(
+        &self,
+        tenant: String,
+        name: String,
+        expected_revision: Option<String>,
+    ) -> ServiceResult<TemplateRevision> {
+        let path = TemplatePath::parse(&name)?;
+        let expected_updated_at = expected_revision
+            .as_deref()
+            .map(parse_revision)
+            .transpose()?;
+        let existing_template = self
+            .template_repository
+            .get_template(&tenant, &path.namespace, &path.name)
+            .await?;
 
         if existing_template.is_none() {
             error!("template {:?} does not exists", &name);
-            return Err(ServiceError::NotFound(String::from(
+            return Err(ServiceError::ObjectConflict(String::from(
                 "template name does not exist",
             )));
         }
 
-        let mut handlebars = Handlebars::new();
-        let source = existing_template.unwrap();
-        assert!(handlebars
-            .register_template_string("t1", source.body)
-            .is_ok());
+        info!("removing template {:?}", &name);
+        let removed_template = self
+            .template_repository
+            .remove_template(&tenant, &path.namespace, &path.name, expected_updated_at)
+            .await?;
 
-        let default_inputs = self
-            .inputs_repository
-            .get_template_inputs(&source.name)
+        match removed_template {
+            Some(template) => {
+                info!("template successfully removed");
+                Ok(template.into())
+            }
+            None => {
+                error!(
+                    "template {:?} has been modified since {:?}",
+                    &name, &expected_revision
+                );
+                Err(ServiceError::ObjectConflict(String::from(
+                    "template has been modified since the expected revision",
+                )))
+            }
+        }
+    }
+
+    async fn list_templates(
+        &self,
+        tenant: String,
+        request: ListTemplateRequest,
+        namespace: Option<String>,
+    ) -> ServiceResult<ListedTemplates> {
+        let namespace = namespace
+            .map(|namespace| parse_namespace(&namespace))
+            .transpose()?;
+        let templates = self
+            .template_repository
+            .list_templates(
+                &tenant,
+                namespace.as_deref(),
+                false,
+                request.offset,
+                request.limit,
+            )
+            .await?;
+        let count = self
+            .template_repository
+            .get_templates_count(&tenant, namespace.as_deref(), false)
             .await?;
 
-        let mut data = BTreeMap::new();
-        default_inputs.into_iter().for_each(|input| {
-            data.insert(input.name, input.default_value);
-        });
-        inputs.into_iter().for_each(|input| {
-            data.insert(input.name, input.value);
-        });
+        Ok(ListedTemplates {
+            templates: templates.into_iter().map(TemplateRevision::from).collect(),
+            count,
+        })
+    }
 
-        handlebars.render("t1", &data).map_err(|_| {
-            ServiceError::InternalServerErrorWithContext("Failed to render template".to_string())
+    async fn list_trashed_templates(
+        &self,
+        tenant: String,
+        request: ListTemplateRequest,
+    ) -> ServiceResult<ListedTemplates> {
+        let templates = self
+            .template_repository
+            .list_templates(&tenant, None, true, request.offset, request.limit)
+            .await?;
+        let count = self
+            .template_repository
+            .get_templates_count(&tenant, None, true)
+            .await?;
+
+        Ok(ListedTemplates {
+            templates: templates.into_iter().map(TemplateRevision::from).collect(),
+            count,
+        })
+    }
+
+    async fn restore_template(
+        &self,
+        tenant: String,
+        name: String,
+    ) -> ServiceResult<TemplateRevision> {
+        let path = TemplatePath::parse(&name)?;
+        let existing_template = self
+            .template_repository
+            .get_template(&tenant, &path.namespace, &path.name)
+            .await?;
+
+        if existing_template.is_some() {
+            error!("template {:?} already exists", &name);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "template name is taken",
+            )));
+        }
+
+        info!("restoring template {:?}", &name);
+        let restored_template = self
+            .template_repository
+            .restore_template(&tenant, &path.namespace, &path.name)
+            .await?;
+
+        match restored_template {
+            Some(template) => {
+                info!("template successfully restored");
+                Ok(template.into())
+            }
+            None => {
+                error!("template {:?} is not in the trash", &name);
+                Err(ServiceError::NotFound(String::from(
+                    "template name is not in the trash",
+                )))
+            }
+        }
+    }
+
+    async fn move_template(
+        &self,
+        tenant: String,
+        name: String,
+        namespace: String,
+    ) -> ServiceResult<TemplateRevision> {
+        let path = TemplatePath::parse(&name)?;
+        let namespace = parse_namespace(&namespace)?;
+
+        let destination_template = self
+            .template_repository
+            .get_template(&tenant, &namespace, &path.name)
+            .await?;
+
+        if destination_template.is_some() {
+            error!(
+                "template {:?} already exists in namespace {:?}",
+                &path.name, &namespace
+            );
+            return Err(ServiceError::ObjectConflict(String::from(
+                "template name is taken in the destination namespace",
+            )));
+        }
+
+        info!("moving template {:?} to namespace {:?}", &name, &namespace);
+        let moved_template = self
+            .template_repository
+            .rename_template(&tenant, &path.namespace, &path.name, &namespace, &path.name)
+            .await?;
+
+        match moved_template {
+            Some(template) => {
+                info!("template successfully moved");
+                Ok(template.into())
+            }
+            None => {
+                error!("template {:?} does not exists", &name);
+                Err(ServiceError::NotFound(String::from(
+                    "template name does not exist",
+                )))
+            }
+        }
+    }
+
+    async fn rename_template(
+        &self,
+        tenant: String,
+        name: String,
+        new_name: String,
+    ) -> ServiceResult<TemplateRevision> {
+        let path = TemplatePath::parse(&name)?;
+        let new_path = TemplatePath::parse(&new_name)?;
+
+        let destination_template = self
+            .template_repository
+            .get_template(&tenant, &new_path.namespace, &new_path.name)
+            .await?;
+
+        if destination_template.is_some() {
+            error!("template {:?} already exists", &new_name);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "template name is taken",
+            )));
+        }
+
+        info!("renaming template {:?} to {:?}", &name, &new_name);
+        let renamed_template = self
+            .template_repository
+            .rename_template(
+                &tenant,
+                &path.namespace,
+                &path.name,
+                &new_path.namespace,
+                &new_path.name,
+            )
+            .await?;
+
+        match renamed_template {
+            Some(template) => {
+                info!("template successfully renamed");
+                Ok(template.into())
+            }
+            None => {
+                error!("template {:?} does not exists", &name);
+                Err(ServiceError::NotFound(String::from(
+                    "template name does not exist",
+                )))
+            }
+        }
+    }
+
+    async fn clone_template(
+        &self,
+        tenant: String,
+        name: String,
+        new_name: String,
+    ) -> ServiceResult<TemplateRevision> {
+        let path = TemplatePath::parse(&name)?;
+        let new_path = TemplatePath::parse(&new_name)?;
+
+        let source_template = self.resolve_template(&tenant, &path).await?;
+
+        if source_template.is_none() {
+            error!("template {:?} does not exists", &name);
+            return Err(ServiceError::NotFound(String::from(
+                "template name does not exist",
+            )));
+        }
+
+        let destination_template = self
+            .template_repository
+            .get_template(&tenant, &new_path.namespace, &new_path.name)
+            .await?;
+
+        if destination_template.is_some() {
+            error!("template {:?} already exists", &new_name);
+            return Err(ServiceError::ObjectConflict(String::from(
+                "template name is taken",
+            )));
+        }
+
+        info!("cloning template {:?} as {:?}", &name, &new_name);
+        let cloned_template = self
+            .template_repository
+            .clone_template(
+                source_template.unwrap().id,
+                &tenant,
+                &new_path.namespace,
+                &new_path.name,
+            )
+            .await?;
+
+        info!("template successfully cloned");
+
+        Ok(cloned_template.into())
+    }
+
+    async fn set_override(
+        &self,
+        tenant: String,
+        name: String,
+        scope: String,
+        body: Option<String>,
+        inputs: Vec<TemplateInput>,
+    ) -> ServiceResult<TemplateResponse> {
+        let template = self.get_existing_template(&tenant, &name).await?;
+
+        if scope.trim().is_empty() {
+            error!("Cannot create override with an empty scope");
+            return Err(ServiceError::BadRequest(
+                "Cannot create override with an empty scope".to_string(),
+            ));
+        }
+
+        if let Some(unknown_input) = inputs.iter().find(|input| {
+            !template
+                .inputs
+                .iter()
+                .any(|template_input| template_input.name == input.name)
+        }) {
+            error!(
+                "template {:?} has no input named {:?}",
+                &name, &unknown_input.name
+            );
+            return Err(ServiceError::BadRequest(format!(
+                "template has no input named {:?}",
+                unknown_input.name
+            )));
+        }
+
+        if let Some(body) = &body {
+            validate_body(
+                &name,
+                parse_engine(&template.engine)?,
+                parse_content_type(&template.content_type)?,
+                body,
+            )?;
+        }
+
+        info!("saving override {:?} of template {:?}", &scope, &name);
+        let template_override = self
+            .override_repository
+            .set_override(template.id, &scope, body.as_deref(), &inputs)
+            .await?;
+        self.template_repository.touch_template(template.id).await?;
+
+        info!("override successfully saved");
+
+        Ok(apply_override(template, template_override).into_template_response())
+    }
+
+    async fn remove_override(
+        &self,
+        tenant: String,
+        name: String,
+        scope: String,
+    ) -> ServiceResult<TemplateResponse> {
+        let template = self.get_existing_template(&tenant, &name).await?;
+
+        let removed_override = self
+            .override_repository
+            .remove_override(template.id, &scope)
+            .await?;
+
+        match removed_override {
+            Some(template_override) => {
+                self.template_repository.touch_template(template.id).await?;
+                info!("removed override {:?} of template {:?}", &scope, &name);
+                Ok(apply_override(template, template_override).into_template_response())
+            }
+            None => {
+                error!("template {:?} has no override {:?}", &name, &scope);
+                Err(ServiceError::NotFound(String::from(
+                    "template override does not exist",
+                )))
+            }
+        }
+    }
+
+    async fn set_variant(
+        &self,
+        tenant: String,
+        name: String,
+        locale: String,
+        body: String,
+    ) -> ServiceResult<TemplateResponse> {
+        let template = self.get_existing_template(&tenant, &name).await?;
+        let locale = parse_locale(&locale)?;
+        validate_body(
+            &name,
+            parse_engine(&template.engine)?,
+            parse_content_type(&template.content_type)?,
+            &body,
+        )?;
+
+        info!("saving {:?} variant of template {:?}", &locale, &name);
+        self.variant_repository
+            .set_variant(template.id, &locale, &body)
+            .await?;
+        self.template_repository.touch_template(template.id).await?;
+
+        info!("variant successfully saved");
+
+        Ok(template.into_template_response())
+    }
+
+    async fn remove_variant(
+        &self,
+        tenant: String,
+        name: String,
+        locale: String,
+    ) -> ServiceResult<TemplateResponse> {
+        let template = self.get_existing_template(&tenant, &name).await?;
+        let locale = parse_locale(&locale)?;
+
+        let removed_variant = self
+            .variant_repository
+            .remove_variant(template.id, &locale)
+            .await?;
+
+        match removed_variant {
+            Some(_) => {
+                self.template_repository.touch_template(template.id).await?;
+                info!("removed {:?} variant of template {:?}", &locale, &name);
+                Ok(template.into_template_response())
+            }
+            None => {
+                error!("template {:?} has no {:?} variant", &name, &locale);
+                Err(ServiceError::NotFound(String::from(
+                    "template variant does not exist",
+                )))
+            }
+        }
+    }
+
+    async fn set_json_schema(
+        &self,
+        tenant: String,
+        name: String,
+        json_schema: String,
+    ) -> ServiceResult<TemplateResponse> {
+        let template = self.get_existing_template(&tenant, &name).await?;
+        if parse_content_type(&template.content_type)? != ContentType::Json {
+            error!("template {:?} does not render JSON", &name);
+            return Err(ServiceError::BadRequest(
+                "JSON schemas can only be attached to JSON templates".to_string(),
+            ));
+        }
+
+        if let Err(schema_error) = compile_json_schema(&json_schema) {
+            error!(
+                "JSON schema of template {:?} is not valid: {:?}",
+                &name, &schema_error
+            );
+            return Err(schema_error);
+        }
+
+        info!("saving JSON schema of template {:?}", &name);
+        self.schema_repository
+            .set_schema(template.id, &json_schema)
+            .await?;
+        self.template_repository.touch_template(template.id).await?;
+
+        info!("JSON schema successfully saved");
+
+        Ok(template.into_template_response())
+    }
+
+    async fn remove_json_schema(
+        &self,
+        tenant: String,
+        name: String,
+    ) -> ServiceResult<TemplateResponse> {
+        let template = self.get_existing_template(&tenant, &name).await?;
+
+        let removed_schema = self.schema_repository.remove_schema(template.id).await?;
+
+        match removed_schema {
+            Some(_) => {
+                self.template_repository.touch_template(template.id).await?;
+                info!("removed JSON schema of template {:?}", &name);
+                Ok(template.into_template_response())
+            }
+            None => {
+                error!("template {:?} has no JSON schema", &name);
+                Err(ServiceError::NotFound(String::from(
+                    "template JSON schema does not exist",
+                )))
+            }
+        }
+    }
+
+    async fn get_template_settings(
+        &self,
+        tenant: String,
+        name: String,
+    ) -> ServiceResult<TemplateSettings> {
+        let template = self.get_existing_template(&tenant, &name).await?;
+
+        self.load_settings(template.id).await
+    }
+
+    async fn set_template_settings(
+        &self,
+        tenant: String,
+        name: String,
+        settings: TemplateSettings,
+    ) -> ServiceResult<TemplateResponse> {
+        let template = self.get_existing_template(&tenant, &name).await?;
+        if let Err(validation_error) = settings.validate(&template) {
+            error!(
+                "settings of template {:?} are not valid: {:?}",
+                &name, &validation_error
+            );
+            return Err(validation_error);
+        }
+        if settings.tracks_clicks() && self.click_tracking.is_none() {
+            error!(
+                "settings of template {:?} track clicks without a click-tracking redirect",
+                &name
+            );
+            return Err(ServiceError::BadRequest(
+                "click tracking is not configured".to_string(),
+            ));
+        }
+
+        let settings = serde_json::to_string(&settings).map_err(|_| {
+            ServiceError::InternalServerErrorWithContext(
+                "Failed to serialise the settings of template".to_string(),
+            )
+        })?;
+
+        info!("saving settings of template {:?}", &name);
+        self.settings_repository
+            .set_settings(template.id, &settings)
+            .await?;
+        self.template_repository.touch_template(template.id).await?;
+
+        info!("template settings successfully saved");
+
+        Ok(template.into_template_response())
+    }
+
+    async fn export_templates(
+        &self,
+        tenant: String,
+        names: Vec<String>,
+    ) -> ServiceResult<TemplateBundle> {
+        let mut templates = vec![];
+        if names.is_empty() {
+            let count = self
+                .template_repository
+                .get_templates_count(&tenant, None, false)
+                .await?;
+            templates = self
+                .template_repository
+                .list_templates(&tenant, None, false, 0, count)
+                .await?;
+        } else {
+            for name in &names {
+                templates.push(self.get_existing_template(&tenant, name).await?);
+            }
+        }
+
+        info!("exporting {} templates", templates.len());
+        let mut bundle_templates = vec![];
+        for template in templates {
+            bundle_templates.push(self.export_template(template).await?);
+        }
+
+        Ok(TemplateBundle::new(bundle_templates))
+    }
+
+    async fn import_templates(
+        &self,
+        tenant: String,
+        bundle: TemplateBundle,
+        options: ImportOptions,
+    ) -> ServiceResult<ImportReport> {
+        bundle.check_version()?;
+
+        let mut report = ImportReport {
+            dry_run: options.dry_run,
+            changes: vec![],
+        };
+        let mut imported_names: HashSet<String> = HashSet::new();
+        for template in bundle.templates {
+            let template = template.normalized();
+            let path = TemplatePath::parse(&template.name)?;
+            let existing_template = self
+                .template_repository
+                .get_template(&tenant, &path.namespace, &path.name)
+                .await?;
+
+            let action = match &existing_template {
+                None => ImportAction::Create,
+                Some(existing_template) => {
+                    let existing_bundle_template =
+                        self.export_template(existing_template.clone()).await?;
+                    match options.strategy {
+                        _ if existing_bundle_template == template => ImportAction::Unchanged,
+                        ConflictStrategy::Skip => ImportAction::Skip,
+                        ConflictStrategy::Overwrite => ImportAction::Overwrite {
+                            changes: existing_bundle_template.diff(&template)?,
+                        },
+                        ConflictStrategy::Rename => {
+                            let mut suffix = 1;
+                            let name = loop {
+                                let candidate = format!("{}-{}", &template.name, suffix);
+                                let candidate_path = TemplatePath::parse(&candidate)?;
+                                let taken = self
+                                    .template_repository
+                                    .get_template(
+                                        &tenant,
+                                        &candidate_path.namespace,
+                                        &candidate_path.name,
+                                    )
+                                    .await?
+                                    .is_some();
+                                if !taken && !imported_names.contains(&candidate) {
+                                    break candidate;
+                                }
+                                suffix += 1;
+                            };
+                            ImportAction::Rename { name }
+                        }
+                    }
+                }
+            };
+
+            info!("import of template {:?}: {:?}", &template.name, &action);
+            if !options.dry_run {
+                match &action {
+                    ImportAction::Create => {
+                        self.write_bundle_template(&tenant, &template.name, &template, None)
+                            .await?
+                    }
+                    ImportAction::Overwrite { .. } => {
+                        self.write_bundle_template(
+                            &tenant,
+                            &template.name,
+                            &template,
+                            existing_template.as_ref(),
+                        )
+                        .await?
+                    }
+                    ImportAction::Rename { name } => {
+                        self.write_bundle_template(&tenant, name, &template, None)
+                            .await?
+                    }
+                    ImportAction::Unchanged | ImportAction::Skip => {}
+                }
+            }
+
+            if let ImportAction::Rename { name } = &action {
+                imported_names.insert(name.clone());
+            }
+            imported_names.insert(template.name.clone());
+            report.changes.push(ImportChange {
+                name: template.name,
+                action,
+            });
+        }
+
+        Ok(report)
+    }
+
+    async fn list_template_locales(
+        &self,
+        tenant: String,
+        request: ListTemplateRequest,
+        namespace: Option<String>,
+    ) -> ServiceResult<Vec<TemplateLocales>> {
+        let namespace = namespace
+            .map(|namespace| parse_namespace(&namespace))
+            .transpose()?;
+        let templates = self
+            .template_repository
+            .list_templates(
+                &tenant,
+                namespace.as_deref(),
+                false,
+                request.offset,
+                request.limit,
+            )
+            .await?;
+        let variant_locales = self
+            .variant_repository
+            .get_variant_locales(
+                &templates
+                    .iter()
+                    .map(|template| template.id)
+                    .collect::<Vec<i64>>(),
+            )
+            .await?;
+
+        Ok(templates
+            .into_iter()
+            .map(|template| {
+                let mut locales = vec![self.locale_settings.default_locale.clone()];
+                variant_locales
+                    .iter()
+                    .filter(|variant| variant.template_id == template.id)
+                    .for_each(|variant| {
+                        if !locales.contains(&variant.locale) {
+                            locales.push(variant.locale.clone());
+                        }
+                    });
+
+                TemplateLocales::new(template.path(), locales, &self.locale_settings)
+            })
+            .collect())
+    }
+
+    async fn list_template_engines(
+        &self,
+        tenant: String,
+        request: ListTemplateRequest,
+        namespace: Option<String>,
+    ) -> ServiceResult<Vec<TemplateEngineListing>> {
+        let namespace = namespace
+            .map(|namespace| parse_namespace(&namespace))
+            .transpose()?;
+        let templates = self
+            .template_repository
+            .list_templates(
+                &tenant,
+                namespace.as_deref(),
+                false,
+                request.offset,
+                request.limit,
+            )
+            .await?;
+
+        templates
+            .into_iter()
+            .map(|template| {
+                Ok(TemplateEngineListing {
+                    engine: parse_engine(&template.engine)?,
+                    content_type: parse_content_type(&template.content_type)?,
+                    name: template.path(),
+                })
+            })
+            .collect()
+    }
+
+    async fn list_messages(
+        &self,
+        tenant: String,
+        offset: i64,
+        limit: i64,
+    ) -> ServiceResult<CatalogMessages> {
+        let messages = self
+            .message_repository
+            .list_messages(&tenant, offset, limit)
+            .await?;
+        let count = self.message_repository.get_messages_count(&tenant).await?;
+
+        Ok(CatalogMessages {
+            messages: messages.into_iter().map(|message| message.into()).collect(),
+            count,
+        })
+    }
+
+    async fn set_message(
+        &self,
+        tenant: String,
+        key: String,
+        locale: String,
+        value: String,
+    ) -> ServiceResult<CatalogMessage> {
+        let locale = parse_locale(&locale)?;
+
+        if key.trim().is_empty() {
+            error!("Cannot create message with an empty key");
+            return Err(ServiceError::BadRequest(
+                "Cannot create message with an empty key".to_string(),
+            ));
+        }
+
+        if let Err(parse_error) = parse_message(&value) {
+            error!("message {:?} is not valid: {:?}", &key, &parse_error);
+            return Err(parse_error);
+        }
+
+        info!("saving {:?} message {:?}", &locale, &key);
+        let message = self
+            .message_repository
+            .set_message(&tenant, &key, &locale, &value)
+            .await?;
+
+        info!("message successfully saved");
+
+        Ok(message.into())
+    }
+
+    async fn remove_message(
+        &self,
+        tenant: String,
+        key: String,
+        locale: String,
+    ) -> ServiceResult<CatalogMessage> {
+        let locale = parse_locale(&locale)?;
+
+        let removed_message = self
+            .message_repository
+            .remove_message(&tenant, &key, &locale)
+            .await?;
+
+        match removed_message {
+            Some(message) => {
+                info!("removed {:?} message {:?}", &locale, &key);
+                Ok(message.into())
+            }
+            None => {
+                error!("message {:?} has no {:?} translation", &key, &locale);
+                Err(ServiceError::NotFound(String::from(
+                    "message does not exist",
+                )))
+            }
+        }
+    }
+
+    async fn get_stylesheet(&self, tenant: String) -> ServiceResult<Stylesheet> {
+        self.stylesheet_repository
+            .get_stylesheet(&tenant)
+            .await?
+            .map(Stylesheet::from)
+            .ok_or_else(|| {
+                error!("tenant {:?} has no stylesheet", &tenant);
+                ServiceError::NotFound(String::from("stylesheet does not exist"))
+            })
+    }
+
+    async fn set_stylesheet(&self, tenant: String, css: String) -> ServiceResult<Stylesheet> {
+        if let Err(validation_error) = validate_stylesheet(&css) {
+            error!("stylesheet is not valid: {:?}", &validation_error);
+            return Err(validation_error);
+        }
+
+        info!("saving stylesheet of tenant {:?}", &tenant);
+        let stylesheet = self
+            .stylesheet_repository
+            .set_stylesheet(&tenant, &css)
+            .await?;
+
+        info!("stylesheet successfully saved");
+
+        Ok(stylesheet.into())
+    }
+
+    async fn remove_stylesheet(&self, tenant: String) -> ServiceResult<Stylesheet> {
+        let removed_stylesheet = self
+            .stylesheet_repository
+            .remove_stylesheet(&tenant)
+            .await?;
+
+        match removed_stylesheet {
+            Some(stylesheet) => {
+                info!("removed stylesheet of tenant {:?}", &tenant);
+                Ok(stylesheet.into())
+            }
+            None => {
+                error!("tenant {:?} has no stylesheet", &tenant);
+                Err(ServiceError::NotFound(String::from(
+                    "stylesheet does not exist",
+                )))
+            }
+        }
+    }
+
+    async fn compose_with_report(
+        &self,
+        tenant: String,
+        name: String,
+        inputs: Vec<InputValue>,
+        options: ComposeOptions,
+    ) -> ServiceResult<ComposedTemplate> {
+        let path = TemplatePath::parse(&name)?;
+        let existing_template = self.resolve_template(&tenant, &path).await?;
+
+        if existing_template.is_none() {
+            error!("template {:?} does not exists", &name);
+            return Err(ServiceError::NotFound(String::from(
+                "template name does not exist",
+            )));
+        }
+
+        let source = existing_template.unwrap();
+        let revision = format_revision(source.updated_at);
+
+        let default_inputs = self
+            .inputs_repository
+            .get_template_inputs(source.id)
+            .await?;
+
+        let template_override = match options.scope {
+            Some(scope) => {
+                let template_override = self
+                    .override_repository
+                    .get_override(source.id, &scope)
+                    .await?;
+                if template_override.is_none() {
+                    info!(
+                        "template {:?} has no override {:?}, composing the base template",
+                        &name, &scope
+                    );
+                }
+                template_override
+            }
+            None => None,
+        };
+
+        let locale = options
+            .locale
+            .map(|locale| parse_locale(&locale))
+            .transpose()?;
+        let locale_chain = self.locale_settings.fallback_chain(
+            locale
+                .as_deref()
+                .unwrap_or(&self.locale_settings.default_locale),
+        );
+
+        let variants = match locale {
+            Some(_) => self.variant_repository.get_variants(source.id).await?,
+            None => Vec::new(),
+        };
+
+        let messages = self
+            .message_repository
+            .get_messages(&[tenant.clone(), GLOBAL_TENANT.to_string()], &locale_chain)
+            .await?;
+
+        let engine = parse_engine(&source.engine)?;
+        let content_type = parse_content_type(&source.content_type)?;
+        if options.layout.is_some() && content_type != ContentType::Markdown {
+            error!("template {:?} is not a Markdown template", &name);
+            return Err(ServiceError::BadRequest(
+                "layouts only apply to Markdown templates".to_string(),
+            ));
+        }
+        let inline = options.inline_css;
+        let stylesheet = if inline {
+            self.resolve_stylesheet(&tenant, content_type, options.output)
+                .await?
+        } else {
+            None
+        };
+
+        let body = select_body(
+            source.body,
+            template_override.as_ref(),
+            &variants,
+            &locale_chain,
+        );
+        let mut data = merge_inputs(default_inputs, template_override.as_ref(), inputs.clone());
+        let settings = self.load_settings(source.id).await?;
+        let sanitized_inputs = sanitize_rich_inputs(&mut data, &settings.rich_html_inputs);
+        for sanitized_input in &sanitized_inputs {
+            info!(
+                "removed {:?} and {:?} from rich HTML input {:?} of template {:?}",
+                &sanitized_input.removed_tags,
+                &sanitized_input.removed_attributes,
+                &sanitized_input.name,
+                &name
+            );
+        }
+        // The layout is given the inputs as they were sanitised for the template.
+        let layout = match options.layout {
+            Some(layout) if options.output != ComposeOutput::Text => {
+                let inputs = inputs
+                    .into_iter()
+                    .map(|input| InputValue {
+                        value: data.get(&input.name).cloned().unwrap_or(input.value),
+                        name: input.name,
+                    })
+                    .collect();
+                Some(
+                    self.resolve_html_template(&tenant, &layout, inputs, "layout")
+                        .await?,
+                )
+            }
+            _ => None,
+        };
+        let unsubscribe = if settings.marketing {
+            Some(self.resolve_unsubscribe(&tenant, &settings, &data).await?)
+        } else {
+            None
+        };
+        let json_schema = match content_type {
+            ContentType::Json => self
+                .schema_repository
+                .get_schema(source.id)
+                .await?
+                .map(|schema| schema.json_schema),
+            _ => None,
+        };
+        let links = if settings.rewrites_links() && renders_html(content_type, options.output) {
+            Some(LinkRewriter {
+                template: name,
+                rules: settings.links,
+                tag_rules: settings.link_tags,
+                click_tracking: self.click_tracking.clone(),
+                unsubscribe_url: unsubscribe
+                    .as_ref()
+                    .map(|unsubscribe| unsubscribe.url.clone()),
+            })
+        } else {
+            None
+        };
+        let unsubscribe_headers = match &unsubscribe {
+            Some(unsubscribe) if options.output != ComposeOutput::Mime => unsubscribe.headers(),
+            _ => vec![],
+        };
+        let limits = self.render_limits;
+        let mut pipeline = ComposePipeline {
+            content_type,
+            locale: locale_chain[0].clone(),
+            messages: resolve_messages(messages, &tenant, &locale_chain),
+            layout,
+            links,
+            unsubscribe,
+            inline_css: inline,
+            stylesheet,
+            limits,
+            deadline: RenderDeadline::new(&limits),
+        };
+        let output = options.output;
+        let mjml_cache = self.mjml_cache.clone();
+
+        render_blocking(limits, move || {
+            let body = match content_type {
+                ContentType::Mjml => mjml_cache.compile(&body)?,
+                _ => body,
+            };
+            pipeline.place_unsubscribe_footer(engine, &body, &data)?;
+            let rendered = render(
+                engine,
+                content_type,
+                &body,
+                &data,
+                TranslateHelper::new(&pipeline.locale, pipeline.messages.clone()),
+                &limits,
+                &pipeline.deadline,
+            )?;
+            if content_type == ContentType::Json {
+                check_json_output(&rendered, json_schema.as_deref())?;
+            }
+
+            pipeline.compose(rendered, output)
+        })
+        .await
+        .map(|output| ComposedTemplate {
+            output,
+            revision,
+            sanitized_inputs,
+            unsubscribe_headers,
         })
     }
 }
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Context};
+use clap::ValueEnum;
+use madtofan_microservice_common::repository::connection_pool::{
+    ServiceConnectionManager, ServiceConnectionPool,
+};
+use tracing::{error, info};
+
+use crate::repository::{
+    filesystem::{
+        FileIdempotencyKeyRepository, FileInputRepository, FileMessageRepository,
+        FileStylesheetRepository, FileTemplateOverrideRepository, FileTemplateRepository,
+        FileTemplateSchemaRepository, FileTemplateSettingsRepository, FileTemplateStore,
+        FileTemplateVariantRepository,
+    },
+    idempotency_key::{DynIdempotencyKeyRepositoryTrait, IdempotencyKeyRepository},
+    input::{DynInputRepositoryTrait, InputRepository},
+    memory::{
+        InMemoryIdempotencyKeyRepository, InMemoryInputRepository, InMemoryMessageRepository,
+        InMemoryStore, InMemoryStylesheetRepository, InMemoryTemplateOverrideRepository,
+        InMemoryTemplateRepository, InMemoryTemplateSchemaRepository,
+        InMemoryTemplateSettingsRepository, InMemoryTemplateVariantRepository,
+    },
+    message::{DynMessageRepositoryTrait, MessageRepository},
+    sqlite::{
+        self, idempotency_key::SqliteIdempotencyKeyRepository, input::SqliteInputRepository,
+        message::SqliteMessageRepository, stylesheet::SqliteStylesheetRepository,
+        template::SqliteTemplateRepository, template_override::SqliteTemplateOverrideRepository,
+        template_schema::SqliteTemplateSchemaRepository,
+        template_settings::SqliteTemplateSettingsRepository,
+        template_variant::SqliteTemplateVariantRepository,
+    },
+    stylesheet::{DynStylesheetRepositoryTrait, StylesheetRepository},
+    template::{DynTemplateRepositoryTrait, TemplateRepository},
+    template_override::{DynTemplateOverrideRepositoryTrait, TemplateOverrideRepository},
+    template_schema::{DynTemplateSchemaRepositoryTrait, TemplateSchemaRepository},
+    template_settings::{DynTemplateSettingsRepositoryTrait, TemplateSettingsRepository},
+    template_variant::{DynTemplateVariantRepositoryTrait, TemplateVariantRepository},
+};
+
+/// Database the repositories live in, `DATABASE_URL` has to point at the same kind.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StorageBackend {
+    #[default]
+    Postgres,
+    /// A single SQLite file, for small deployments that do not run Postgres.
+    Sqlite,
+    /// A directory of template files instead of a database, see
+    /// [`FileTemplateStore`].
+    Filesystem,
+}
+
+/// The storage [`crate::service::templating::TemplatingService`] runs on, every
+/// repository can be replaced by another implementation of its trait.
+pub struct Repositories {
+    pub template_repository: DynTemplateRepositoryTrait,
+    pub inputs_repository: DynInputRepositoryTrait,
+    pub override_repository: DynTemplateOverrideRepositoryTrait,
+    pub variant_repository: DynTemplateVariantRepositoryTrait,
+    pub schema_repository: DynTemplateSchemaRepositoryTrait,
+    pub settings_repository: DynTemplateSettingsRepositoryTrait,
+    pub message_repository: DynMessageRepositoryTrait,
+    pub stylesheet_repository: DynStylesheetRepositoryTrait,
+    pub idempotency_key_repository: DynIdempotencyKeyRepositoryTrait,
+}
+
+impl Repositories {
+    /// Repositories sharing one [`InMemoryStore`], for rendering templates locally
+    /// without a database.
+    pub fn memory(store: Arc<InMemoryStore>) -> Self {
+        Self {
+            template_repository: Arc::new(InMemoryTemplateRepository::new(store.clone()))
+                as DynTemplateRepositoryTrait,
+            inputs_repository: Arc::new(InMemoryInputRepository::new(store.clone()))
+                as DynInputRepositoryTrait,
+            override_repository: Arc::new(InMemoryTemplateOverrideRepository::new(store.clone()))
+                as DynTemplateOverrideRepositoryTrait,
+            variant_repository: Arc::new(InMemoryTemplateVariantRepository::new(store.clone()))
+                as DynTemplateVariantRepositoryTrait,
+            schema_repository: Arc::new(InMemoryTemplateSchemaRepository::new(store.clone()))
+                as DynTemplateSchemaRepositoryTrait,
+            settings_repository: Arc::new(InMemoryTemplateSettingsRepository::new(store.clone()))
+                as DynTemplateSettingsRepositoryTrait,
+            message_repository: Arc::new(InMemoryMessageRepository::new(store.clone()))
+                as DynMessageRepositoryTrait,
+            stylesheet_repository: Arc::new(InMemoryStylesheetRepository::new(store.clone()))
+                as DynStylesheetRepositoryTrait,
+            idempotency_key_repository: Arc::new(InMemoryIdempotencyKeyRepository::new(store))
+                as DynIdempotencyKeyRepositoryTrait,
+        }
+    }
+
+    /// Repositories sharing one [`FileTemplateStore`], for running without a database.
+    pub fn filesystem(store: Arc<FileTemplateStore>) -> Self {
+        Self {
+            template_repository: Arc::new(FileTemplateRepository::new(store.clone()))
+                as DynTemplateRepositoryTrait,
+            inputs_repository: Arc::new(FileInputRepository::new(store.clone()))
+                as DynInputRepositoryTrait,
+            override_repository: Arc::new(FileTemplateOverrideRepository::new(store.clone()))
+                as DynTemplateOverrideRepositoryTrait,
+            variant_repository: Arc::new(FileTemplateVariantRepository::new(store.clone()))
+                as DynTemplateVariantRepositoryTrait,
+            schema_repository: Arc::new(FileTemplateSchemaRepository::new(store.clone()))
+                as DynTemplateSchemaRepositoryTrait,
+            settings_repository: Arc::new(FileTemplateSettingsRepository::new(store.clone()))
+                as DynTemplateSettingsRepositoryTrait,
+            message_repository: Arc::new(FileMessageRepository::new(store.clone()))
+                as DynMessageRepositoryTrait,
+            stylesheet_repository: Arc::new(FileStylesheetRepository::new(store.clone()))
+                as DynStylesheetRepositoryTrait,
+            idempotency_key_repository: Arc::new(FileIdempotencyKeyRepository::new(store))
+                as DynIdempotencyKeyRepositoryTrait,
+        }
+    }
+
+    pub fn postgres(pool: ServiceConnectionPool) -> Self {
+        let inputs_repository =
+            Arc::new(InputRepository::new(pool.clone())) as DynInputRepositoryTrait;
+
+        Self {
+            template_repository: Arc::new(TemplateRepository::new(
+                pool.clone(),
+                inputs_repository.clone(),
+            )) as DynTemplateRepositoryTrait,
+            inputs_repository,
+            override_repository: Arc::new(TemplateOverrideRepository::new(pool.clone()))
+                as DynTemplateOverrideRepositoryTrait,
+            variant_repository: Arc::new(TemplateVariantRepository::new(pool.clone()))
+                as DynTemplateVariantRepositoryTrait,
+            schema_repository: Arc::new(TemplateSchemaRepository::new(pool.clone()))
+                as DynTemplateSchemaRepositoryTrait,
+            settings_repository: Arc::new(TemplateSettingsRepository::new(pool.clone()))
+                as DynTemplateSettingsRepositoryTrait,
+            message_repository: Arc::new(MessageRepository::new(pool.clone()))
+                as DynMessageRepositoryTrait,
+            stylesheet_repository: Arc::new(StylesheetRepository::new(pool.clone()))
+                as DynStylesheetRepositoryTrait,
+            idempotency_key_repository: Arc::new(IdempotencyKeyRepository::new(pool))
+                as DynIdempotencyKeyRepositoryTrait,
+        }
+    }
+
+    pub async fn connect(
+        backend: StorageBackend,
+        database_url: &str,
+        run_migrations: bool,
+    ) -> anyhow::Result<Self> {
+        match backend {
+            StorageBackend::Postgres => {
+                let pg_pool = ServiceConnectionManager::new_pool(database_url)
+                    .await
+                    .context("could not initialize the database connection pool")?;
+
+                if run_migrations {
+                    info!("migrations enabled, running...");
+                    sqlx::migrate!().run(&pg_pool).await.unwrap_or_else(|err| {
+                        error!("There was an error during migration: {:?}", err)
+                    });
+                }
+
+                Ok(Self::postgres(pg_pool))
+            }
+            StorageBackend::Sqlite => {
+                let sqlite_pool = sqlite::new_pool(database_url).await?;
+
+                if run_migrations {
+                    info!("migrations enabled, running...");
+                    sqlx::migrate!("./migrations_sqlite")
+                        .run(&sqlite_pool)
+                        .await
+                        .unwrap_or_else(|err| {
+                            error!("There was an error during migration: {:?}", err)
+                        });
+                }
+
+                let inputs_repository = Arc::new(SqliteInputRepository::new(sqlite_pool.clone()))
+                    as DynInputRepositoryTrait;
+                Ok(Self {
+                    template_repository: Arc::new(SqliteTemplateRepository::new(
+                        sqlite_pool.clone(),
+                        inputs_repository.clone(),
+                    )) as DynTemplateRepositoryTrait,
+                    inputs_repository,
+                    override_repository: Arc::new(SqliteTemplateOverrideRepository::new(
+                        sqlite_pool.clone(),
+                    ))
+                        as DynTemplateOverrideRepositoryTrait,
+                    variant_repository: Arc::new(SqliteTemplateVariantRepository::new(
+                        sqlite_pool.clone(),
+                    )) as DynTemplateVariantRepositoryTrait,
+                    schema_repository: Arc::new(SqliteTemplateSchemaRepository::new(
+                        sqlite_pool.clone(),
+                    )) as DynTemplateSchemaRepositoryTrait,
+                    settings_repository: Arc::new(SqliteTemplateSettingsRepository::new(
+                        sqlite_pool.clone(),
+                    ))
+                        as DynTemplateSettingsRepositoryTrait,
+                    message_repository: Arc::new(SqliteMessageRepository::new(sqlite_pool.clone()))
+                        as DynMessageRepositoryTrait,
+                    stylesheet_repository: Arc::new(SqliteStylesheetRepository::new(
+                        sqlite_pool.clone(),
+                    )) as DynStylesheetRepositoryTrait,
+                    idempotency_key_repository: Arc::new(SqliteIdempotencyKeyRepository::new(
+                        sqlite_pool,
+                    ))
+                        as DynIdempotencyKeyRepositoryTrait,
+                })
+            }
+            StorageBackend::Filesystem => {
+                bail!("the filesystem backend does not connect to a database")
+            }
+        }
+    }
+}